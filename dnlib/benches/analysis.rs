@@ -0,0 +1,64 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use dnlib::prelude::*;
+use std::path::PathBuf;
+
+const NUM_SOLUTIONS: usize = 20;
+const PROJECTS_PER_SOLUTION: usize = 25;
+
+/// Generates a tree of solutions, each with its own directory of SDK-style projects
+/// that reference their predecessor and a couple of packages.
+fn make_file_set() -> (PathsToAnalyze, MemoryFileLoader) {
+    let mut paths = PathsToAnalyze::default();
+    let mut file_loader = MemoryFileLoader::new();
+
+    for s in 0..NUM_SOLUTIONS {
+        let sln_dir = PathBuf::from(format!("/bench/sln{}", s));
+        let mut sln_contents = String::new();
+
+        for p in 0..PROJECTS_PER_SOLUTION {
+            let proj_path = sln_dir.join(format!("Project{}", p)).join(format!("Project{}.csproj", p));
+            sln_contents.push_str(&format!(
+                "Project(\"{{9A19103F-16F7-4668-BE54-9A1E7A4F7556}}\") = \"Project{0}\", \"Project{0}\\Project{0}.csproj\", \"{{{0}}}\"\nEndProject\n",
+                p
+            ));
+
+            let mut proj_contents = String::from(r#"<Project Sdk="Microsoft.NET.Sdk">
+  <PropertyGroup>
+    <TargetFramework>netstandard2.0</TargetFramework>
+  </PropertyGroup>
+  <ItemGroup>
+    <PackageReference Include="Newtonsoft.Json" Version="12.0.1" />
+    <PackageReference Include="Serilog" Version="2.9.0" />
+  </ItemGroup>
+"#);
+            if p > 0 {
+                proj_contents.push_str(&format!(
+                    "  <ItemGroup>\n    <ProjectReference Include=\"..\\Project{0}\\Project{0}.csproj\" />\n  </ItemGroup>\n",
+                    p - 1
+                ));
+            }
+            proj_contents.push_str("</Project>\n");
+
+            file_loader.files.insert(proj_path.clone(), proj_contents);
+            paths.csproj_files.push(proj_path);
+        }
+
+        let sln_path = sln_dir.join(format!("Solution{}.sln", s));
+        file_loader.files.insert(sln_path.clone(), sln_contents);
+        paths.sln_files.push(sln_path);
+    }
+
+    (paths, file_loader)
+}
+
+fn analyze_in_memory(c: &mut Criterion) {
+    let configuration = Configuration::default();
+    let (paths, file_loader) = make_file_set();
+
+    c.bench_function("analyze in-memory file set", |b| {
+        b.iter(|| Analysis::from_paths(&configuration, paths.clone(), file_loader.clone()).unwrap())
+    });
+}
+
+criterion_group!(benches, analyze_in_memory);
+criterion_main!(benches);
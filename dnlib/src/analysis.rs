@@ -1,26 +1,56 @@
-use crate::errors::DnLibResult;
-use crate::git_info::GitInfo;
+use crate::errors::{DnLibError, DnLibResult};
+use crate::git_info::{GitExecutor, GitInfo};
 use crate::enums::*;
-use crate::io::{PathExtensions, PathsToAnalyze, DiskFileLoader, find_files, FileLoader};
-use crate::configuration::Configuration;
+use crate::io::{PathExtensions, PathsToAnalyze, DiskFileLoader, find_files, find_files_parallel, is_file_of_interest, FileLoader, IgnorePatterns};
+use crate::configuration::{Configuration, PackageGroup};
+use crate::property_groups::PropertyGroup;
+use crate::editorconfig::EditorConfigCache;
 
 use logging_timer::{timer, finish};
 use lazy_static::lazy_static;
 use regex::{Regex, RegexBuilder};
 use rayon::prelude::*;
-use log::warn;
+use log::{info, warn};
+use serde::{Serialize, Deserialize};
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
 use std::path::{Path, PathBuf};
 use std::ffi::OsStr;
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::hash::{Hash, Hasher};
+use std::time::{Duration, SystemTime};
 use std::fmt;
 
 /// The set of all files found during analysis.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Analysis {
     pub root_path: PathBuf,
     pub paths_analyzed: PathsToAnalyze,
     pub solution_directories: Vec<SolutionDirectory>,
+
+    /// Projects that could not be associated with any solution, either by being
+    /// mentioned in it or by being in (or under) its directory.
+    pub unassociated_projects: Vec<Project>,
+
+    /// Copied from the configuration; determines how project paths are compared.
+    pub case_sensitive_paths: bool,
+}
+
+/// Runs `op` in a dedicated thread pool of the given size, or in rayon's
+/// global pool if no size is specified.
+fn run_in_thread_pool<OP, R>(num_threads: Option<usize>, op: OP) -> DnLibResult<R>
+where OP: FnOnce() -> R + Send,
+      R: Send
+{
+    match num_threads {
+        Some(n) => {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(n).build()?;
+            Ok(pool.install(op))
+        },
+        None => Ok(op()),
+    }
 }
 
 impl PartialEq for Analysis {
@@ -42,25 +72,119 @@ impl Eq for Analysis { }
 impl Analysis {
     pub fn new(configuration: &Configuration) -> DnLibResult<Self>
     {
-        let pta = find_files(&configuration.input_directory)?;
+        Self::new_with_file_loader(configuration, DiskFileLoader::default())
+    }
+
+    /// As `new`, but the files found by the directory walk are read via `file_loader`,
+    /// e.g. a `RecordingFileLoader` that keeps track of what was read.
+    pub fn new_with_file_loader<L>(configuration: &Configuration, file_loader: L) -> DnLibResult<Self>
+    where L: FileLoader + std::marker::Sync
+    {
+        let progress_interval = match configuration.walk_progress_seconds {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        };
+
+        let pta = if configuration.parallel_walk {
+            find_files_parallel(&configuration.input_directory, &configuration.exclude_files, &configuration.ignore_patterns, progress_interval)?
+        } else {
+            find_files(&configuration.input_directory, &configuration.exclude_files, &configuration.ignore_patterns, progress_interval)?
+        };
+        let mut af = Self::from_paths(configuration, pta, file_loader)?;
+
+        if configuration.input_directory.is_sln_file() {
+            af.retain_single_solution_projects();
+        }
+
+        Ok(af)
+    }
 
+    /// Analyzes a set of paths that has already been found, reading the files via
+    /// `file_loader` rather than from disk. This allows the analysis to be run over
+    /// files held in memory, for example in benchmarks.
+    pub fn from_paths<L>(configuration: &Configuration, paths: PathsToAnalyze, file_loader: L) -> DnLibResult<Self>
+    where L: FileLoader + std::marker::Sync
+    {
         let mut af = Self {
-            root_path: configuration.input_directory.clone(),
-            paths_analyzed: pta,
+            root_path: configuration.scan_directory().to_owned(),
+            paths_analyzed: paths,
             ..Default::default()
         };
 
-        let fs_loader = DiskFileLoader::default();
-        af.analyze(configuration, fs_loader)?;
-
+        af.analyze(configuration, file_loader)?;
         Ok(af)
     }
 
+    /// When analyzing a single sln we still have to walk its directory to find the
+    /// projects, but that will find projects that belong to other solutions (which
+    /// would otherwise be reported as orphans of this one). So we only keep the
+    /// projects that the sln actually needs.
+    fn retain_single_solution_projects(&mut self) {
+        for sln in self.solution_directories.iter_mut().flat_map(|sd| sd.solutions.iter_mut()) {
+            sln.projects.retain(|p| p.ownership != ProjectOwnership::Orphaned);
+        }
+
+        self.unassociated_projects.clear();
+    }
+
+    /// Restricts the analysis to the single solution called `name`, which may be
+    /// given with or without the ".sln" extension and is matched ignoring case. The
+    /// analysis is done before filtering, so references to projects outside the
+    /// solution have already been resolved. It is an error if `name` matches no
+    /// solutions or more than one.
+    pub fn retain_solution(&mut self, name: &str) -> DnLibResult<()> {
+        let is_match = |sln: &Solution| {
+            sln.file_info.path.filename_as_str().eq_ignore_ascii_case(name) ||
+                sln.file_info.path.file_stem_as_str().eq_ignore_ascii_case(name)
+        };
+
+        let matches = self.solution_directories.iter()
+            .flat_map(|sd| sd.solutions.iter())
+            .filter(|sln| is_match(sln))
+            .map(|sln| sln.file_info.path_as_str())
+            .collect::<Vec<_>>();
+
+        match matches.len() {
+            0 => return Err(DnLibError::SolutionFilterError(format!("No solution is called '{}'", name))),
+            1 => {}
+            _ => return Err(DnLibError::SolutionFilterError(
+                format!("More than one solution is called '{}': {}", name, matches.join(", ")))),
+        }
+
+        for sd in &mut self.solution_directories {
+            sd.solutions.retain(|sln| is_match(sln));
+        }
+        self.solution_directories.retain(|sd| !sd.solutions.is_empty());
+        self.unassociated_projects.clear();
+        Ok(())
+    }
+
+    /// Removes the solution directories that match any of `patterns`, along with their
+    /// solutions and projects. The patterns use the same glob syntax as `.dnscanignore`
+    /// and are matched against the directory relative to the `root_path`; an absolute
+    /// path under the `root_path` is made relative first.
+    pub fn exclude_solution_directories<S: AsRef<str>>(&mut self, patterns: &[S]) {
+        let patterns = patterns.iter()
+            .map(|p| {
+                let p = p.as_ref();
+                Path::new(p).strip_prefix(&self.root_path).map_or(p, |rel| rel.as_str()).to_owned()
+            })
+            .collect::<Vec<_>>();
+        let patterns = IgnorePatterns::new(&patterns);
+
+        let root_path = &self.root_path;
+        self.solution_directories.retain(|sd| {
+            let relative_dir = sd.directory.strip_prefix(root_path).unwrap_or(&sd.directory);
+            !patterns.is_ignored(relative_dir)
+        });
+    }
+
     pub fn sort(&mut self) {
         self.solution_directories.sort();
         for sd in &mut self.solution_directories {
             sd.sort();
         }
+        self.unassociated_projects.sort();
     }
 
     pub fn is_empty(&self) -> bool {
@@ -85,19 +209,263 @@ impl Analysis {
             .sum()
     }
 
+    /// Returns every project that was found, whether it is associated with a solution or not.
+    pub fn projects(&self) -> impl Iterator<Item = &Project> {
+        self.solution_directories.iter()
+            .flat_map(|sln_dir| sln_dir.solutions.iter())
+            .flat_map(|sln| sln.projects.iter())
+            .chain(self.unassociated_projects.iter())
+    }
+
+    /// Counts the projects of each output type. Only output types that occur
+    /// at least once are included; the result is ordered by output type.
+    pub fn output_type_histogram(&self) -> Vec<(OutputType, usize)> {
+        let mut counts = BTreeMap::new();
+        for proj in self.projects() {
+            *counts.entry(proj.output_type).or_insert(0) += 1;
+        }
+
+        counts.into_iter().collect()
+    }
+
+    /// Counts the test projects using each test framework. Projects that are not
+    /// test projects are excluded; see `num_non_test_projects`.
+    pub fn test_framework_histogram(&self) -> Vec<(TestFramework, usize)> {
+        let mut counts = BTreeMap::new();
+        for proj in self.projects().filter(|p| p.test_framework != TestFramework::None) {
+            *counts.entry(proj.test_framework).or_insert(0) += 1;
+        }
+
+        counts.into_iter().collect()
+    }
+
+    pub fn num_non_test_projects(&self) -> usize {
+        self.projects().filter(|p| p.test_framework == TestFramework::None).count()
+    }
+
+    pub fn num_projects_allowing_unsafe_code(&self) -> usize {
+        self.projects().filter(|p| p.allows_unsafe_code).count()
+    }
+
+    /// Groups all the distinct packages used by any project by their class (as
+    /// determined by the `package_groups` in the configuration). Each package
+    /// (name and version) appears only once even if many projects use it.
+    pub fn packages_by_class(&self) -> HashMap<String, Vec<&Package>> {
+        let mut result = HashMap::<String, Vec<&Package>>::new();
+        for pkg in self.projects().flat_map(|proj| proj.packages.iter()) {
+            result.entry(pkg.class.clone()).or_default().push(pkg);
+        }
+
+        for packages in result.values_mut() {
+            packages.sort();
+            packages.dedup();
+        }
+
+        result
+    }
+
+    /// Finds root namespaces that are shared by more than one project within the same
+    /// solution. Such projects can cause type-resolution ambiguities when both are
+    /// referenced. A namespace shared in several solutions is reported once per solution.
+    pub fn root_namespace_collisions(&self) -> Vec<(String, Vec<&Project>)> {
+        let mut result = vec![];
+
+        for sln in self.solution_directories.iter().flat_map(|sd| sd.solutions.iter()) {
+            let mut projects_by_ns = BTreeMap::<&str, Vec<&Project>>::new();
+            for proj in &sln.projects {
+                projects_by_ns.entry(proj.effective_root_namespace()).or_default().push(proj);
+            }
+
+            result.extend(projects_by_ns.into_iter()
+                .filter(|(_, projects)| projects.len() > 1)
+                .map(|(ns, projects)| (ns.to_owned(), projects)));
+        }
+
+        result
+    }
+
+    /// Returns the distinct names of the packages that do not match any of the configured
+    /// package groups, and so were given the `default_package_class`.
+    pub fn unclassified_packages(&self, configuration: &Configuration) -> Vec<&str> {
+        self.projects()
+            .flat_map(|proj| proj.packages.iter())
+            .map(|pkg| pkg.name.as_str())
+            .filter(|name| configuration.package_group_for(name).is_none())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Returns each of the configured package groups along with the number of distinct
+    /// package names that it classified. A package is classified by the first group
+    /// that matches it, so later groups can be shadowed by earlier ones.
+    pub fn package_group_usage<'c>(&self, configuration: &'c Configuration) -> Vec<(&'c PackageGroup, usize)> {
+        let mut counts = vec![0; configuration.package_groups.len()];
+
+        let names = self.projects()
+            .flat_map(|proj| proj.packages.iter())
+            .map(|pkg| pkg.name.as_str())
+            .collect::<BTreeSet<_>>();
+
+        for name in names {
+            if let Some(idx) = configuration.package_groups.iter().position(|pkg_group| pkg_group.is_match(name)) {
+                counts[idx] += 1;
+            }
+        }
+
+        configuration.package_groups.iter().zip(counts).collect()
+    }
+
+    /// Returns the names of the configured package groups that did not classify any
+    /// package. These are dead rules that can probably be removed from the configuration.
+    pub fn unmatched_package_groups(&self, configuration: &Configuration) -> Vec<String> {
+        self.package_group_usage(configuration).into_iter()
+            .filter(|(_, count)| *count == 0)
+            .map(|(pkg_group, _)| pkg_group.name.clone())
+            .collect()
+    }
+
+    /// Finds packages that are built from a project in the analysis, i.e. where the
+    /// package name is the assembly name of a project. The project referencing the
+    /// package could probably use a `<ProjectReference>` to the source project instead.
+    /// Returns (referencing project, package, source project) triples.
+    pub fn package_shadowing_projects(&self) -> Vec<(&Project, &Package, &Project)> {
+        let mut projects_by_assembly = HashMap::<String, Vec<&Project>>::new();
+        for proj in self.projects() {
+            projects_by_assembly.entry(proj.effective_assembly_name().to_lowercase()).or_default().push(proj);
+        }
+
+        let mut result = self.projects()
+            .flat_map(|proj| proj.packages.iter().map(move |pkg| (proj, pkg)))
+            .flat_map(|(proj, pkg)| {
+                projects_by_assembly.get(&pkg.name.to_lowercase())
+                    .into_iter()
+                    .flatten()
+                    .filter(move |source| source.file_info.path != proj.file_info.path)
+                    .map(move |source| (proj, pkg, *source))
+            })
+            .collect::<Vec<_>>();
+
+        result.sort();
+        result.dedup();
+        result
+    }
+
+    /// Returns every project path mentioned by a sln file that was not found on disk,
+    /// along with the solution that mentions it.
+    pub fn missing_projects(&self) -> Vec<(&Solution, &PathBuf)> {
+        let found = self.paths_analyzed.csproj_files.iter().cloned().collect::<HashSet<_>>();
+
+        self.solution_directories.iter()
+            .flat_map(|sd| sd.solutions.iter())
+            .flat_map(|sln| sln.missing_mentioned_projects(&found).into_iter().map(move |mp| (sln, mp)))
+            .collect()
+    }
+
+    /// Returns every project path mentioned by a sln file that only matches a project
+    /// found on disk if case is ignored, along with the solution and the on-disk path.
+    /// These build on Windows but break on case-sensitive filesystems such as Linux CI.
+    pub fn case_mismatches(&self) -> Vec<(&Solution, &PathBuf, &PathBuf)> {
+        let found = &self.paths_analyzed.csproj_files;
+
+        self.solution_directories.iter()
+            .flat_map(|sd| sd.solutions.iter())
+            .flat_map(|sln| sln.case_mismatched_projects(found).into_iter().map(move |(mp, f)| (sln, mp, f)))
+            .collect()
+    }
+
+    /// Returns the output directories that are used by more than one project, with
+    /// the projects that use them. Such projects overwrite each other's build outputs.
+    /// Only explicit `output_path`s are considered, resolved against each project's
+    /// directory (see `Project::resolved_output_path`); the comparison ignores case on
+    /// case-insensitive file systems.
+    pub fn shared_output_paths(&self) -> Vec<(String, Vec<&Project>)> {
+        let mut groups = BTreeMap::<String, (String, Vec<&Project>)>::new();
+
+        let projects = self.solution_directories.iter()
+            .flat_map(|sd| sd.solutions.iter())
+            .flat_map(|sln| sln.projects.iter().map(move |proj| (sln.file_info.path.parent(), proj)))
+            .chain(self.unassociated_projects.iter().map(|proj| (None, proj)));
+
+        for (solution_directory, proj) in projects {
+            if let Some(path) = proj.resolved_output_path(solution_directory) {
+                let path = path.as_str().to_owned();
+                let key = if self.case_sensitive_paths { path.clone() } else { path.to_lowercase() };
+                groups.entry(key).or_insert_with(|| (path, vec![])).1.push(proj);
+            }
+        }
+
+        groups.into_values()
+            .filter(|(_, projects)| projects.len() > 1)
+            .collect()
+    }
+
+    /// Returns the solutions whose `coverage_ratio` is below `min_coverage`, i.e. those
+    /// that fail to mention a large proportion of the projects in their directory tree.
+    pub fn low_coverage_solutions(&self, min_coverage: f64) -> Vec<&Solution> {
+        self.solution_directories.iter()
+            .flat_map(|sd| sd.solutions.iter())
+            .filter(|sln| sln.coverage_ratio() < min_coverage)
+            .collect()
+    }
+
+    /// Returns every project found on disk that is not mentioned in any sln file
+    /// anywhere in the scan. This is a superset of the orphaned projects, because
+    /// it also includes projects that could not be associated with a solution at all.
+    pub fn unreferenced_projects(&self) -> Vec<&Project> {
+        let mut projects = self.solution_directories.iter()
+            .flat_map(|sln_dir| sln_dir.solutions.iter())
+            .flat_map(|sln| sln.orphaned_projects().chain(sln.transitively_required_projects()))
+            .chain(self.unassociated_projects.iter())
+            .collect::<Vec<_>>();
+
+        projects.sort();
+        projects
+    }
+
+    /// Returns every project that references nothing: see `Project::is_isolated`.
+    pub fn isolated_projects(&self) -> Vec<&Project> {
+        let mut projects = self.projects()
+            .filter(|proj| proj.is_isolated())
+            .collect::<Vec<_>>();
+
+        projects.sort();
+        projects
+    }
+
+    /// Writes the analysis to `path` as JSON. It can be read back with `load`,
+    /// which allows the outputs to be regenerated without scanning again.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> DnLibResult<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(writer, self)?;
+        Ok(())
+    }
+
+    /// Reads an analysis previously written by `save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> DnLibResult<Self> {
+        let path = path.as_ref();
+        let reader = BufReader::new(File::open(path).map_err(|e| DnLibError::file_read(path, e))?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+
     /// The actual guts of `new`, using a file loader so we can test it.
     fn analyze<L>(&mut self, configuration: &Configuration, file_loader: L) -> DnLibResult<()>
     where L: FileLoader + std::marker::Sync
     {
+        self.case_sensitive_paths = configuration.case_sensitive_paths;
+
         // Load and analyze each solution and place them into folders.
         let tmr = timer!("Load And Analyze Solution files");
-        let solutions = self.paths_analyzed.sln_files.par_iter()
-            .map(|sln_path| {
-                Solution::new(sln_path, &file_loader.clone())
-            }).collect::<Vec<_>>();
-
+        let solutions = run_in_thread_pool(configuration.solution_threads, || {
+            self.paths_analyzed.sln_files.par_iter()
+                .map(|sln_path| {
+                    Solution::new(sln_path, &file_loader.clone(), configuration)
+                }).collect::<Vec<_>>()
+        })?;
+
+        let git_executor = GitExecutor::new(configuration.max_git_operations);
         for sln in solutions {
-            self.add_solution(sln);
+            self.add_solution(sln, &file_loader, &git_executor);
         }
         drop(tmr);
 
@@ -106,31 +474,309 @@ impl Analysis {
         // (This is very hacky. Assumes they are all in the project directory! Can fix by replacing
         // the '==' with a closure). Then analyze the project itself.
         let tmr = timer!("Load And Analyze Project files");
-        let projects = self.paths_analyzed.csproj_files.par_iter()
-            .map(|proj_path| {
-                let other_paths = self.paths_analyzed.other_files.iter()
-                    .filter(|&other_path| other_path.is_same_dir(proj_path))
-                    .cloned()
-                    .collect::<Vec<_>>();
-
-                Project::new(proj_path, other_paths, &file_loader.clone(), configuration)
-            })
-            .collect::<Vec<_>>();
+        let projects = run_in_thread_pool(configuration.project_threads, || {
+            self.paths_analyzed.csproj_files.par_iter()
+                .map(|proj_path| {
+                    let other_paths = self.paths_analyzed.other_files.iter()
+                        .filter(|&other_path| other_path.is_same_dir(proj_path, self.case_sensitive_paths))
+                        .cloned()
+                        .collect::<Vec<_>>();
+
+                    Project::new(proj_path, other_paths, &file_loader.clone(), configuration)
+                })
+                .collect::<Vec<_>>()
+        })?;
 
         for proj in projects {
-            self.add_project(proj);
+            self.add_project(proj, configuration);
         }
 
+        if configuration.follow_external_refs {
+            self.load_external_projects(configuration, &file_loader);
+        }
+
+        self.apply_editorconfigs(configuration, &file_loader);
+
         finish!(tmr, "Found {} linked projects and {} orphaned projects",
             self.num_linked_projects(),
             self.num_orphaned_projects()
             );
 
+        self.mark_transitively_required_projects();
         self.sort();
+
+        for (sln, mentioned, on_disk) in self.case_mismatches() {
+            warn!("Solution {:?} mentions {:?} but the file on disk is {:?}", sln.file_info.path, mentioned, on_disk);
+        }
+
+        for proj in self.obsolete_project_json_projects() {
+            warn!("Project {:?} uses the obsolete project.json format and needs migrating", proj.file_info.path);
+        }
+
         Ok(())
     }
 
-    fn add_solution(&mut self, sln: Solution)
+    /// Loads the projects that are referenced by a solution or by another project, but
+    /// which are outside the scanned directory tree and so were not found by the walk.
+    /// Loading a project can reveal further references, so this repeats until no new
+    /// projects are found. Projects outside the `external_refs_ceiling` are not loaded;
+    /// a relative ceiling is taken to be relative to the scanned directory.
+    fn load_external_projects<L: FileLoader>(&mut self, configuration: &Configuration, file_loader: &L) {
+        let case_sensitive = self.case_sensitive_paths;
+        let key = |path: &Path| if case_sensitive { path.as_str().to_owned() } else { path.as_str().to_lowercase() };
+        let ceiling = configuration.external_refs_ceiling.as_ref()
+            .map(|ceiling| normalize_path(&self.root_path.join(ceiling)));
+
+        loop {
+            let known = self.projects()
+                .map(|proj| key(&proj.file_info.path))
+                .collect::<HashSet<_>>();
+
+            let mut candidates = self.solution_directories.iter()
+                .flat_map(|sd| sd.solutions.iter())
+                .flat_map(|sln| sln.mentioned_projects.iter().cloned())
+                .chain(self.projects().flat_map(|proj| proj.child_project_paths.iter().cloned()))
+                .filter(|path| !known.contains(&key(path)))
+                .filter(|path| match ceiling {
+                    Some(ref ceiling) => path.starts_with(ceiling),
+                    None => true,
+                })
+                .filter(|path| file_loader.read_to_string(path).is_ok())
+                .collect::<Vec<_>>();
+
+            candidates.sort();
+            candidates.dedup();
+            if candidates.is_empty() {
+                break;
+            }
+
+            for path in candidates {
+                info!("Loading external project {:?}", path);
+                let other_files = Self::other_files_alongside(&path, configuration);
+                let mut proj = Project::new(&path, other_files, file_loader, configuration);
+                proj.is_external = true;
+                self.paths_analyzed.csproj_files.push(path);
+                self.add_project(proj, configuration);
+            }
+        }
+    }
+
+    /// The files of interest in the same directory as `project_path`. This is the
+    /// equivalent of what the walk finds for projects inside the scanned tree.
+    fn other_files_alongside(project_path: &Path, configuration: &Configuration) -> Vec<PathBuf> {
+        let dir = match project_path.parent().map(fs::read_dir) {
+            Some(Ok(dir)) => dir,
+            _ => return vec![],
+        };
+
+        let mut result = dir
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && is_file_of_interest(path.filename_as_str(), &configuration.exclude_files))
+            .collect::<Vec<_>>();
+
+        result.sort();
+        result
+    }
+
+    /// Sets the fields of each project that come from the `.editorconfig` files covering
+    /// it. This is done once all the projects are loaded so that the files they share
+    /// are only read once.
+    fn apply_editorconfigs<L: FileLoader>(&mut self, configuration: &Configuration, file_loader: &L) {
+        let mut cache = EditorConfigCache::default();
+        let projects = self.solution_directories.iter_mut()
+            .flat_map(|sd| sd.solutions.iter_mut())
+            .flat_map(|sln| sln.projects.iter_mut())
+            .chain(self.unassociated_projects.iter_mut())
+            .filter(|proj| proj.file_info.is_valid_utf8);
+
+        for proj in projects {
+            if let Some(project_dir) = proj.file_info.path.parent() {
+                let severities = cache.analyzer_rule_severities(project_dir, configuration.scan_directory(), file_loader);
+                proj.covered_by_editorconfig = severities.is_some();
+                proj.analyzer_rule_severities = severities.unwrap_or_default();
+            }
+        }
+    }
+
+    /// Orphaned projects that turn out to be required by their solution (because
+    /// a linked project references them, directly or indirectly) are re-classified
+    /// as `TransitivelyRequired`.
+    fn mark_transitively_required_projects(&mut self) {
+        let required = self.transitively_required_projects().iter()
+            .map(|(sln, proj)| (sln.file_info.path.clone(), proj.file_info.path.clone()))
+            .collect::<HashSet<_>>();
+
+        for sln in self.solution_directories.iter_mut().flat_map(|sd| sd.solutions.iter_mut()) {
+            for proj in &mut sln.projects {
+                if proj.ownership == ProjectOwnership::Orphaned &&
+                    required.contains(&(sln.file_info.path.clone(), proj.file_info.path.clone()))
+                {
+                    proj.ownership = ProjectOwnership::TransitivelyRequired;
+                }
+            }
+        }
+    }
+
+    /// For each distinct package (names are matched ignoring case), finds the highest
+    /// version any project uses and how many projects are on a lower version. This is
+    /// the work remaining to standardize on one version. A project referencing the
+    /// package more than once counts at its highest version; references without a
+    /// version are ignored. The result is ordered by package name.
+    pub fn package_consolidation_report(&self) -> Vec<PackageConsolidation> {
+        let mut versions_by_package = BTreeMap::<String, (&str, Vec<&str>)>::new();
+
+        for proj in self.projects() {
+            let mut highest = HashMap::<String, &Package>::new();
+            for pkg in proj.packages.iter().filter(|pkg| !pkg.version.is_empty()) {
+                highest.entry(pkg.name.to_lowercase())
+                    .and_modify(|best| if compare_package_versions(&pkg.version, &best.version) == Ordering::Greater { *best = pkg })
+                    .or_insert(pkg);
+            }
+
+            for (key, pkg) in highest {
+                versions_by_package.entry(key).or_insert_with(|| (pkg.name.as_str(), vec![])).1.push(pkg.version.as_str());
+            }
+        }
+
+        versions_by_package.into_iter()
+            .map(|(_, (package_name, versions))| {
+                let max_version = versions.iter()
+                    .copied()
+                    .max_by(|a, b| compare_package_versions(a, b))
+                    .unwrap_or_default();
+
+                PackageConsolidation {
+                    package_name,
+                    max_version,
+                    num_projects: versions.len(),
+                    num_laggards: versions.iter().filter(|v| compare_package_versions(v, max_version) == Ordering::Less).count(),
+                }
+            })
+            .collect()
+    }
+
+    /// The projects that still use project.json, ordered by path.
+    pub fn obsolete_project_json_projects(&self) -> Vec<&Project> {
+        let mut result = self.projects()
+            .filter(|proj| proj.uses_obsolete_project_json)
+            .collect::<Vec<_>>();
+
+        result.sort_by(|a, b| a.file_info.path.cmp(&b.file_info.path));
+        result
+    }
+
+    /// Returns every project that references the package called `package_name`
+    /// (matched ignoring case), with the package as the project references it. The
+    /// result is ordered by version and then by project path. Versions that compare
+    /// equal but are written differently, such as "1.0" and "1.0.0", are kept apart.
+    pub fn package_usages(&self, package_name: &str) -> Vec<(&Project, &Package)> {
+        let mut result = self.projects()
+            .flat_map(|proj| proj.packages.iter().map(move |pkg| (proj, pkg)))
+            .filter(|(_, pkg)| pkg.name.eq_ignore_ascii_case(package_name))
+            .collect::<Vec<_>>();
+
+        result.sort_by(|a, b| compare_package_versions(&a.1.version, &b.1.version)
+            .then_with(|| a.1.version.cmp(&b.1.version))
+            .then_with(|| a.0.file_info.path.cmp(&b.0.file_info.path)));
+        result
+    }
+
+    /// Finds packages that a project references directly at a lower version than a
+    /// project it depends on (directly or transitively) requires. NuGet will resolve
+    /// such a package to the lower version, which the child project may not work with.
+    pub fn potential_package_downgrades(&self) -> Vec<Downgrade> {
+        let projects_by_path = self.projects()
+            .map(|proj| (proj.file_info.path.as_path(), proj))
+            .collect::<HashMap<_, _>>();
+
+        let mut seen = HashSet::new();
+        let mut result = vec![];
+
+        for parent in self.projects() {
+            let mut stack = vec![parent];
+            let mut visited = HashSet::new();
+            visited.insert(parent.file_info.path.as_path());
+
+            while let Some(proj) = stack.pop() {
+                for child_path in &proj.child_project_paths {
+                    if let Some(child) = projects_by_path.get(child_path.as_path()) {
+                        if !visited.insert(child.file_info.path.as_path()) {
+                            continue;
+                        }
+                        stack.push(child);
+
+                        for parent_pkg in &parent.packages {
+                            for child_pkg in child.packages.iter().filter(|p| p.name.eq_ignore_ascii_case(&parent_pkg.name)) {
+                                if compare_package_versions(&child_pkg.version, &parent_pkg.version) == Ordering::Greater &&
+                                    seen.insert((&parent.file_info.path, &child.file_info.path, &parent_pkg.name, &child_pkg.version))
+                                {
+                                    result.push(Downgrade {
+                                        parent,
+                                        child,
+                                        package_name: &parent_pkg.name,
+                                        parent_version: &parent_pkg.version,
+                                        child_version: &child_pkg.version,
+                                    });
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        result.sort_by(|a, b| (a.parent, a.package_name, a.child).cmp(&(b.parent, b.package_name, b.child)));
+        result
+    }
+
+    /// Returns every orphaned project along with the solution directory and solution
+    /// it was associated with, in directory order. See `Project::orphan_reason`.
+    pub fn orphaned_projects(&self) -> Vec<(&SolutionDirectory, &Solution, &Project)> {
+        self.solution_directories.iter()
+            .flat_map(|sd| sd.solutions.iter().map(move |sln| (sd, sln)))
+            .flat_map(|(sd, sln)| sln.orphaned_projects().map(move |proj| (sd, sln, proj)))
+            .collect()
+    }
+
+    /// Finds, for each solution, the projects that are reachable via `<ProjectReference>`
+    /// chains from the projects mentioned in the solution, but which are not themselves
+    /// mentioned in it. The referenced projects may be anywhere in the analysis, not
+    /// just in the solution's own directory.
+    pub fn transitively_required_projects(&self) -> Vec<(&Solution, &Project)> {
+        let projects_by_path = self.projects()
+            .map(|proj| (proj.file_info.path.as_path(), proj))
+            .collect::<HashMap<_, _>>();
+
+        let mut result = vec![];
+
+        for sln in self.solution_directories.iter().flat_map(|sd| sd.solutions.iter()) {
+            let mut stack = sln.projects.iter()
+                .filter(|proj| sln.refers_to_project(&proj.file_info.path, self.case_sensitive_paths))
+                .collect::<Vec<_>>();
+            let mut visited = stack.iter()
+                .map(|proj| proj.file_info.path.as_path())
+                .collect::<HashSet<_>>();
+
+            while let Some(proj) = stack.pop() {
+                for child_path in &proj.child_project_paths {
+                    if let Some(child) = projects_by_path.get(child_path.as_path()) {
+                        if visited.insert(child.file_info.path.as_path()) {
+                            if !sln.refers_to_project(&child.file_info.path, self.case_sensitive_paths) {
+                                result.push((sln, *child));
+                            }
+                            stack.push(child);
+                        }
+                    }
+                }
+            }
+        }
+
+        result.sort();
+        result
+    }
+
+    fn add_solution<L: FileLoader>(&mut self, sln: Solution, file_loader: &L, git_executor: &GitExecutor)
     {
         let sln_dir = sln.file_info.path.parent().unwrap();
 
@@ -142,60 +788,95 @@ impl Analysis {
         }
 
         let mut sd = SolutionDirectory::new(sln_dir);
-        sd.get_git_info(&self.root_path);
+        sd.get_git_info(&self.root_path, git_executor);
+        sd.has_ci_config = SolutionDirectory::find_ci_config(&sd.directory, &self.root_path);
+        sd.pinned_sdk_version = SolutionDirectory::find_pinned_sdk_version(&sd.directory, &self.root_path, file_loader);
+        sd.has_editorconfig = SolutionDirectory::find_editorconfig(&sd.directory, &self.root_path, file_loader);
+        sd.has_readme = SolutionDirectory::find_readme(&sd.directory, file_loader);
         sd.solutions.push(sln);
         self.solution_directories.push(sd);
     }
 
-    fn add_project(&mut self, mut project: Project) {
-        if let Some((sln, ownership)) = self.get_solution_that_owns_project(&project.file_info.path) {
+    fn add_project(&mut self, mut project: Project, configuration: &Configuration) {
+        if let Some((sln, ownership, orphan_reason)) = self.get_solution_that_owns_project(&project.file_info.path) {
             project.ownership = ownership;
+            project.orphan_reason = orphan_reason;
             sln.projects.push(project);
         } else {
-            warn!("Could not associate project {:?} with a solution, ignoring.", &project.file_info.path);
+            if !configuration.suppress_orphan_warnings {
+                warn!("Could not associate project {:?} with a solution.", &project.file_info.path);
+            }
+            self.unassociated_projects.push(project);
         }
     }
 
-    fn get_solution_that_owns_project<P>(&mut self, project_path: P) -> Option<(&mut Solution, ProjectOwnership)>
+    fn get_solution_that_owns_project<P>(&mut self, project_path: P) -> Option<(&mut Solution, ProjectOwnership, Option<OrphanReason>)>
     where
         P: AsRef<Path>,
     {
-        let project_path = project_path.as_ref();
-        let parent_dir = project_path.parent().expect("Should always be able to get the parent dir of a project.");
+        let (dir_idx, sln_idx, ownership_type, orphan_reason) = self.find_owner_of_project(project_path.as_ref())?;
+        Some((&mut self.solution_directories[dir_idx].solutions[sln_idx], ownership_type, orphan_reason))
+    }
+
+    /// Returns the solution that owns the project at `project_path`, whether the
+    /// project is linked to it or orphaned in its directory tree, using the same rules
+    /// as the analysis. Returns None for projects that no solution owns.
+    pub fn find_solution_for_project<P>(&self, project_path: P) -> Option<&Solution>
+    where
+        P: AsRef<Path>,
+    {
+        let (dir_idx, sln_idx, _, _) = self.find_owner_of_project(project_path.as_ref())?;
+        Some(&self.solution_directories[dir_idx].solutions[sln_idx])
+    }
+
+    /// Returns the solution directory containing the solution that owns the project
+    /// at `project_path`. See `find_solution_for_project`.
+    pub fn find_solution_directory_for_project<P>(&self, project_path: P) -> Option<&SolutionDirectory>
+    where
+        P: AsRef<Path>,
+    {
+        let (dir_idx, _, _, _) = self.find_owner_of_project(project_path.as_ref())?;
+        Some(&self.solution_directories[dir_idx])
+    }
+
+    /// Finds the indexes of the solution directory and solution that own a project.
+    fn find_owner_of_project(&self, project_path: &Path) -> Option<(usize, usize, ProjectOwnership, Option<OrphanReason>)> {
+        let parent_dir = project_path.parent()?;
+        let case_sensitive = self.case_sensitive_paths;
 
         let mut handles = None;
 
-        'outer: for ownership_type in &[ProjectOwnership::Linked, ProjectOwnership::Orphaned] {
-            for (dir_idx, sln_dir) in self.solution_directories.iter_mut().enumerate() {
-                for (sln_idx, sln) in sln_dir.solutions.iter_mut().enumerate() {
-
-                    match ownership_type {
-                        ProjectOwnership::Linked => if sln.refers_to_project(project_path) {
-                            handles = Some((dir_idx, sln_idx, ownership_type));
-                            break 'outer;
-                        },
-                        ProjectOwnership::Orphaned => if sln.file_info.path.is_same_dir(project_path) ||
-                                                        sln.file_info.path.is_same_dir(parent_dir)
-                        {
-                            handles = Some((dir_idx, sln_idx, ownership_type));
-                            break 'outer;
-                        },
-                        ProjectOwnership::Unknown => unreachable!("There are only 2 ownership types to check.")
+        // A sln in the project's own directory takes precedence over one in the
+        // parent directory, whichever order the solutions were found in.
+        let passes = [
+            (ProjectOwnership::Linked, None),
+            (ProjectOwnership::Orphaned, Some(OrphanReason::SameDirectory)),
+            (ProjectOwnership::Orphaned, Some(OrphanReason::ParentDirectory)),
+        ];
+
+        'outer: for (ownership_type, orphan_reason) in &passes {
+            for (dir_idx, sln_dir) in self.solution_directories.iter().enumerate() {
+                for (sln_idx, sln) in sln_dir.solutions.iter().enumerate() {
+                    let owns = match orphan_reason {
+                        None => sln.refers_to_project(project_path, case_sensitive),
+                        Some(OrphanReason::SameDirectory) => sln.file_info.path.is_same_dir(project_path, case_sensitive),
+                        Some(OrphanReason::ParentDirectory) => sln.file_info.path.is_same_dir(parent_dir, case_sensitive),
+                    };
+
+                    if owns {
+                        handles = Some((dir_idx, sln_idx, *ownership_type, *orphan_reason));
+                        break 'outer;
                     }
                 }
             }
         };
 
-        if let Some((dir_idx, sln_idx, ownership_type)) = handles {
-            Some((&mut self.solution_directories[dir_idx].solutions[sln_idx], *ownership_type))
-        } else {
-            None
-        }
+        handles
     }
 }
 
 
-#[derive(Debug, Default, Eq)]
+#[derive(Debug, Default, Eq, Serialize, Deserialize)]
 /// Represents a directory that contains 1 or more solution files.
 pub struct SolutionDirectory {
     /// The directory path, e.g. `C:\temp\my_solution`.
@@ -206,6 +887,20 @@ pub struct SolutionDirectory {
 
     /// Info about the Git repo, if any.
     pub git_info: Option<GitInfo>,
+
+    /// True if a CI pipeline definition was found in this directory or above it.
+    pub has_ci_config: bool,
+
+    /// The SDK version pinned by the nearest `global.json`, if any.
+    pub pinned_sdk_version: Option<String>,
+
+    /// True if an `.editorconfig` was found in this directory or above it.
+    #[serde(default)]
+    pub has_editorconfig: bool,
+
+    /// True if this directory has a `README.md` or `README.txt`, in any case.
+    #[serde(default)]
+    pub has_readme: bool,
 }
 
 impl PartialEq for SolutionDirectory {
@@ -269,31 +964,157 @@ impl SolutionDirectory {
             .sum()
     }
 
-    fn get_git_info<C>(&mut self, ceiling_dir: C)
+    /// Returns every package used by the projects of all the solutions in this
+    /// directory, along with the number of projects that use it. Packages are
+    /// considered the same if they have the same name and version, and a project
+    /// that is in several solutions is only counted once.
+    pub fn aggregate_packages(&self) -> Vec<(Package, usize)> {
+        let mut seen_projects = HashSet::new();
+        let mut counts: BTreeMap<(&str, &str), (&Package, usize)> = BTreeMap::new();
+
+        for proj in self.solutions.iter().flat_map(|sln| &sln.projects) {
+            if !seen_projects.insert(&proj.file_info.path) {
+                continue;
+            }
+
+            let proj_packages = proj.packages.iter()
+                .map(|pkg| ((pkg.name.as_str(), pkg.version.as_str()), pkg))
+                .collect::<BTreeMap<_, _>>();
+
+            for (key, pkg) in proj_packages {
+                counts.entry(key).or_insert((pkg, 0)).1 += 1;
+            }
+        }
+
+        counts.into_iter()
+            .map(|(_, (pkg, count))| (pkg.clone(), count))
+            .collect()
+    }
+
+    fn get_git_info<C>(&mut self, ceiling_dir: C, git_executor: &GitExecutor)
     where C: AsRef<OsStr>
     {
-        self.git_info = GitInfo::new(&self.directory, ceiling_dir).ok();
+        let directory = &self.directory;
+        self.git_info = git_executor.run(|| GitInfo::new(directory, ceiling_dir)).ok();
     }
-}
 
-#[derive(Debug, Default)]
-/// Represents a sln file and any projects that are associated with it.
-pub struct Solution {
-    pub file_info: FileInfo,
-    pub version: VisualStudioVersion,
-    pub git_info: GitInfo,
+    /// Looks for a CI pipeline definition (`.github/workflows`, `azure-pipelines.yml`
+    /// or `.gitlab-ci.yml`) in `directory` and its parents. The search stops at the
+    /// root of the git repository, or at the `ceiling_dir` if that comes first.
+    fn find_ci_config(directory: &Path, ceiling_dir: &Path) -> bool {
+        const CI_CONFIG_FILES: [&str; 3] = [".github/workflows", "azure-pipelines.yml", ".gitlab-ci.yml"];
 
-    // The set of projects that we found during the disk walk and have loaded and
-    // associated with this solution (either by explicit linkage because they are
-    // mentioned in the .sln file, or by assumed-orphanship because they are in
-    // the same directory, but no longer in the solution).
-    pub projects: Vec<Project>,
+        for dir in directory.ancestors() {
+            if CI_CONFIG_FILES.iter().any(|f| dir.join(f).exists()) {
+                return true;
+            }
 
-    /// The set of projects that is mentioned inside the sln file.
-    /// This is populated by reading the solution file and normalizing
-    /// the extracted paths.
-    mentioned_projects: Vec<PathBuf>
-}
+            if dir == ceiling_dir || dir.join(".git").exists() {
+                break;
+            }
+        }
+
+        false
+    }
+
+    /// Finds the nearest `global.json` in `directory` or its parents (stopping at the
+    /// same place as `find_ci_config`) and returns the SDK version that it pins.
+    /// The dotnet CLI only looks at the nearest file, so we do the same.
+    fn find_pinned_sdk_version<L: FileLoader>(directory: &Path, ceiling_dir: &Path, file_loader: &L) -> Option<String> {
+        for dir in directory.ancestors() {
+            if let Ok(contents) = file_loader.read_to_string(&dir.join("global.json")) {
+                return Self::extract_sdk_version(&contents);
+            }
+
+            if dir == ceiling_dir || dir.join(".git").exists() {
+                break;
+            }
+        }
+
+        None
+    }
+
+    /// Looks for an `.editorconfig` in `directory` and its parents, stopping at the
+    /// same place as `find_ci_config`. Analyzer severities are often configured in
+    /// one at the root of the repository.
+    fn find_editorconfig<L: FileLoader>(directory: &Path, ceiling_dir: &Path, file_loader: &L) -> bool {
+        for dir in directory.ancestors() {
+            if file_loader.read_to_string(&dir.join(".editorconfig")).is_ok() {
+                return true;
+            }
+
+            if dir == ceiling_dir || dir.join(".git").exists() {
+                break;
+            }
+        }
+
+        false
+    }
+
+    /// Looks for a readme in `directory` only. Unlike the files above, a readme in
+    /// a parent directory does not document the solutions in this one.
+    fn find_readme<L: FileLoader>(directory: &Path, file_loader: &L) -> bool {
+        file_loader.file_names(directory).iter()
+            .any(|name| unicase::eq_ascii(name.as_str(), "README.md") || unicase::eq_ascii(name.as_str(), "README.txt"))
+    }
+
+    /// global.json may contain comments, which serde_json rejects, so use a regex.
+    fn extract_sdk_version(global_json_contents: &str) -> Option<String> {
+        lazy_static! {
+            static ref SDK_VERSION_REGEX: Regex = RegexBuilder::new(r#""sdk"\s*:\s*\{[^}]*?"version"\s*:\s*"(?P<version>[^"]*)""#)
+                .case_insensitive(true).build().unwrap();
+        }
+
+        SDK_VERSION_REGEX.captures(global_json_contents)
+            .map(|cap| cap["version"].trim().to_owned())
+    }
+}
+
+/// True if the assembly is part of the .NET Framework, i.e. one of the references
+/// that Visual Studio adds to a new project. The name may be a full strong name.
+fn is_framework_assembly(assembly: &str) -> bool {
+    const FRAMEWORK_ASSEMBLIES: [&str; 7] = [
+        "System", "mscorlib", "Microsoft.CSharp", "Microsoft.VisualBasic",
+        "WindowsBase", "PresentationCore", "PresentationFramework",
+    ];
+
+    let name = assembly.split(',').next().unwrap_or_default().trim();
+    name.starts_with("System.") || FRAMEWORK_ASSEMBLIES.iter().any(|fa| fa.eq_ignore_ascii_case(name))
+}
+
+/// Converts an old-style target framework such as "v4.6.2" to the short form used in
+/// packages.config and SDK-style projects, e.g. "net462". Other forms are lowercased.
+fn short_framework_name(target_framework: &str) -> String {
+    let tf = target_framework.trim().to_lowercase();
+    match tf.strip_prefix('v') {
+        Some(version) if version.starts_with(|c: char| c.is_ascii_digit()) => format!("net{}", version.replace('.', "")),
+        _ => tf,
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+/// Represents a sln file and any projects that are associated with it.
+pub struct Solution {
+    pub file_info: FileInfo,
+    pub version: VisualStudioVersion,
+    /// The MinimumVisualStudioVersion declared in the sln file, e.g. "10.0.40219.1".
+    pub minimum_version: Option<String>,
+    pub git_info: GitInfo,
+    /// The domain that the solution belongs to, as determined by the configured
+    /// `solution_classifiers`.
+    domain: String,
+
+    // The set of projects that we found during the disk walk and have loaded and
+    // associated with this solution (either by explicit linkage because they are
+    // mentioned in the .sln file, or by assumed-orphanship because they are in
+    // the same directory, but no longer in the solution).
+    pub projects: Vec<Project>,
+
+    /// The set of projects that is mentioned inside the sln file.
+    /// This is populated by reading the solution file and normalizing
+    /// the extracted paths.
+    mentioned_projects: Vec<PathBuf>
+}
 
 impl PartialEq for Solution {
     #[inline]
@@ -371,24 +1192,33 @@ fn normalize_path(path: &Path) -> PathBuf {
 }
 
 impl Solution {
-    pub fn new<P, L>(path: P, file_loader: &L) -> Self
+    pub fn new<P, L>(path: P, file_loader: &L, configuration: &Configuration) -> Self
     where
         P: AsRef<Path>,
         L: FileLoader,
     {
         let fi = FileInfo::new(path.as_ref(), file_loader);
+        let domain = configuration.solution_domain_for(fi.filename_as_str()).to_owned();
         let ver = VisualStudioVersion::extract(&fi.contents).unwrap_or_default();
         let sln_dir = fi.path.parent().unwrap().to_owned();
         let mp = Self::extract_mentioned_projects(sln_dir, &fi.contents);
+        let min_ver = Self::extract_minimum_version(&fi.contents);
 
         Solution {
             file_info: fi,
             version: ver,
+            minimum_version: min_ver,
+            domain,
             mentioned_projects: mp,
             ..Default::default()
         }
     }
 
+    /// The domain that the solution belongs to, e.g. "Billing" for "Billing.Api.sln".
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
     fn sort(&mut self) {
         self.projects.sort();
     }
@@ -401,24 +1231,154 @@ impl Solution {
         self.projects.iter().filter(|p| p.ownership == ProjectOwnership::Orphaned)
     }
 
-    /// Extracts the projects from the contents of the solution file. Note that there is
-    /// a potential problem here, in that the paths constructed will be in the format
-    /// of the system that the solution was created on (e.g. Windows) and not the
-    /// format of the system the program is running on (e.g. Linux).
-    /// See also `refers_to_project` where this surfaces.
-    fn extract_mentioned_projects(sln_dir: PathBuf, contents: &str) -> Vec<PathBuf> {
+    pub fn transitively_required_projects(&self) -> impl Iterator<Item = &Project> {
+        self.projects.iter().filter(|p| p.ownership == ProjectOwnership::TransitivelyRequired)
+    }
+
+    /// Returns the projects mentioned in the sln file that do not exist in `found`, the
+    /// set of csproj files discovered by the directory walk. This typically happens when
+    /// a project is deleted without updating the solution. Slns are usually written on
+    /// Windows, so the comparison ignores case.
+    pub fn missing_mentioned_projects(&self, found: &HashSet<PathBuf>) -> Vec<&PathBuf> {
+        self.mentioned_projects.iter()
+            .filter(|mp| !found.contains(*mp) && !found.iter().any(|f| f.eq_ignoring_case(mp)))
+            .collect()
+    }
+
+    /// Returns the projects mentioned in the sln file whose exact-case path does not exist
+    /// in `found` but which match one of them when case is ignored, paired with that
+    /// on-disk path. Mentioned paths keep the case they were written with in the sln.
+    pub fn case_mismatched_projects<'a>(&'a self, found: &'a [PathBuf]) -> Vec<(&'a PathBuf, &'a PathBuf)> {
+        self.mentioned_projects.iter()
+            .filter(|mp| !found.contains(*mp))
+            .filter_map(|mp| found.iter().find(|f| f.eq_ignoring_case(mp)).map(|f| (mp, f)))
+            .collect()
+    }
+
+    /// The fraction of the projects associated with the solution (those in its
+    /// directory tree) that it actually mentions, i.e. linked / (linked + orphaned).
+    /// A solution with no associated projects is considered fully covered.
+    pub fn coverage_ratio(&self) -> f64 {
+        let linked = self.linked_projects().count();
+        let orphaned = self.orphaned_projects().count();
+        if linked + orphaned == 0 {
+            1.0
+        } else {
+            linked as f64 / (linked + orphaned) as f64
+        }
+    }
+
+    /// The fraction of the solution's projects that are SDK-style, as a measure of how
+    /// far it has got in migrating from old-style projects. A solution with no
+    /// projects is considered fully migrated.
+    pub fn migration_progress(&self) -> f64 {
+        let sdk_style = self.projects.iter()
+            .filter(|proj| match proj.version {
+                ProjectVersion::MicrosoftNetSdk | ProjectVersion::MicrosoftNetSdkWeb => true,
+                ProjectVersion::OldStyle | ProjectVersion::Unknown => false,
+            })
+            .count();
+
+        if self.projects.is_empty() {
+            1.0
+        } else {
+            sdk_style as f64 / self.projects.len() as f64
+        }
+    }
+
+    /// True if the solution has a mixture of SDK-style and other projects.
+    pub fn partially_migrated(&self) -> bool {
+        let progress = self.migration_progress();
+        progress > 0.0 && progress < 1.0
+    }
+
+    /// The percentage (0 to 100) of the distinct packages used by the solution's
+    /// projects that are in each package class, e.g. "Microsoft" => 60.0. Packages
+    /// are distinct by name, ignoring case and version. A solution that uses no
+    /// packages has no classes.
+    pub fn package_class_breakdown(&self) -> HashMap<String, f64> {
+        let distinct_packages = self.projects.iter()
+            .flat_map(|proj| proj.packages.iter())
+            .map(|pkg| (pkg.name.to_lowercase(), pkg.class.as_str()))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for class in distinct_packages.values() {
+            *counts.entry(class.to_string()).or_insert(0) += 1;
+        }
+
+        counts.into_iter()
+            .map(|(class, count)| (class, count as f64 * 100.0 / distinct_packages.len() as f64))
+            .collect()
+    }
+
+    /// The distinct LangVersions used by the projects in the solution. Projects
+    /// that do not specify a LangVersion contribute `None`.
+    pub fn distinct_lang_versions(&self) -> Vec<Option<&str>> {
+        self.projects.iter()
+            .map(|p| p.lang_version.as_deref())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    pub fn lang_version_consistent(&self) -> bool {
+        self.distinct_lang_versions().len() <= 1
+    }
+
+    /// True if the sln file declares a Visual Studio version that predates SDK-style
+    /// projects (which arrived in VS2017) but the solution contains some anyway.
+    pub fn version_project_mismatch(&self) -> bool {
+        self.version == VisualStudioVersion::VS2015 &&
+            self.linked_projects().any(|proj| match proj.version {
+                ProjectVersion::MicrosoftNetSdk | ProjectVersion::MicrosoftNetSdkWeb => true,
+                ProjectVersion::OldStyle | ProjectVersion::Unknown => false,
+            })
+    }
+
+    fn extract_minimum_version(contents: &str) -> Option<String> {
+        lazy_static! {
+            static ref MINIMUM_VERSION_RE: Regex = Regex::new(r#"(?m)^\s*MinimumVisualStudioVersion\s*=\s*(?P<ver>\S+)"#).unwrap();
+        }
+
+        MINIMUM_VERSION_RE.captures(contents)
+            .map(|cap| cap["ver"].to_owned())
+    }
+
+    /// Returns the byte offset in the sln file of the first mention of the project,
+    /// or None if the solution does not mention it.
+    pub fn mention_offset<P: AsRef<Path>>(&self, project_path: P) -> Option<usize> {
+        let sln_dir = self.file_info.path.parent()?;
+        Self::mentions(sln_dir, &self.file_info.contents)
+            .find(|(_, path)| path == project_path.as_ref())
+            .map(|(offset, _)| offset)
+    }
+
+    /// Every project path in the sln file, normalized relative to the sln's directory,
+    /// along with the byte offset at which it appears.
+    fn mentions<'a>(sln_dir: &'a Path, contents: &'a str) -> impl Iterator<Item = (usize, PathBuf)> + 'a {
         lazy_static! {
             static ref PROJECT_RE: Regex = RegexBuilder::new(r#""(?P<projpath>[^"]+csproj)"#)
                 .case_insensitive(true).build().unwrap();
         }
 
-        let mut project_paths = PROJECT_RE.captures_iter(contents)
-            .map(|cap| {
-                let mut path = sln_dir.clone();
-                let x = norm_mentioned_path(&cap["projpath"]);
-                path.push(x);
-                path
+        PROJECT_RE.captures_iter(contents)
+            .map(move |cap| {
+                let projpath = cap.name("projpath").unwrap();
+                let mut path = sln_dir.to_owned();
+                path.push(norm_mentioned_path(projpath.as_str()));
+                (projpath.start(), normalize_path(&path))
             })
+    }
+
+    /// Extracts the projects from the contents of the solution file. Note that there is
+    /// a potential problem here, in that the paths constructed will be in the format
+    /// of the system that the solution was created on (e.g. Windows) and not the
+    /// format of the system the program is running on (e.g. Linux).
+    /// See also `refers_to_project` where this surfaces.
+    fn extract_mentioned_projects(sln_dir: PathBuf, contents: &str) -> Vec<PathBuf> {
+        let mut project_paths = Self::mentions(&sln_dir, contents)
+            .map(|(_, path)| path)
             .collect::<Vec<_>>();
 
         project_paths.sort();
@@ -426,18 +1386,23 @@ impl Solution {
         project_paths
     }
 
-    fn refers_to_project<P: AsRef<Path>>(&self, project_path: P) -> bool {
+    fn refers_to_project<P: AsRef<Path>>(&self, project_path: P, case_sensitive: bool) -> bool {
         let project_path = project_path.as_ref();
-        self.mentioned_projects.iter().any(|mp| mp.eq_ignoring_case(project_path))
+        self.mentioned_projects.iter().any(|mp| mp.eq_path(project_path, case_sensitive))
     }
 }
 
-#[derive(Debug, Default, Clone, Eq)]
+#[derive(Debug, Default, Clone, Eq, Serialize, Deserialize)]
 /// Represents information about a .sln or .csproj file.
 pub struct FileInfo {
     pub path: PathBuf,
     pub contents: String,
     pub is_valid_utf8: bool,
+    /// When the file was last modified. Only known for files loaded from disk.
+    #[serde(default)]
+    pub modified: Option<SystemTime>,
+    #[serde(default)]
+    pub line_ending: LineEnding,
 }
 
 impl FileInfo {
@@ -450,9 +1415,19 @@ impl FileInfo {
         let file_contents_result = file_loader.read_to_string(&fi.path);
         fi.is_valid_utf8 = file_contents_result.is_ok();
         fi.contents = file_contents_result.unwrap_or_default();
+        fi.line_ending = LineEnding::extract(&fi.contents);
+        fi.modified = file_loader.modified(&fi.path);
         fi
     }
 
+    /// Returns the modification time in ISO-8601 format, e.g. "2020-09-13T12:26:40Z",
+    /// or "" if it is not known.
+    pub fn modified_as_string(&self) -> String {
+        self.modified
+            .map(|m| DateTime::<Utc>::from(m).to_rfc3339_opts(SecondsFormat::Secs, true))
+            .unwrap_or_default()
+    }
+
     /// Returns the whole path as a str, or "" if it cannot be converted.
     pub fn path_as_str(&self) -> &str {
         self.path.as_str()
@@ -501,30 +1476,126 @@ impl Ord for FileInfo {
 
 
 /// The results of analyzing a project file.
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct Project {
     pub file_info: FileInfo,
     pub ownership: ProjectOwnership,
+    /// How the project came to be associated with its solution, if it is orphaned
+    /// (or was orphaned before being found to be transitively required).
+    #[serde(default)]
+    pub orphan_reason: Option<OrphanReason>,
     pub other_files: Vec<PathBuf>,
     pub version: ProjectVersion,
     pub output_type: OutputType,
+    pub root_namespace: Option<String>,
+    pub assembly_name: Option<String>,
+    /// The `<ProjectGuid>`, without the braces and lowercased. Old-style projects
+    /// always have one; SDK-style projects usually do not.
+    #[serde(default)]
+    pub project_guid: Option<String>,
+    /// The `<Company>` package metadata, exactly as written in the project file.
+    #[serde(default)]
+    pub company: Option<String>,
+    /// The `<Authors>` package metadata, exactly as written in the project file.
+    #[serde(default)]
+    pub authors: Option<String>,
+    /// The `<Copyright>` package metadata, exactly as written in the project file.
+    #[serde(default)]
+    pub copyright: Option<String>,
+    /// The `<UserSecretsId>`, if the project reads user secrets during development.
+    #[serde(default)]
+    pub user_secrets_id: Option<String>,
     pub xml_doc: XmlDoc,
     pub tt_file: bool,
     pub embedded_debugging: bool,
+    pub debug_type: Option<String>,
+    pub produces_portable_pdb: bool,
     pub linked_solution_info: bool,
     pub auto_generate_binding_redirects: bool,
+    pub platform_target: Option<String>,
+    /// The `<OutputPath>`, or failing that the `<BaseOutputPath>`, as written in the
+    /// project file. None if the project uses the default.
+    #[serde(default)]
+    pub output_path: Option<String>,
+    pub lang_version: Option<String>,
+    pub prefer_32bit: bool,
+    /// The value of `<Deterministic>`, or None if the project does not set it. SDK-style
+    /// projects are deterministic by default, old-style ones are not, so None does not
+    /// mean the same thing for both.
+    #[serde(default)]
+    pub deterministic: Option<bool>,
+    /// True if the project sets `<AllowUnsafeBlocks>` to true, allowing `unsafe` code.
+    #[serde(default)]
+    pub allows_unsafe_code: bool,
+    /// True if there is an `.editorconfig` in the project's directory or above it,
+    /// up to the root of the git repository or the scan directory. Set by the analysis.
+    #[serde(default)]
+    pub covered_by_editorconfig: bool,
+    /// The analyzer rule severities set for C# files by the `.editorconfig` files
+    /// covering the project, e.g. "CA1822" => "error". Set by the analysis, see
+    /// `EditorConfigCache::analyzer_rule_severities`.
+    #[serde(default)]
+    pub analyzer_rule_severities: BTreeMap<String, String>,
+    /// The analyzer rules, such as CA2000 or IDE0005, that the project turns off with
+    /// `<NoWarn>`. Compiler warnings such as CS0168 are not included.
+    #[serde(default)]
+    pub suppressed_analyzer_rules: Vec<String>,
+    pub define_constants: Vec<String>,
     pub referenced_assemblies: Vec<String>,
+    /// The `<HintPath>`s of assembly references that point into a `packages` or `bin`
+    /// folder, or are absolute. Such references break as soon as the packages are
+    /// restored elsewhere or the other project is not built first.
+    pub fragile_reference_hint_paths: Vec<String>,
     pub target_frameworks: Vec<String>,
+    /// True if a target framework is given by a property, e.g. `$(DefaultTargetFramework)`,
+    /// rather than literally. `target_frameworks` holds the text as written.
+    #[serde(default)]
+    pub framework_is_variable: bool,
+    /// The target frameworks with the property references replaced, as far as they can
+    /// be, by properties set in the project or its Directory.Build.props. The same as
+    /// `target_frameworks` when no properties are used.
+    #[serde(default)]
+    pub resolved_target_frameworks: Vec<String>,
+    pub imported_targets: Vec<String>,
+    /// True if the project still imports .props or .targets files from a `packages`
+    /// folder, which is a leftover from packages.config that breaks restore once the
+    /// project has been migrated to PackageReference.
+    pub has_packages_config_imports: bool,
+    /// True if the project's package versions are managed centrally. This is the case
+    /// when the nearest Directory.Packages.props turns ManagePackageVersionsCentrally on,
+    /// or the project turns it on itself.
+    pub central_package_management: bool,
+    /// True if central package management is turned on by Directory.Packages.props, but
+    /// the project turns it off again and so still pins its package versions inline.
+    pub central_package_management_optout: bool,
+    pub has_wildcard_includes: bool,
+    /// True if the project is outside the scanned directory tree, and was only loaded
+    /// because a solution or project references it (see `follow_external_refs`).
+    pub is_external: bool,
     pub web_config: FileStatus,
     pub app_config: FileStatus,
+    /// The number of `<bindingRedirect>` elements in the app.config on disk, or 0 if
+    /// there is none. A large number is a sign of assembly version conflicts.
+    #[serde(default)]
+    pub binding_redirect_count: usize,
     pub app_settings_json: FileStatus,
     pub package_json: FileStatus,
     pub packages_config: FileStatus,
     pub project_json: FileStatus,
+    /// True if there is a project.json on disk next to the project. That format was
+    /// abandoned with .NET Core 1.x tooling, so the project needs migrating.
+    #[serde(default)]
+    pub uses_obsolete_project_json: bool,
+    pub has_global_usings: bool,
 
     pub packages: Vec<Package>,
+    pub intra_project_package_conflicts: Vec<(String, Vec<String>)>,
     pub test_framework: TestFramework,
+    /// True if this is an SDK-style test project that is missing the test SDK or
+    /// the adapter for its framework, so `dotnet test` will not run its tests.
+    pub test_project_misconfigured: bool,
     pub uses_specflow: bool,
+    pub uses_source_link: bool,
 
     // This is a collection of the normalized 'foo.csproj' paths as extracted from this csproj file.
     // We call these 'child projects'.
@@ -584,26 +1655,71 @@ impl Project {
 
         proj.version = ProjectVersion::extract(&proj.file_info.contents).unwrap_or_default();
         proj.output_type = OutputType::extract(&proj.file_info.contents);
+        proj.root_namespace = proj.extract_root_namespace();
+        proj.assembly_name = proj.extract_assembly_name();
+        proj.project_guid = proj.extract_project_guid();
         proj.xml_doc = XmlDoc::extract(&proj.file_info.contents);
         proj.tt_file = proj.extract_tt_file();
+        proj.debug_type = proj.extract_debug_type();
         proj.embedded_debugging = proj.extract_embedded_debugging();
+        proj.produces_portable_pdb = proj.extract_produces_portable_pdb();
         proj.linked_solution_info = proj.extract_linked_solution_info();
         proj.auto_generate_binding_redirects = proj.extract_auto_generate_binding_redirects();
+        proj.platform_target = proj.extract_platform_target();
+        proj.output_path = proj.extract_output_path();
+        proj.prefer_32bit = proj.extract_prefer_32bit();
+        proj.deterministic = proj.extract_deterministic();
+        proj.allows_unsafe_code = proj.extract_allows_unsafe_code();
+        proj.lang_version = proj.extract_lang_version();
+        proj.company = proj.extract_package_metadata("Company");
+        proj.authors = proj.extract_package_metadata("Authors");
+        proj.copyright = proj.extract_package_metadata("Copyright");
+        proj.user_secrets_id = proj.extract_user_secrets_id();
+        proj.define_constants = proj.extract_define_constants();
+        proj.suppressed_analyzer_rules = proj.extract_suppressed_analyzer_rules();
         proj.referenced_assemblies = proj.extract_referenced_assemblies();
+        proj.fragile_reference_hint_paths = proj.extract_fragile_reference_hint_paths();
         proj.target_frameworks = proj.extract_target_frameworks();
+        proj.framework_is_variable = proj.target_frameworks.iter().any(|tf| tf.contains("$("));
+        proj.resolved_target_frameworks = proj.resolve_target_frameworks(file_loader);
+        proj.imported_targets = proj.extract_imported_targets();
+        proj.has_packages_config_imports = proj.extract_has_packages_config_imports();
+        let cpm_enabled_by_props = Self::directory_packages_props_enables_cpm(&proj.file_info.path, file_loader);
+        let cpm_setting = Self::extract_manage_package_versions_centrally(&proj.file_info.contents);
+        proj.central_package_management = cpm_setting.unwrap_or(cpm_enabled_by_props);
+        proj.central_package_management_optout = cpm_enabled_by_props && cpm_setting == Some(false);
+        if proj.has_packages_config_imports {
+            warn!("Project {:?} imports build files from a packages folder", proj.file_info.path);
+        }
+        proj.has_wildcard_includes = proj.extract_has_wildcard_includes();
         proj.web_config = proj.has_file_of_interest(InterestingFile::WebConfig);
         proj.app_config = proj.has_file_of_interest(InterestingFile::AppConfig);
+        proj.binding_redirect_count = proj.extract_binding_redirect_count(file_loader);
         proj.app_settings_json = proj.has_file_of_interest(InterestingFile::AppSettingsJson);
         proj.package_json = proj.has_file_of_interest(InterestingFile::PackageJson);
         proj.packages_config = proj.has_file_of_interest(InterestingFile::PackagesConfig);
         proj.project_json = proj.has_file_of_interest(InterestingFile::ProjectJson);
+        proj.uses_obsolete_project_json = proj.project_json == FileStatus::OnDiskOnly
+            || proj.project_json == FileStatus::InProjectFileAndOnDisk;
+        proj.has_global_usings = proj.extract_has_global_usings();
         proj.child_project_paths = proj.extract_project_paths();
 
         // The things after here are dependent on having first determined the packages
         // that the project uses.
         proj.packages = proj.extract_packages(file_loader, configuration);
+        proj.intra_project_package_conflicts = proj.extract_intra_project_package_conflicts();
+        for (name, versions) in &proj.intra_project_package_conflicts {
+            warn!("Project {:?} references package {} at multiple versions: {}",
+                proj.file_info.path, name, versions.join(", "));
+        }
         proj.test_framework = proj.extract_test_framework();
+        proj.test_project_misconfigured = proj.extract_test_project_misconfigured();
+        if proj.test_project_misconfigured {
+            warn!("Project {:?} uses {} but is missing Microsoft.NET.Test.Sdk or a test adapter package",
+                proj.file_info.path, proj.test_framework.as_ref());
+        }
         proj.uses_specflow = proj.extract_uses_specflow();
+        proj.uses_source_link = proj.extract_uses_source_link();
 
         proj
     }
@@ -626,12 +1742,209 @@ impl Project {
             .collect()
     }
 
+    /// Finds all the projects in the solution that this project depends on, directly
+    /// or via other projects. The project itself is never included, even if there is
+    /// a cycle.
+    pub fn get_transitive_child_projects<'s>(&self, sln: &'s Solution) -> Vec<&'s Project> {
+        self.traverse(|proj| proj.get_child_projects(sln))
+    }
+
+    /// Finds all the projects in the solution that depend on this project, directly
+    /// or via other projects.
+    pub fn get_transitive_parent_projects<'s>(&self, sln: &'s Solution) -> Vec<&'s Project> {
+        self.traverse(|proj| proj.get_parent_projects(sln))
+    }
+
+    fn traverse<'s, F>(&self, next: F) -> Vec<&'s Project>
+    where F: Fn(&Project) -> Vec<&'s Project>
+    {
+        let mut visited = HashSet::new();
+        visited.insert(&self.file_info.path);
+        let mut result = vec![];
+        let mut stack = next(self);
+
+        while let Some(proj) = stack.pop() {
+            if visited.insert(&proj.file_info.path) {
+                result.push(proj);
+                stack.extend(next(proj));
+            }
+        }
+
+        result.sort();
+        result
+    }
+
+    /// Classifies the project according to whether it has parents and/or
+    /// children within the solution.
+    pub fn graph_role(&self, sln: &Solution) -> GraphRole {
+        let has_parents = !self.get_parent_projects(sln).is_empty();
+        let has_children = !self.get_child_projects(sln).is_empty();
+
+        match (has_parents, has_children) {
+            (false, true) => GraphRole::Root,
+            (true, false) => GraphRole::Leaf,
+            (false, false) => GraphRole::Isolated,
+            (true, true) => GraphRole::Internal,
+        }
+    }
+
+    /// The architectural component that the project belongs to, which is the first
+    /// directory under the solution's directory. Projects directly in the solution's
+    /// directory belong to ".", projects outside it belong to "..".
+    pub fn component(&self, sln: &Solution) -> String {
+        let sln_dir = sln.file_info.path.parent().unwrap_or_else(|| Path::new(""));
+        let proj_dir = self.file_info.path.parent().unwrap_or_else(|| Path::new(""));
+
+        match proj_dir.strip_prefix(sln_dir) {
+            Ok(relative) => match relative.components().next() {
+                Some(first) => first.as_os_str().to_string_lossy().into_owned(),
+                None => ".".to_owned(),
+            },
+            Err(_) => "..".to_owned(),
+        }
+    }
+
+    /// True if the project has no packages, no project references and no assembly
+    /// references other than to the .NET Framework itself. Such a project is either
+    /// a pure leaf library or dead code that is worth reviewing.
+    pub fn is_isolated(&self) -> bool {
+        self.packages.is_empty() &&
+            self.child_project_paths.is_empty() &&
+            self.referenced_assemblies.iter().all(|asm| is_framework_assembly(asm))
+    }
+
+    /// For multi-targeted projects, returns each (target framework, package) pair.
+    /// A `<PackageReference>` whose own condition, or whose `<ItemGroup>`'s condition,
+    /// tests `$(TargetFramework)` only applies to that framework; all other references
+    /// apply to every target framework. Returns nothing for single-targeted projects.
+    pub fn packages_by_framework(&self) -> Vec<(&str, &Package)> {
+        lazy_static! {
+            static ref ITEM_GROUP_RE: Regex = RegexBuilder::new(r#"<ItemGroup\b(?P<attrs>[^<>]*?)>(?P<body>.*?)</ItemGroup\s*>"#)
+                .case_insensitive(true).dot_matches_new_line(true).build().unwrap();
+            static ref PKG_REF_RE: Regex = RegexBuilder::new(r#"<PackageReference\s+Include="(?P<name>[^"]+)"(?P<rest>.+?)(/>|</PackageReference>)"#)
+                .case_insensitive(true).dot_matches_new_line(true).build().unwrap();
+            static ref VERSION_RE: Regex = RegexBuilder::new(r#"(Version="(?P<version>[^"]+)"|<Version>(?P<version2>[^<]+)</Version>)"#)
+                .case_insensitive(true).build().unwrap();
+            static ref CONDITION_RE: Regex = Regex::new(r#"Condition\s*=\s*"(?P<cond>[^"]*)""#).unwrap();
+            static ref TF_CONDITION_RE: Regex = Regex::new(r#"'?\$\(TargetFramework\)'?\s*==\s*'(?P<tf>[^']+)'"#).unwrap();
+        }
+
+        if self.target_frameworks.len() < 2 {
+            return vec![];
+        }
+
+        fn condition_framework(attrs: &str) -> Option<&str> {
+            CONDITION_RE.captures(attrs)
+                .and_then(|c| TF_CONDITION_RE.captures(c.name("cond").unwrap().as_str()))
+                .map(|c| c.name("tf").unwrap().as_str().trim())
+        }
+
+        let mut result = vec![];
+        for group in ITEM_GROUP_RE.captures_iter(&self.file_info.contents) {
+            let group_framework = condition_framework(group.name("attrs").unwrap().as_str());
+
+            for pkg_ref in PKG_REF_RE.captures_iter(group.name("body").unwrap().as_str()) {
+                let rest = pkg_ref.name("rest").unwrap().as_str();
+                let version = VERSION_RE.captures(rest)
+                    .and_then(|vc| vc.name("version").or(vc.name("version2")))
+                    .map(|v| v.as_str())
+                    .unwrap_or_default();
+                let pkg = match self.packages.iter().find(|p| p.name == pkg_ref["name"] && p.version == version) {
+                    Some(pkg) => pkg,
+                    None => continue,
+                };
+
+                // The reference's own condition is in its start tag.
+                let start_tag = rest.split('>').next().unwrap_or_default();
+                match condition_framework(start_tag).or(group_framework) {
+                    Some(tf) => result.push((tf, pkg)),
+                    None => result.extend(self.target_frameworks.iter().map(|tf| (tf.as_str(), pkg))),
+                }
+            }
+        }
+
+        result.sort();
+        result.dedup();
+        result
+    }
+
     fn refers_to(&self, other: &Self) -> bool {
         self.child_project_paths
             .iter()
             .find(|our_child_path| **our_child_path == other.file_info.path).is_some()
     }
 
+    fn extract_root_namespace(&self) -> Option<String> {
+        lazy_static! {
+            static ref ROOT_NAMESPACE_REGEX: Regex = Regex::new(r#"<RootNamespace>(?P<ns>.*?)</RootNamespace>"#).unwrap();
+        }
+
+        ROOT_NAMESPACE_REGEX.captures(&self.file_info.contents)
+            .map(|cap| cap["ns"].trim().to_owned())
+            .filter(|ns| !ns.is_empty())
+    }
+
+    fn extract_assembly_name(&self) -> Option<String> {
+        lazy_static! {
+            static ref ASSEMBLY_NAME_REGEX: Regex = Regex::new(r#"<AssemblyName>(?P<name>.*?)</AssemblyName>"#).unwrap();
+        }
+
+        ASSEMBLY_NAME_REGEX.captures(&self.file_info.contents)
+            .map(|cap| cap["name"].trim().to_owned())
+            .filter(|name| !name.is_empty())
+    }
+
+    /// The `<ProjectGuid>`, without the braces and lowercased so that it compares
+    /// the same however it was written. SDK-style projects usually do not have one.
+    fn extract_project_guid(&self) -> Option<String> {
+        lazy_static! {
+            static ref PROJECT_GUID_REGEX: Regex = Regex::new(r#"<ProjectGuid>(?P<guid>.*?)</ProjectGuid>"#).unwrap();
+        }
+
+        PROJECT_GUID_REGEX.captures(&self.file_info.contents)
+            .map(|cap| cap["guid"].trim().trim_start_matches('{').trim_end_matches('}').to_lowercase())
+            .filter(|guid| !guid.is_empty())
+    }
+
+    /// An identity for the project that does not depend on where it is on disk, so that
+    /// it can be tracked across runs even if it moves. It is made from the assembly name,
+    /// the root namespace and the project GUID, if there is one. The first two default
+    /// to the file stem, so a project that sets none of them is identified by its name.
+    pub fn stable_id(&self) -> String {
+        let mut id = format!("{}|{}", self.effective_assembly_name(), self.effective_root_namespace());
+        if let Some(guid) = &self.project_guid {
+            id.push('|');
+            id.push_str(guid);
+        }
+        id
+    }
+
+    /// The name of the assembly that the project builds. If the project does not
+    /// specify one then MSBuild defaults it to the name of the project.
+    pub fn effective_assembly_name(&self) -> &str {
+        match &self.assembly_name {
+            Some(name) => name,
+            None => self.file_info.path.file_stem_as_str(),
+        }
+    }
+
+    /// True if the project is in a directory named after the assembly that it builds,
+    /// ignoring case, e.g. Acme.Core/Acme.Core.csproj. Uses `effective_assembly_name`,
+    /// so a project that does not set an assembly name is compared by its file stem.
+    pub fn folder_name_matches_assembly(&self) -> bool {
+        self.file_info.path.parent()
+            .map_or(false, |dir| dir.filename_as_str().eq_ignore_ascii_case(self.effective_assembly_name()))
+    }
+
+    /// The root namespace that the compiler will actually use. If the project does
+    /// not specify one then MSBuild defaults it to the name of the project.
+    pub fn effective_root_namespace(&self) -> &str {
+        match &self.root_namespace {
+            Some(ns) => ns,
+            None => self.file_info.path.file_stem_as_str(),
+        }
+    }
+
     fn extract_tt_file(&self) -> bool {
         lazy_static! {
             static ref TT_REGEX: Regex = Regex::new(r#"<None (Include|Update).*?\.tt">"#).unwrap();
@@ -644,7 +1957,30 @@ impl Project {
     fn extract_embedded_debugging(&self) -> bool {
         match self.version {
             // We expect both for it to be correct.
-            ProjectVersion::MicrosoftNetSdk | ProjectVersion::MicrosoftNetSdkWeb => self.file_info.contents.contains("<DebugType>embedded</DebugType>") && self.file_info.contents.contains("<EmbedAllSources>true</EmbedAllSources>"),
+            ProjectVersion::MicrosoftNetSdk | ProjectVersion::MicrosoftNetSdkWeb => self.debug_type.as_deref() == Some("embedded") && self.file_info.contents.contains("<EmbedAllSources>true</EmbedAllSources>"),
+            ProjectVersion::OldStyle | ProjectVersion::Unknown => false,
+        }
+    }
+
+    /// Like `extract_platform_target`, this takes the first DebugType found and ignores
+    /// any configuration conditions.
+    fn extract_debug_type(&self) -> Option<String> {
+        lazy_static! {
+            static ref DEBUG_TYPE_REGEX: Regex = Regex::new(r#"<DebugType>(?P<dt>.*?)</DebugType>"#).unwrap();
+        }
+
+        DEBUG_TYPE_REGEX.captures(&self.file_info.contents)
+            .map(|cap| cap["dt"].trim().to_owned())
+    }
+
+    /// SDK-style projects produce portable PDBs (possibly embedded in the DLL) unless
+    /// they ask for something else; 'portable' is the SDK default.
+    fn extract_produces_portable_pdb(&self) -> bool {
+        match self.version {
+            ProjectVersion::MicrosoftNetSdk | ProjectVersion::MicrosoftNetSdkWeb => match self.debug_type.as_deref() {
+                None => true,
+                Some(dt) => dt.eq_ignore_ascii_case("portable") || dt.eq_ignore_ascii_case("embedded"),
+            },
             ProjectVersion::OldStyle | ProjectVersion::Unknown => false,
         }
     }
@@ -661,88 +1997,399 @@ impl Project {
         self.file_info.contents.contains("<AutoGenerateBindingRedirects>true</AutoGenerateBindingRedirects>")
     }
 
-    fn extract_referenced_assemblies(&self) -> Vec<String> {
-        // Necessary to exclude those references that come from NuGet packages?
-        // Actually the regex seems good enough, at least for the example files
-        // in this project.
+    /// Note that these are usually found inside PropertyGroups that are conditioned
+    /// on the build configuration, and they can differ between configurations. We
+    /// ignore that and just take the first one we find.
+    fn extract_platform_target(&self) -> Option<String> {
         lazy_static! {
-            static ref ASM_REF_REGEX: Regex = Regex::new(r#"<Reference Include="(?P<name>.*?)"\s*?/>"#).unwrap();
+            static ref PLATFORM_TARGET_REGEX: Regex = Regex::new(r#"<PlatformTarget>(?P<pt>.*?)</PlatformTarget>"#).unwrap();
         }
 
-        let mut result = ASM_REF_REGEX.captures_iter(&self.file_info.contents)
-            .map(|cap| cap["name"].to_owned())
-            .collect::<Vec<_>>();
-
-        result.sort();
-        result.dedup();
-        result
+        PLATFORM_TARGET_REGEX.captures(&self.file_info.contents)
+            .map(|cap| cap["pt"].to_owned())
     }
 
-    fn extract_target_frameworks(&self) -> Vec<String> {
+    /// Subject to the same first-match limitation as `extract_platform_target`.
+    fn extract_output_path(&self) -> Option<String> {
         lazy_static! {
-            static ref OLD_TF_REGEX: Regex = Regex::new(r#"<TargetFrameworkVersion>(?P<tf>.*?)</TargetFrameworkVersion>"#).unwrap();
-            static ref SDK_SINGLE_TF_REGEX: Regex = Regex::new(r#"<TargetFramework>(?P<tf>.*?)</TargetFramework>"#).unwrap();
-            static ref SDK_MULTI_TF_REGEX: Regex = Regex::new(r#"<TargetFrameworks>(?P<tfs>.*?)</TargetFrameworks>"#).unwrap();
+            static ref OUTPUT_PATH_REGEX: Regex = Regex::new(r#"<OutputPath>(?P<path>.*?)</OutputPath>"#).unwrap();
+            static ref BASE_OUTPUT_PATH_REGEX: Regex = Regex::new(r#"<BaseOutputPath>(?P<path>.*?)</BaseOutputPath>"#).unwrap();
         }
 
-        match self.version {
-            ProjectVersion::Unknown => vec![],
-            ProjectVersion::OldStyle => OLD_TF_REGEX.captures_iter(&self.file_info.contents)
-                .map(|cap| cap["tf"].to_owned())
-                .collect(),
-            ProjectVersion::MicrosoftNetSdk | ProjectVersion::MicrosoftNetSdkWeb => {
-                // One or the other will match.
-                let single: Vec<_> = SDK_SINGLE_TF_REGEX.captures_iter(&self.file_info.contents)
-                    .map(|cap| cap["tf"].to_owned())
-                    .collect();
+        OUTPUT_PATH_REGEX.captures(&self.file_info.contents)
+            .or_else(|| BASE_OUTPUT_PATH_REGEX.captures(&self.file_info.contents))
+            .map(|cap| cap["path"].trim().to_owned())
+            .filter(|path| !path.is_empty())
+    }
 
-                if !single.is_empty() {
-                    return single;
-                }
+    /// The `output_path` resolved against the project's directory, or None if the
+    /// project does not set one. The directory properties that MSBuild defines are
+    /// expanded: `$(ProjectDir)`, `$(MSBuildProjectDirectory)` and, if the project's
+    /// `solution_directory` is known, `$(SolutionDir)`. A path that still refers to a
+    /// property after that cannot be resolved without evaluating the project, so it
+    /// is also None.
+    pub fn resolved_output_path(&self, solution_directory: Option<&Path>) -> Option<PathBuf> {
+        let output_path = self.output_path.as_ref()?;
+        let project_dir = self.file_info.path.parent().unwrap_or_else(|| Path::new(""));
+        // MSBuild's directory properties end with a separator, apart from MSBuildProjectDirectory.
+        let with_separator = |dir: &Path| format!("{}{}", dir.as_str(), std::path::MAIN_SEPARATOR);
+
+        let mut properties = HashMap::new();
+        properties.insert("projectdir".to_owned(), with_separator(project_dir));
+        properties.insert("msbuildprojectdirectory".to_owned(), project_dir.as_str().to_owned());
+        if let Some(dir) = solution_directory {
+            properties.insert("solutiondir".to_owned(), with_separator(dir));
+        }
 
-                let mut result = vec![];
+        let output_path = Self::expand_properties(output_path, &properties);
+        if output_path.contains("$(") {
+            return None;
+        }
 
-                for cap in SDK_MULTI_TF_REGEX.captures_iter(&self.file_info.contents) {
-                    let tfs = cap["tfs"].split(';');
-                    for tf in tfs {
-                        result.push(tf.to_owned());
-                    }
-                }
+        let mut path = project_dir.to_owned();
+        path.push(norm_mentioned_path(&output_path));
+        Some(normalize_path(&path))
+    }
 
-                result
-            }
-        }
+    /// Subject to the same first-match limitation as `extract_platform_target`.
+    fn extract_prefer_32bit(&self) -> bool {
+        self.file_info.contents.contains("<Prefer32Bit>true</Prefer32Bit>")
     }
 
-    fn has_file_of_interest(&self, interesting_file: InterestingFile) -> FileStatus {
-        // TODO: An optimisation would be to scan for all of these at once rather than separately.
+    /// Subject to the same first-match limitation as `extract_platform_target`.
+    fn extract_deterministic(&self) -> Option<bool> {
         lazy_static! {
-            static ref WEB_CONFIG_RE: Regex = RegexBuilder::new(&format!("\\sInclude=\"{}\"\\s*?/>", InterestingFile::WebConfig))
+            static ref DETERMINISTIC_REGEX: Regex = RegexBuilder::new(r#"<Deterministic>\s*(?P<value>true|false)\s*</Deterministic>"#)
                 .case_insensitive(true).build().unwrap();
+        }
 
-            static ref APP_CONFIG_RE: Regex = RegexBuilder::new(&format!("\\sInclude=\"{}\"\\s*?/>", InterestingFile::AppConfig))
-                .case_insensitive(true).build().unwrap();
+        DETERMINISTIC_REGEX.captures(&self.file_info.contents).map(|cap| cap["value"].eq_ignore_ascii_case("true"))
+    }
 
-            static ref APP_SETTINGS_JSON_RE: Regex = RegexBuilder::new(&format!("\\sInclude=\"{}\"\\s*?/>", InterestingFile::AppSettingsJson))
+    /// Subject to the same first-match limitation as `extract_platform_target`.
+    fn extract_allows_unsafe_code(&self) -> bool {
+        lazy_static! {
+            static ref ALLOW_UNSAFE_BLOCKS_REGEX: Regex = RegexBuilder::new(r#"<AllowUnsafeBlocks>\s*(?P<value>true|false)\s*</AllowUnsafeBlocks>"#)
                 .case_insensitive(true).build().unwrap();
+        }
 
-            static ref PACKAGE_JSON_RE: Regex = RegexBuilder::new(&format!("\\sInclude=\"{}\"\\s*?/>", InterestingFile::PackageJson))
-                .case_insensitive(true).build().unwrap();
+        ALLOW_UNSAFE_BLOCKS_REGEX.captures(&self.file_info.contents).map_or(false, |cap| cap["value"].eq_ignore_ascii_case("true"))
+    }
 
-            static ref PACKAGES_CONFIG_RE: Regex = RegexBuilder::new(&format!("\\sInclude=\"{}\"\\s*?/>", InterestingFile::PackagesConfig))
-                .case_insensitive(true).build().unwrap();
+    /// Subject to the same first-match limitation as `extract_platform_target`.
+    fn extract_lang_version(&self) -> Option<String> {
+        lazy_static! {
+            static ref LANG_VERSION_REGEX: Regex = Regex::new(r#"<LangVersion>(?P<lv>.*?)</LangVersion>"#).unwrap();
+        }
 
-            static ref PROJECT_JSON_RE: Regex = RegexBuilder::new(&format!("\\sInclude=\"{}\"\\s*?/>", InterestingFile::ProjectJson))
-                .case_insensitive(true).build().unwrap();
+        LANG_VERSION_REGEX.captures(&self.file_info.contents)
+            .map(|cap| cap["lv"].trim().to_owned())
+    }
+
+    /// The value of the first `<Company>`, `<Authors>` or `<Copyright>` element, as
+    /// named by `element` (MSBuild ignores the case of element names). The value is
+    /// not trimmed or unescaped, so that the report shows exactly what will end up
+    /// in the package.
+    fn extract_package_metadata(&self, element: &str) -> Option<String> {
+        lazy_static! {
+            static ref PACKAGE_METADATA_REGEX: Regex =
+                RegexBuilder::new(r#"<(?P<element>Company|Authors|Copyright)>(?P<value>.*?)</(Company|Authors|Copyright)>"#)
+                .case_insensitive(true).dot_matches_new_line(true).build().unwrap();
         }
 
-        let re: &Regex = match interesting_file {
-            InterestingFile::WebConfig => &WEB_CONFIG_RE,
+        PACKAGE_METADATA_REGEX.captures_iter(&self.file_info.contents)
+            .find(|cap| cap["element"].eq_ignore_ascii_case(element))
+            .map(|cap| cap["value"].to_owned())
+    }
+
+    fn extract_user_secrets_id(&self) -> Option<String> {
+        lazy_static! {
+            static ref USER_SECRETS_ID_REGEX: Regex = Regex::new(r#"<UserSecretsId>(?P<id>.*?)</UserSecretsId>"#).unwrap();
+        }
+
+        USER_SECRETS_ID_REGEX.captures(&self.file_info.contents)
+            .map(|cap| cap["id"].trim().to_owned())
+            .filter(|id| !id.is_empty())
+    }
+
+    /// The constants from all the DefineConstants elements, merged together. The
+    /// standard DEBUG and TRACE constants are included. References to other properties,
+    /// such as the common `$(DefineConstants);FOO`, are excluded.
+    fn extract_define_constants(&self) -> Vec<String> {
+        Self::split_define_constants(PropertyGroup::parse_all(&self.file_info.contents).iter())
+    }
+
+    /// Returns the constants defined when building the given configuration, e.g. "Debug".
+    /// Unlike `define_constants`, constants set only for other configurations are excluded.
+    pub fn define_constants_for(&self, configuration: &str) -> Vec<String> {
+        let groups = PropertyGroup::parse_all(&self.file_info.contents);
+        Self::split_define_constants(groups.iter().filter(|g| g.applies_to(configuration)))
+    }
+
+    fn split_define_constants<'a, I>(groups: I) -> Vec<String>
+        where I: Iterator<Item = &'a PropertyGroup>
+    {
+        let mut result = groups
+            .flat_map(|g| g.values("DefineConstants"))
+            .flat_map(|dc| dc.split(';'))
+            .map(|c| c.trim().to_owned())
+            .filter(|c| !c.is_empty() && !c.starts_with("$("))
+            .collect::<Vec<_>>();
+
+        result.sort();
+        result.dedup();
+        result
+    }
+
+    /// The analyzer rules from all the NoWarn elements, merged together. NoWarn lists
+    /// are usually separated by semicolons, but commas also work.
+    fn extract_suppressed_analyzer_rules(&self) -> Vec<String> {
+        let groups = PropertyGroup::parse_all(&self.file_info.contents);
+        let mut result = groups.iter()
+            .flat_map(|g| g.values("NoWarn"))
+            .flat_map(|nw| nw.split(|c| c == ';' || c == ','))
+            .map(|code| code.trim().to_uppercase())
+            .filter(|code| is_analyzer_rule(code))
+            .collect::<Vec<_>>();
+
+        result.sort();
+        result.dedup();
+        result
+    }
+
+    fn extract_referenced_assemblies(&self) -> Vec<String> {
+        // Necessary to exclude those references that come from NuGet packages?
+        // Actually the regex seems good enough, at least for the example files
+        // in this project.
+        lazy_static! {
+            static ref ASM_REF_REGEX: Regex = Regex::new(r#"<Reference Include="(?P<name>.*?)"\s*?/>"#).unwrap();
+        }
+
+        let mut result = ASM_REF_REGEX.captures_iter(&self.file_info.contents)
+            .map(|cap| cap["name"].to_owned())
+            .collect::<Vec<_>>();
+
+        result.sort();
+        result.dedup();
+        result
+    }
+
+    fn extract_fragile_reference_hint_paths(&self) -> Vec<String> {
+        lazy_static! {
+            static ref REF_WITH_BODY_REGEX: Regex = RegexBuilder::new(r#"<Reference\s[^>]*?[^/]>(?P<body>.*?)</Reference>"#)
+                .dot_matches_new_line(true).build().unwrap();
+            static ref HINT_PATH_REGEX: Regex = Regex::new(r#"<HintPath>\s*(?P<hp>.*?)\s*</HintPath>"#).unwrap();
+            static ref FRAGILE_REGEX: Regex = RegexBuilder::new(r#"(^|[\\/])(packages|bin)[\\/]|^([a-z]:)?[\\/]"#)
+                .case_insensitive(true).build().unwrap();
+        }
+
+        let mut result = REF_WITH_BODY_REGEX.captures_iter(&self.file_info.contents)
+            .filter_map(|cap| HINT_PATH_REGEX.captures(&cap["body"]).map(|hp| hp["hp"].to_owned()))
+            .filter(|hp| FRAGILE_REGEX.is_match(hp))
+            .collect::<Vec<_>>();
+
+        result.sort();
+        result.dedup();
+        result
+    }
+
+    fn extract_target_frameworks(&self) -> Vec<String> {
+        lazy_static! {
+            static ref OLD_TF_REGEX: Regex = Regex::new(r#"<TargetFrameworkVersion>(?P<tf>.*?)</TargetFrameworkVersion>"#).unwrap();
+            static ref SDK_SINGLE_TF_REGEX: Regex = Regex::new(r#"<TargetFramework>(?P<tf>.*?)</TargetFramework>"#).unwrap();
+            static ref SDK_MULTI_TF_REGEX: Regex = Regex::new(r#"<TargetFrameworks>(?P<tfs>.*?)</TargetFrameworks>"#).unwrap();
+        }
+
+        match self.version {
+            ProjectVersion::Unknown => vec![],
+            ProjectVersion::OldStyle => OLD_TF_REGEX.captures_iter(&self.file_info.contents)
+                .map(|cap| cap["tf"].to_owned())
+                .collect(),
+            ProjectVersion::MicrosoftNetSdk | ProjectVersion::MicrosoftNetSdkWeb => {
+                // One or the other will match.
+                let single: Vec<_> = SDK_SINGLE_TF_REGEX.captures_iter(&self.file_info.contents)
+                    .map(|cap| cap["tf"].to_owned())
+                    .collect();
+
+                if !single.is_empty() {
+                    return single;
+                }
+
+                let mut result = vec![];
+
+                for cap in SDK_MULTI_TF_REGEX.captures_iter(&self.file_info.contents) {
+                    let tfs = cap["tfs"].split(';');
+                    for tf in tfs {
+                        result.push(tf.to_owned());
+                    }
+                }
+
+                result
+            }
+        }
+    }
+
+    /// Expands the property references in the target frameworks. This is not a full
+    /// MSBuild evaluation: conditions are ignored and only the project itself and the
+    /// nearest Directory.Build.props (the only one MSBuild imports) are consulted.
+    /// References that cannot be resolved are left as they are.
+    fn resolve_target_frameworks<L: FileLoader>(&self, file_loader: &L) -> Vec<String> {
+        if !self.framework_is_variable {
+            return self.target_frameworks.clone();
+        }
+
+        // Directory.Build.props is imported before the body of the project, so the
+        // project's own properties win. Property names are not case sensitive.
+        let props_contents = Self::read_nearest_file(&self.file_info.path, "Directory.Build.props", file_loader);
+        let mut properties = HashMap::new();
+        for contents in props_contents.iter().chain(std::iter::once(&self.file_info.contents)) {
+            for group in PropertyGroup::parse_all(contents) {
+                for (name, value) in group.properties {
+                    properties.insert(name.to_lowercase(), value);
+                }
+            }
+        }
+
+        self.target_frameworks.iter()
+            .flat_map(|tf| Self::expand_properties(tf, &properties)
+                .split(';')
+                .map(|tf| tf.trim().to_owned())
+                .filter(|tf| !tf.is_empty())
+                .collect::<Vec<_>>())
+            .collect()
+    }
+
+    /// Replaces `$(Name)` references with the values in `properties`, whose keys are
+    /// lowercase. Values can refer to other properties, so this is repeated a few times.
+    fn expand_properties(text: &str, properties: &HashMap<String, String>) -> String {
+        lazy_static! {
+            static ref PROPERTY_REFERENCE_REGEX: Regex = Regex::new(r#"\$\((?P<name>[A-Za-z_][\w.]*)\)"#).unwrap();
+        }
+
+        let mut result = text.to_owned();
+        for _ in 0..5 {
+            let expanded = PROPERTY_REFERENCE_REGEX.replace_all(&result, |cap: &regex::Captures| {
+                properties.get(&cap["name"].to_lowercase()).cloned().unwrap_or_else(|| cap[0].to_owned())
+            }).into_owned();
+
+            if expanded == result {
+                break;
+            }
+            result = expanded;
+        }
+
+        result
+    }
+
+    /// Extracts the custom .targets files imported by the project. The standard imports
+    /// (those rooted at an MSBuild path property, or that come from an SDK) are excluded
+    /// because they are not something that needs attention during migration.
+    fn extract_imported_targets(&self) -> Vec<String> {
+        lazy_static! {
+            static ref IMPORT_REGEX: Regex = RegexBuilder::new(r#"<Import\s+Project="(?P<path>[^"]+?\.targets)"(?P<rest>[^>]*)>"#)
+                .case_insensitive(true).build().unwrap();
+        }
+
+        let is_standard = |path: &str, rest: &str| {
+            let lower = path.to_lowercase();
+            lower.starts_with("$(msbuild") || lower.starts_with("$(vstoolspath)") || rest.contains("Sdk=")
+        };
+
+        let mut result = IMPORT_REGEX.captures_iter(&self.file_info.contents)
+            .filter(|cap| !is_standard(&cap["path"], &cap["rest"]))
+            .map(|cap| cap["path"].to_owned())
+            .collect::<Vec<_>>();
+
+        result.sort();
+        result.dedup();
+        result
+    }
+
+    /// Detects `<Import Project="..\packages\Foo.1.0.0\build\Foo.targets" />` and the
+    /// like. Unlike `extract_imported_targets` this also considers .props files.
+    fn extract_has_packages_config_imports(&self) -> bool {
+        lazy_static! {
+            static ref PACKAGES_IMPORT_REGEX: Regex = RegexBuilder::new(r#"<Import\s+Project="([^"]*[\\/])?packages[\\/][^"]*""#)
+                .case_insensitive(true).build().unwrap();
+        }
+
+        PACKAGES_IMPORT_REGEX.is_match(&self.file_info.contents)
+    }
+
+    /// Returns the value of the ManagePackageVersionsCentrally property, if it is set.
+    fn extract_manage_package_versions_centrally(contents: &str) -> Option<bool> {
+        lazy_static! {
+            static ref CPM_REGEX: Regex = RegexBuilder::new(r#"<ManagePackageVersionsCentrally>\s*(?P<value>true|false)\s*</ManagePackageVersionsCentrally>"#)
+                .case_insensitive(true).build().unwrap();
+        }
+
+        CPM_REGEX.captures(contents).map(|cap| cap["value"].eq_ignore_ascii_case("true"))
+    }
+
+    /// Reads the file called `filename` in the project's directory or, failing that, the
+    /// nearest one above it. This is how MSBuild finds Directory.Build.props and
+    /// Directory.Packages.props, and like MSBuild the search does not stop at the scan
+    /// directory or the root of the repository.
+    fn read_nearest_file<L: FileLoader>(project_path: &Path, filename: &str, file_loader: &L) -> Option<String> {
+        project_path.ancestors().skip(1)
+            .find_map(|dir| file_loader.read_to_string(&dir.join(filename)).ok())
+    }
+
+    /// MSBuild imports the Directory.Packages.props in the project's directory, or failing
+    /// that the nearest one above it. Only that file counts.
+    fn directory_packages_props_enables_cpm<L: FileLoader>(project_path: &Path, file_loader: &L) -> bool {
+        Self::read_nearest_file(project_path, "Directory.Packages.props", file_loader)
+            .and_then(|contents| Self::extract_manage_package_versions_centrally(&contents))
+            .unwrap_or(false)
+    }
+
+    /// Old-style projects that use wildcards in their Compile, Content or None items
+    /// behave unpredictably in Visual Studio. SDK-style projects glob by default, so
+    /// wildcards are normal there and are not reported.
+    fn extract_has_wildcard_includes(&self) -> bool {
+        lazy_static! {
+            static ref WILDCARD_REGEX: Regex = Regex::new(r#"<(Compile|Content|None)\s+Include="[^"]*\*[^"]*""#).unwrap();
+        }
+
+        match self.version {
+            ProjectVersion::OldStyle => WILDCARD_REGEX.is_match(&self.file_info.contents),
+            ProjectVersion::MicrosoftNetSdk | ProjectVersion::MicrosoftNetSdkWeb | ProjectVersion::Unknown => false,
+        }
+    }
+
+    fn has_file_of_interest(&self, interesting_file: InterestingFile) -> FileStatus {
+        // TODO: An optimisation would be to scan for all of these at once rather than separately.
+        lazy_static! {
+            static ref WEB_CONFIG_RE: Regex = RegexBuilder::new(&format!("\\sInclude=\"{}\"\\s*?/>", InterestingFile::WebConfig))
+                .case_insensitive(true).build().unwrap();
+
+            static ref APP_CONFIG_RE: Regex = RegexBuilder::new(&format!("\\sInclude=\"{}\"\\s*?/>", InterestingFile::AppConfig))
+                .case_insensitive(true).build().unwrap();
+
+            static ref APP_SETTINGS_JSON_RE: Regex = RegexBuilder::new(&format!("\\sInclude=\"{}\"\\s*?/>", InterestingFile::AppSettingsJson))
+                .case_insensitive(true).build().unwrap();
+
+            static ref PACKAGE_JSON_RE: Regex = RegexBuilder::new(&format!("\\sInclude=\"{}\"\\s*?/>", InterestingFile::PackageJson))
+                .case_insensitive(true).build().unwrap();
+
+            static ref PACKAGES_CONFIG_RE: Regex = RegexBuilder::new(&format!("\\sInclude=\"{}\"\\s*?/>", InterestingFile::PackagesConfig))
+                .case_insensitive(true).build().unwrap();
+
+            static ref PROJECT_JSON_RE: Regex = RegexBuilder::new(&format!("\\sInclude=\"{}\"\\s*?/>", InterestingFile::ProjectJson))
+                .case_insensitive(true).build().unwrap();
+
+            static ref GLOBAL_USINGS_RE: Regex = RegexBuilder::new(&format!("\\sInclude=\"{}\"\\s*?/>", InterestingFile::GlobalUsings))
+                .case_insensitive(true).build().unwrap();
+        }
+
+        let re: &Regex = match interesting_file {
+            InterestingFile::WebConfig => &WEB_CONFIG_RE,
             InterestingFile::AppConfig => &APP_CONFIG_RE,
             InterestingFile::AppSettingsJson => &APP_SETTINGS_JSON_RE,
             InterestingFile::PackageJson => &PACKAGE_JSON_RE,
             InterestingFile::PackagesConfig => &PACKAGES_CONFIG_RE,
             InterestingFile::ProjectJson => &PROJECT_JSON_RE,
+            InterestingFile::GlobalUsings => &GLOBAL_USINGS_RE,
         };
 
         match (re.is_match(&self.file_info.contents), self.find_other_file(interesting_file).is_some()) {
@@ -764,6 +2411,29 @@ impl Project {
             .find(|item| unicase::eq(item.filename_as_str(), other_file.as_ref()))
     }
 
+    fn extract_binding_redirect_count<L: FileLoader>(&self, file_loader: &L) -> usize {
+        lazy_static! {
+            static ref BINDING_REDIRECT_RE: Regex = RegexBuilder::new(r#"<bindingRedirect\b"#)
+                .case_insensitive(true).build().unwrap();
+        }
+
+        self.find_other_file(InterestingFile::AppConfig)
+            .and_then(|path| file_loader.read_to_string(path).ok())
+            .map(|contents| BINDING_REDIRECT_RE.find_iter(&contents).count())
+            .unwrap_or_default()
+    }
+
+    /// True if `package_tf`, the `targetFramework` of a package in packages.config, is
+    /// not one of the frameworks the project targets. This happens when the project is
+    /// retargeted without reinstalling its packages. Unknown frameworks, including ones
+    /// given by a property that could not be resolved, are not flagged.
+    fn is_framework_mismatch(&self, package_tf: &str) -> bool {
+        !package_tf.is_empty() &&
+            !self.resolved_target_frameworks.is_empty() &&
+            !self.resolved_target_frameworks.iter().any(|tf| tf.contains("$(")) &&
+            !self.resolved_target_frameworks.iter().any(|tf| short_framework_name(tf).eq_ignore_ascii_case(package_tf))
+    }
+
     fn extract_project_paths(&self) -> Vec<PathBuf> {
         lazy_static! {
             static ref PROJECT_REF_REGEX: Regex = RegexBuilder::new(r#"<ProjectReference\s+Include="(?P<name>[^"]+)"(?P<rest>.+?)(/>|</ProjectReference>)"#)
@@ -823,16 +2493,15 @@ impl Project {
 
             static ref PKG_CONFIG_RE: Regex = RegexBuilder::new(r#"<package\s*?id="(?P<name>.*?)"\s*?version="(?P<version>.*?)"(?P<inner>.*?)\s*?/>"#)
                 .case_insensitive(true).build().unwrap();
+
+            static ref PKG_CONFIG_TF_RE: Regex = RegexBuilder::new(r#"targetFramework="(?P<tf>[^"]*)""#)
+                .case_insensitive(true).build().unwrap();
         }
 
         let classify = |pkg_name: &str| -> String {
-            for pkg_group in &configuration.package_groups {
-                if pkg_group.regex.is_match(pkg_name) {
-                    return pkg_group.name.clone();
-                }
-            }
-
-            "Unclassified".to_owned()
+            configuration.package_group_for(pkg_name)
+                .unwrap_or(&configuration.default_package_class)
+                .to_owned()
         };
 
         let mut packages = match self.version {
@@ -840,18 +2509,21 @@ impl Project {
                 .map(|cap| {
                     let pkg_name = &cap["name"];
                     let rest = &cap["rest"];
-                    let version_captures = SDK_VERSION_RE.captures(rest).unwrap();
-                    let version = version_captures.name("version")
-                            .or(version_captures.name("version2"))
-                            .unwrap()
-                            .as_str();
-
-                    Package::new(
-                        pkg_name,
-                        version,
-                        rest.contains("<PrivateAssets>"),
-                        classify(pkg_name),
-                    )
+                    // Under central package management the version is not specified here.
+                    let version = SDK_VERSION_RE.captures(rest)
+                            .and_then(|vc| vc.name("version").or(vc.name("version2")))
+                            .map(|v| v.as_str())
+                            .unwrap_or_default();
+
+                    Package {
+                        missing_version: version.is_empty() && !self.central_package_management,
+                        ..Package::new(
+                            pkg_name,
+                            version,
+                            rest.contains("<PrivateAssets>"),
+                            classify(pkg_name),
+                        )
+                    }
                 })
                 .collect(),
             ProjectVersion::OldStyle => {
@@ -860,12 +2532,19 @@ impl Project {
                     .and_then(|pc_path| file_loader.read_to_string(pc_path).ok())
                     .map(|pc_contents| { PKG_CONFIG_RE.captures_iter(&pc_contents)
                             .map(|cap| {
-                                Package::new(
-                                    &cap["name"],
-                                    &cap["version"],
-                                    cap["inner"].contains("developmentDependency=\"true\""),
-                                    classify(&cap["name"]),
-                                )
+                                let package_tf = PKG_CONFIG_TF_RE.captures(&cap["inner"])
+                                    .map(|tf_cap| tf_cap["tf"].trim().to_owned())
+                                    .unwrap_or_default();
+
+                                Package {
+                                    framework_mismatch: self.is_framework_mismatch(&package_tf),
+                                    ..Package::new(
+                                        &cap["name"],
+                                        &cap["version"],
+                                        cap["inner"].contains("developmentDependency=\"true\""),
+                                        classify(&cap["name"]),
+                                    )
+                                }
                             })
                             .collect()
                     })
@@ -879,6 +2558,32 @@ impl Project {
         packages
     }
 
+    /// A project uses global usings if it has a GlobalUsings.cs file alongside it
+    /// or declares <Using Include="..." /> items in the project file.
+    fn extract_has_global_usings(&self) -> bool {
+        lazy_static! {
+            static ref USING_ITEM_REGEX: Regex = Regex::new(r#"<Using\s+Include=""#).unwrap();
+        }
+
+        self.has_file_of_interest(InterestingFile::GlobalUsings) != FileStatus::NotPresent
+            || USING_ITEM_REGEX.is_match(&self.file_info.contents)
+    }
+
+    /// Finds packages that are referenced more than once within this project with
+    /// different versions. `packages` is sorted and deduped, so any remaining entries
+    /// with the same name must have different versions.
+    fn extract_intra_project_package_conflicts(&self) -> Vec<(String, Vec<String>)> {
+        let mut versions_by_name = BTreeMap::<&str, Vec<String>>::new();
+        for pkg in &self.packages {
+            versions_by_name.entry(&pkg.name).or_default().push(pkg.version.clone());
+        }
+
+        versions_by_name.into_iter()
+            .filter(|(_, versions)| versions.len() > 1)
+            .map(|(name, versions)| (name.to_owned(), versions))
+            .collect()
+    }
+
     fn extract_test_framework(&self) -> TestFramework {
         for pkg in &self.packages {
             let name = pkg.name.to_lowercase();
@@ -893,20 +2598,67 @@ impl Project {
             }
         }
 
+        // Old test projects reference the MSTest v1 assembly that was installed with
+        // Visual Studio rather than a package. The Include may be a full strong name.
+        let uses_mstest_v1 = self.referenced_assemblies.iter()
+            .map(|asm| asm.split(',').next().unwrap_or_default().trim())
+            .any(|name| name.eq_ignore_ascii_case("Microsoft.VisualStudio.QualityTools.UnitTestFramework"));
+        if uses_mstest_v1 {
+            return TestFramework::MSTest;
+        }
+
         TestFramework::None
     }
 
+    /// `dotnet test` needs both Microsoft.NET.Test.Sdk and an adapter for the test
+    /// framework. The MSTest metapackage brings in both. Old-style projects are run
+    /// by Visual Studio's own runner, so they are not checked.
+    fn extract_test_project_misconfigured(&self) -> bool {
+        const TEST_SDK_PACKAGES: [&str; 2] = ["microsoft.net.test.sdk", "mstest"];
+        const ADAPTER_PACKAGES: [&str; 5] = ["xunit.runner.visualstudio", "nunit3testadapter", "nunittestadapter", "mstest.testadapter", "mstest"];
+
+        let has_package = |names: &[&str]| self.packages.iter()
+            .any(|pkg| names.contains(&pkg.name.to_lowercase().as_str()));
+
+        match self.version {
+            ProjectVersion::MicrosoftNetSdk | ProjectVersion::MicrosoftNetSdkWeb =>
+                self.test_framework != TestFramework::None &&
+                    !(has_package(&TEST_SDK_PACKAGES) && has_package(&ADAPTER_PACKAGES)),
+            ProjectVersion::OldStyle | ProjectVersion::Unknown => false,
+        }
+    }
+
     fn extract_uses_specflow(&self) -> bool {
         self.packages.iter().any(|pkg| pkg.name.to_lowercase().contains("specflow"))
     }
+
+    /// Source Link can be indicated either by one of its packages or by the
+    /// properties that are set alongside it.
+    fn extract_uses_source_link(&self) -> bool {
+        self.file_info.contents.contains("<PublishRepositoryUrl>true</PublishRepositoryUrl>") ||
+            self.file_info.contents.contains("<EmbedUntrackedSources>true</EmbedUntrackedSources>") ||
+            self.packages.iter().any(|pkg| pkg.name.to_lowercase().starts_with("microsoft.sourcelink."))
+    }
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Package {
     pub name: String,
     pub version: String,
     pub development: bool,
-    pub class: String
+    pub class: String,
+    /// True if the `<PackageReference>` has no version and the project does not use
+    /// central package management to supply one. NuGet will pick the lowest version.
+    #[serde(default)]
+    pub missing_version: bool,
+    /// True if the package's `targetFramework` in packages.config is not one of the
+    /// frameworks that the project targets, so the package should be reinstalled.
+    #[serde(default)]
+    pub framework_mismatch: bool,
+    /// The newer version of the package on the feed, if there is one. Only set when
+    /// a feed index is given; see `flag_available_updates`.
+    #[serde(default)]
+    pub update_available: Option<String>,
 }
 
 impl Package {
@@ -919,7 +2671,10 @@ impl Package {
             name: name.into(),
             version: version.into(),
             development,
-            class: class.into()
+            class: class.into(),
+            missing_version: false,
+            framework_mismatch: false,
+            update_available: None,
         }
     }
 
@@ -928,6 +2683,83 @@ impl Package {
     }
 }
 
+/// Compares two NuGet package versions, such as "4.0.1" and "4.0.1-beta2".
+/// Missing numeric parts are treated as 0 and a pre-release version is lower
+/// than the corresponding release.
+pub fn compare_package_versions(a: &str, b: &str) -> Ordering {
+    fn split(version: &str) -> (&str, Option<&str>) {
+        let version = version.trim();
+        match version.find('-') {
+            Some(idx) => (&version[..idx], Some(&version[idx + 1..])),
+            None => (version, None),
+        }
+    }
+
+    fn compare_parts(a: &str, b: &str) -> Ordering {
+        let mut a_parts = a.split('.');
+        let mut b_parts = b.split('.');
+        loop {
+            let ord = match (a_parts.next(), b_parts.next()) {
+                (None, None) => return Ordering::Equal,
+                (a_part, b_part) => {
+                    let a_part = a_part.unwrap_or("0");
+                    let b_part = b_part.unwrap_or("0");
+                    match (a_part.parse::<u64>(), b_part.parse::<u64>()) {
+                        (Ok(a_num), Ok(b_num)) => a_num.cmp(&b_num),
+                        _ => a_part.to_lowercase().cmp(&b_part.to_lowercase()),
+                    }
+                }
+            };
+
+            if ord != Ordering::Equal {
+                return ord;
+            }
+        }
+    }
+
+    let (a_release, a_pre) = split(a);
+    let (b_release, b_pre) = split(b);
+
+    compare_parts(a_release, b_release).then_with(|| match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a_pre), Some(b_pre)) => compare_parts(a_pre, b_pre),
+    })
+}
+
+/// Returns true if the warning code, in upper case, is one raised by the .NET code
+/// analyzers, such as "CA2000" or "IDE0005", rather than by the compiler.
+pub fn is_analyzer_rule(code: &str) -> bool {
+    const ANALYZER_RULE_PREFIXES: &[&str] = &["CA", "IDE"];
+
+    ANALYZER_RULE_PREFIXES.iter().any(|prefix| {
+        code.strip_prefix(prefix)
+            .map_or(false, |number| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+    })
+}
+
+/// A package that a project references at a lower version than one of the
+/// projects it depends on. See `Analysis::potential_package_downgrades`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Downgrade<'a> {
+    pub parent: &'a Project,
+    pub child: &'a Project,
+    pub package_name: &'a str,
+    pub parent_version: &'a str,
+    pub child_version: &'a str,
+}
+
+/// The highest version of a package that is in use, and how many of the projects
+/// using the package are behind it. See `Analysis::package_consolidation_report`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageConsolidation<'a> {
+    pub package_name: &'a str,
+    pub max_version: &'a str,
+    pub num_projects: usize,
+    pub num_laggards: usize,
+}
+
 
 
 #[cfg(test)]
@@ -1017,34 +2849,1397 @@ mod analysis_tests {
         assert_eq!(truck_sln.orphaned_projects().nth(0).unwrap().file_info.path.filename_as_str(), "mercedes.csproj");
         assert_eq!(truck_sln.orphaned_projects().nth(1).unwrap().file_info.path.filename_as_str(), "renault.csproj");
     }
-}
 
-#[cfg(test)]
- mod project_tests {
-    use super::*;
-    use crate::io::MemoryFileLoader;
+    #[test]
+    pub fn exclude_solution_directories_works() {
+        let temp_files = make_temporary_directory().unwrap();
+        let root_dir = temp_files.path();
+        let config = Configuration {
+            input_directory: root_dir.to_owned(),
+            ..Configuration::default()
+        };
+        let directories = |analysis: &Analysis| analysis.solution_directories.iter()
+            .map(|sd| sd.directory.clone())
+            .collect::<Vec<_>>();
 
-    #[derive(Default)]
-    struct ProjectBuilder {
-         csproj_contents: String,
-         project_version: ProjectVersion,
-         packages_config_contents: Option<String>,
-         other_files: Vec<PathBuf>
-     }
+        let mut analysis = Analysis::new(&config).unwrap();
+        analysis.exclude_solution_directories(&["truck*"]);
+        assert_eq!(directories(&analysis), vec![root_dir.to_owned()]);
 
-    impl ProjectBuilder {
-        fn new<S>(csproj_contents: S) -> Self
-        where S: Into<String>
-        {
-            ProjectBuilder {
-                csproj_contents: csproj_contents.into(),
-                .. ProjectBuilder::default()
-            }
-        }
+        let mut analysis = Analysis::new(&config).unwrap();
+        analysis.exclude_solution_directories(&[root_dir.join("trucks").as_str()]);
+        assert_eq!(directories(&analysis), vec![root_dir.to_owned()]);
 
-        fn with_packages_config(mut self, packages_config_contents: &str) -> Self {
-            self.packages_config_contents = Some(packages_config_contents.to_owned());
-            self
+        let mut analysis = Analysis::new(&config).unwrap();
+        analysis.exclude_solution_directories(&["cars"]);
+        assert_eq!(directories(&analysis), vec![root_dir.to_owned(), root_dir.join("trucks")]);
+    }
+
+    #[test]
+    pub fn find_solution_for_project_works() {
+        let temp_files = make_temporary_directory().unwrap();
+        let root_dir = temp_files.path();
+        let config = Configuration {
+            input_directory: root_dir.to_owned(),
+            ..Configuration::default()
+        };
+        let analysis = Analysis::new(&config).unwrap();
+
+        let sln_name = |path: PathBuf| analysis.find_solution_for_project(path).map(|sln| sln.file_info.filename_as_str());
+        // Linked.
+        assert_eq!(sln_name(root_dir.join("sub").join("toyota.csproj")), Some("car.sln"));
+        assert_eq!(sln_name(root_dir.join("trucks").join("volvo.csproj")), Some("truck.sln"));
+        // Orphaned.
+        assert_eq!(sln_name(root_dir.join("bmw.csproj")), Some("car.sln"));
+        assert_eq!(sln_name(root_dir.join("trucks").join("renault.csproj")), Some("truck.sln"));
+        // Owned by nothing.
+        assert_eq!(sln_name(PathBuf::from("/elsewhere/audi.csproj")), None);
+        assert_eq!(sln_name(PathBuf::from("Audi.csproj")), None);
+        assert_eq!(sln_name(PathBuf::from("")), None);
+
+        let sd = analysis.find_solution_directory_for_project(root_dir.join("trucks").join("mercedes.csproj")).unwrap();
+        assert_eq!(sd.directory, root_dir.join("trucks"));
+        assert!(analysis.find_solution_directory_for_project(PathBuf::from("/elsewhere/audi.csproj")).is_none());
+    }
+
+    #[test]
+    pub fn orphan_prefers_a_sln_in_its_own_directory_whatever_the_order() {
+        let root = tempfile::Builder::new().prefix("dnlib-temp-").rand_bytes(5).tempdir().unwrap();
+        let sub_dir = root.path().join("sub");
+        fs::create_dir_all(&sub_dir).unwrap();
+        for file in &["outer.sln", "sub/inner.sln", "sub/stray.csproj"] {
+            fs::write(root.path().join(file), "").unwrap();
+        }
+
+        let config = Configuration {
+            input_directory: root.path().to_owned(),
+            ..Configuration::default()
+        };
+        let outer = root.path().join("outer.sln");
+        let inner = sub_dir.join("inner.sln");
+
+        for sln_files in vec![vec![outer.clone(), inner.clone()], vec![inner.clone(), outer.clone()]] {
+            let paths = PathsToAnalyze {
+                sln_files,
+                csproj_files: vec![sub_dir.join("stray.csproj")],
+                other_files: vec![],
+            };
+            let analysis = Analysis::from_paths(&config, paths, DiskFileLoader::default()).unwrap();
+
+            let (_, sln, proj) = analysis.orphaned_projects().into_iter().next().unwrap();
+            assert_eq!(sln.file_info.filename_as_str(), "inner.sln");
+            assert_eq!(proj.orphan_reason, Some(OrphanReason::SameDirectory));
+        }
+    }
+
+    #[test]
+    pub fn test_orphaned_projects_are_grouped_with_their_reasons() {
+        let temp_files = make_temporary_directory().unwrap();
+        let root_dir = temp_files.path();
+        File::create(root_dir.join("sub").join("honda.csproj")).unwrap();
+
+        let config = Configuration {
+            input_directory: root_dir.to_owned(),
+            ..Configuration::default()
+        };
+        let analysis = Analysis::new(&config).unwrap();
+
+        let orphans = analysis.orphaned_projects().into_iter()
+            .map(|(sd, sln, proj)| (
+                sd.directory.strip_prefix(root_dir).unwrap().to_owned(),
+                sln.file_info.filename_as_str(),
+                proj.file_info.filename_as_str(),
+                proj.orphan_reason,
+            ))
+            .collect::<Vec<_>>();
+
+        assert_eq!(orphans, vec![
+            (PathBuf::from(""), "car.sln", "bmw.csproj", Some(OrphanReason::SameDirectory)),
+            (PathBuf::from(""), "car.sln", "honda.csproj", Some(OrphanReason::ParentDirectory)),
+            (PathBuf::from("trucks"), "truck.sln", "mercedes.csproj", Some(OrphanReason::SameDirectory)),
+            (PathBuf::from("trucks"), "truck.sln", "renault.csproj", Some(OrphanReason::SameDirectory)),
+        ]);
+
+        let linked = analysis.projects().find(|proj| proj.file_info.path.filename_as_str() == "ford.csproj").unwrap();
+        assert_eq!(linked.orphan_reason, None);
+    }
+
+    #[test]
+    pub fn test_thread_counts_do_not_affect_results() {
+        let temp_files = make_temporary_directory().unwrap();
+        let root_dir = temp_files.path();
+
+        let analyze = |solution_threads, project_threads| {
+            let config = Configuration {
+                input_directory: root_dir.to_owned(),
+                solution_threads,
+                project_threads,
+                ..Configuration::default()
+            };
+            format!("{:?}", Analysis::new(&config).unwrap())
+        };
+
+        let expected = analyze(None, None);
+        assert_eq!(analyze(Some(1), Some(1)), expected);
+        assert_eq!(analyze(Some(1), Some(4)), expected);
+        assert_eq!(analyze(Some(3), Some(2)), expected);
+    }
+
+    fn make_project(path: &str, output_type: OutputType) -> Project {
+        Project {
+            file_info: FileInfo { path: PathBuf::from(path), ..Default::default() },
+            output_type,
+            ..Default::default()
+        }
+    }
+
+    fn make_linked_project(path: &str, children: &[&str]) -> Project {
+        Project {
+            file_info: FileInfo { path: PathBuf::from(path), ..Default::default() },
+            child_project_paths: children.iter().map(PathBuf::from).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    pub fn test_transitive_dependency_counts() {
+        // A diamond: app -> (web, worker) -> core, plus core -> util.
+        let sln = Solution {
+            projects: vec![
+                make_linked_project("/temp/app.csproj", &["/temp/web.csproj", "/temp/worker.csproj"]),
+                make_linked_project("/temp/web.csproj", &["/temp/core.csproj"]),
+                make_linked_project("/temp/worker.csproj", &["/temp/core.csproj"]),
+                make_linked_project("/temp/core.csproj", &["/temp/util.csproj"]),
+                make_linked_project("/temp/util.csproj", &[]),
+            ],
+            ..Default::default()
+        };
+
+        let counts = |name: &str| {
+            let proj = sln.projects.iter().find(|p| p.file_info.path.file_stem_as_str() == name).unwrap();
+            (
+                proj.get_child_projects(&sln).len(),
+                proj.get_parent_projects(&sln).len(),
+                proj.get_transitive_child_projects(&sln).len(),
+                proj.get_transitive_parent_projects(&sln).len(),
+            )
+        };
+
+        assert_eq!(counts("app"), (2, 0, 4, 0));
+        assert_eq!(counts("web"), (1, 1, 2, 1));
+        assert_eq!(counts("worker"), (1, 1, 2, 1));
+        assert_eq!(counts("core"), (1, 2, 1, 3));
+        assert_eq!(counts("util"), (0, 1, 0, 4));
+    }
+
+    #[test]
+    pub fn test_transitive_dependencies_with_cycle() {
+        let sln = Solution {
+            projects: vec![
+                make_linked_project("/temp/a.csproj", &["/temp/b.csproj"]),
+                make_linked_project("/temp/b.csproj", &["/temp/a.csproj"]),
+            ],
+            ..Default::default()
+        };
+
+        let a = &sln.projects[0];
+        let children = a.get_transitive_child_projects(&sln);
+        assert_eq!(children, vec![&sln.projects[1]]);
+        assert_eq!(a.get_transitive_parent_projects(&sln), vec![&sln.projects[1]]);
+    }
+
+    #[test]
+    pub fn test_graph_role() {
+        // app -> lib -> core, and tool on its own.
+        let sln = Solution {
+            projects: vec![
+                make_linked_project("/temp/app.csproj", &["/temp/lib.csproj"]),
+                make_linked_project("/temp/lib.csproj", &["/temp/core.csproj"]),
+                make_linked_project("/temp/core.csproj", &[]),
+                make_linked_project("/temp/tool.csproj", &[]),
+            ],
+            ..Default::default()
+        };
+
+        let roles = sln.projects.iter().map(|p| p.graph_role(&sln)).collect::<Vec<_>>();
+        assert_eq!(roles, vec![GraphRole::Root, GraphRole::Internal, GraphRole::Leaf, GraphRole::Isolated]);
+    }
+
+    #[test]
+    pub fn test_component() {
+        let sln = Solution {
+            file_info: FileInfo { path: PathBuf::from("/temp/big.sln"), ..Default::default() },
+            projects: vec![
+                make_linked_project("/temp/big.csproj", &[]),
+                make_linked_project("/temp/Core/Core.csproj", &[]),
+                make_linked_project("/temp/Web/Api/Api.csproj", &[]),
+                make_linked_project("/temp/Web/Ui/deep/Ui.csproj", &[]),
+                make_linked_project("/elsewhere/Shared/Shared.csproj", &[]),
+            ],
+            ..Default::default()
+        };
+
+        let components = sln.projects.iter().map(|p| p.component(&sln)).collect::<Vec<_>>();
+        assert_eq!(components, vec![".", "Core", "Web", "Web", ".."]);
+    }
+
+    #[test]
+    pub fn test_component_graph() {
+        use crate::graph::make_component_graph;
+
+        // Api -> Ui and Api -> Core cross components; Ui -> Core too, and
+        // Api -> Models stays within the Web component.
+        let sln = Solution {
+            file_info: FileInfo { path: PathBuf::from("/temp/big.sln"), ..Default::default() },
+            projects: vec![
+                make_linked_project("/temp/Core/Core.csproj", &[]),
+                make_linked_project("/temp/Web/Api/Api.csproj", &["/temp/Web/Ui/Ui.csproj", "/temp/Core/Core.csproj", "/temp/Web/Models/Models.csproj"]),
+                make_linked_project("/temp/Web/Ui/Ui.csproj", &["/temp/Core/Core.csproj"]),
+                make_linked_project("/temp/Web/Models/Models.csproj", &[]),
+                make_linked_project("/temp/Tools/Tool/Tool.csproj", &[]),
+            ],
+            ..Default::default()
+        };
+
+        let graph = make_component_graph(&sln);
+        let mut nodes = graph.node_weights().cloned().collect::<Vec<_>>();
+        nodes.sort();
+        assert_eq!(nodes, vec!["Core", "Tools", "Web"]);
+
+        assert_eq!(graph.edge_count(), 1);
+        let edge = graph.edge_indices().next().unwrap();
+        let (source, target) = graph.edge_endpoints(edge).unwrap();
+        assert_eq!(graph[source], "Web");
+        assert_eq!(graph[target], "Core");
+        assert_eq!(graph[edge], 2);
+    }
+
+    #[test]
+    pub fn test_solution_versions() {
+        use crate::io::MemoryFileLoader;
+
+        let sln_path = PathBuf::from("/temp/x.sln");
+        let mut file_loader = MemoryFileLoader::new();
+        file_loader.files.insert(sln_path.clone(), r#"
+Microsoft Visual Studio Solution File, Format Version 12.00
+# Visual Studio Version 16
+VisualStudioVersion = 16.0.29519.181
+MinimumVisualStudioVersion = 10.0.40219.1
+Global
+EndGlobal
+"#.to_owned());
+
+        let sln = Solution::new(&sln_path, &file_loader, &Configuration::default());
+        assert_eq!(sln.version, VisualStudioVersion::VS2019);
+        assert_eq!(sln.minimum_version, Some("10.0.40219.1".to_owned()));
+
+        file_loader.files.insert(sln_path.clone(), "# Visual Studio 15".to_owned());
+        let sln = Solution::new(&sln_path, &file_loader, &Configuration::default());
+        assert_eq!(sln.version, VisualStudioVersion::VS2017);
+        assert_eq!(sln.minimum_version, None);
+    }
+
+    #[test]
+    pub fn test_follow_external_refs() {
+        let root = make_temporary_directory().unwrap();
+        let scan_dir = root.path().join("scan");
+        let ext_dir = root.path().join("ext");
+        let lib_dir = root.path().join("lib");
+        for dir in &[&scan_dir, &ext_dir, &lib_dir] {
+            fs::create_dir_all(dir).unwrap();
+        }
+
+        // The sln references a project one directory above the scan root, which
+        // in turn references another project outside the root.
+        fs::write(scan_dir.join("app.sln"), r#""app.csproj" "..\ext\ext.csproj""#).unwrap();
+        fs::write(scan_dir.join("app.csproj"), r#"<Project Sdk="Microsoft.NET.Sdk"></Project>"#).unwrap();
+        fs::write(ext_dir.join("ext.csproj"), r#"<Project Sdk="Microsoft.NET.Sdk">
+            <ProjectReference Include="..\lib\lib.csproj" />
+            </Project>"#).unwrap();
+        fs::write(lib_dir.join("lib.csproj"), r#"<Project Sdk="Microsoft.NET.Sdk"></Project>"#).unwrap();
+
+        let mut config = Configuration {
+            input_directory: scan_dir.clone(),
+            ..Configuration::default()
+        };
+        let analysis = Analysis::new(&config).unwrap();
+        assert_eq!(analysis.projects().count(), 1);
+        assert_eq!(analysis.missing_projects().len(), 1);
+
+        config.follow_external_refs = true;
+        let analysis = Analysis::new(&config).unwrap();
+        assert!(analysis.missing_projects().is_empty());
+
+        let project = |name: &str| analysis.projects().find(|p| p.file_info.filename_as_str() == name).unwrap();
+        assert!(!project("app.csproj").is_external);
+        assert!(project("ext.csproj").is_external);
+        assert_eq!(project("ext.csproj").ownership, ProjectOwnership::Linked);
+
+        // The sln does not mention lib, so it cannot own it, but it does need it.
+        assert!(project("lib.csproj").is_external);
+        assert_eq!(analysis.unassociated_projects.len(), 1);
+        let required = analysis.transitively_required_projects().iter()
+            .map(|(_, proj)| proj.file_info.filename_as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(required, vec!["lib.csproj"]);
+
+        // Nothing above the ceiling is loaded.
+        config.external_refs_ceiling = Some(scan_dir.clone());
+        let analysis = Analysis::new(&config).unwrap();
+        assert_eq!(analysis.projects().count(), 1);
+
+        // A relative ceiling is relative to the scanned directory.
+        config.external_refs_ceiling = Some(PathBuf::from("../ext"));
+        let analysis = Analysis::new(&config).unwrap();
+        assert_eq!(analysis.projects().count(), 2);
+        config.external_refs_ceiling = Some(PathBuf::from(".."));
+        let analysis = Analysis::new(&config).unwrap();
+        assert_eq!(analysis.projects().count(), 3);
+    }
+
+    /// Captures log messages so that tests can check what was warned about. The logger
+    /// is global, so tests should only look for messages that mention their own files.
+    mod captured_log {
+        use log::{Log, Metadata, Record};
+        use std::sync::{Mutex, Once};
+
+        static MESSAGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        static INIT: Once = Once::new();
+
+        struct CapturingLogger;
+
+        impl Log for CapturingLogger {
+            fn enabled(&self, _metadata: &Metadata) -> bool {
+                true
+            }
+
+            fn log(&self, record: &Record) {
+                MESSAGES.lock().unwrap().push(record.args().to_string());
+            }
+
+            fn flush(&self) {}
+        }
+
+        pub fn init() {
+            INIT.call_once(|| {
+                log::set_logger(&CapturingLogger).unwrap();
+                log::set_max_level(log::LevelFilter::Warn);
+            });
+        }
+
+        pub fn any_contains(text: &str) -> bool {
+            MESSAGES.lock().unwrap().iter().any(|msg| msg.contains(text))
+        }
+    }
+
+    #[test]
+    pub fn test_suppress_orphan_warnings() {
+        captured_log::init();
+
+        let root = tempfile::Builder::new().prefix("dnlib-temp-").rand_bytes(5).tempdir().unwrap();
+        let app_dir = root.path().join("app");
+        let scratch_dir = root.path().join("scratch");
+        fs::create_dir_all(&app_dir).unwrap();
+        fs::create_dir_all(&scratch_dir).unwrap();
+        fs::write(app_dir.join("app.sln"), r#""app.csproj""#).unwrap();
+        fs::write(app_dir.join("app.csproj"), "").unwrap();
+        fs::write(scratch_dir.join("quiet.csproj"), "").unwrap();
+
+        let config = Configuration {
+            input_directory: root.path().to_owned(),
+            suppress_orphan_warnings: true,
+            ..Configuration::default()
+        };
+        let analysis = Analysis::new(&config).unwrap();
+
+        // The orphan is still recorded, it just isn't warned about.
+        assert_eq!(analysis.unassociated_projects.len(), 1);
+        assert_eq!(analysis.unassociated_projects[0].file_info.filename_as_str(), "quiet.csproj");
+        let quiet_path = scratch_dir.join("quiet.csproj");
+        assert!(!captured_log::any_contains(quiet_path.as_str()));
+
+        fs::rename(&quiet_path, scratch_dir.join("noisy.csproj")).unwrap();
+        let config = Configuration { suppress_orphan_warnings: false, ..config };
+        let analysis = Analysis::new(&config).unwrap();
+        assert_eq!(analysis.unassociated_projects.len(), 1);
+        assert!(captured_log::any_contains(scratch_dir.join("noisy.csproj").as_str()));
+    }
+
+    #[test]
+    pub fn test_file_info_modified() {
+        use std::time::{Duration, UNIX_EPOCH};
+        use crate::io::MemoryFileLoader;
+
+        let dir = make_temporary_directory().unwrap();
+        let path = dir.path().join("car.sln");
+        let mtime = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        File::options().write(true).open(&path).unwrap().set_modified(mtime).unwrap();
+
+        let fi = FileInfo::new(&path, &DiskFileLoader);
+        assert_eq!(fi.modified, Some(mtime));
+        assert_eq!(fi.modified_as_string(), "2020-09-13T12:26:40Z");
+
+        // The memory loader does not know about modification times.
+        let mut loader = MemoryFileLoader::new();
+        loader.files.insert(path.clone(), "".to_owned());
+        let fi = FileInfo::new(&path, &loader);
+        assert_eq!(fi.modified, None);
+        assert_eq!(fi.modified_as_string(), "");
+    }
+
+    #[test]
+    pub fn test_file_info_line_ending() {
+        use crate::io::MemoryFileLoader;
+
+        let mut loader = MemoryFileLoader::new();
+        let cases = [
+            ("crlf.csproj", "<Project>\r\n</Project>\r\n", LineEnding::Crlf),
+            ("lf.csproj", "<Project>\n</Project>\n", LineEnding::Lf),
+            ("mixed.csproj", "<Project>\r\n<PropertyGroup />\n</Project>\r\n", LineEnding::Mixed),
+            ("none.csproj", "<Project></Project>", LineEnding::None),
+            ("empty.csproj", "", LineEnding::None),
+        ];
+
+        for (filename, contents, _) in &cases {
+            loader.files.insert(PathBuf::from("/temp").join(filename), (*contents).to_owned());
+        }
+
+        for (filename, _, expected) in &cases {
+            let fi = FileInfo::new(PathBuf::from("/temp").join(filename), &loader);
+            assert_eq!(fi.line_ending, *expected, "{}", filename);
+        }
+    }
+
+    #[test]
+    pub fn test_solution_domain() {
+        use crate::io::MemoryFileLoader;
+
+        let configuration = Configuration {
+            solution_classifiers: vec![
+                PackageGroup::new("Billing", "^Billing\\."),
+                PackageGroup::new("Identity", "^(Identity|Auth)\\."),
+            ],
+            default_solution_domain: "Other".to_owned(),
+            ..Configuration::default()
+        };
+
+        let mut file_loader = MemoryFileLoader::new();
+        let mut domain_of = |path: &str| {
+            let path = PathBuf::from(path);
+            file_loader.files.insert(path.clone(), "".to_owned());
+            Solution::new(&path, &file_loader, &configuration).domain().to_owned()
+        };
+
+        assert_eq!(domain_of("/temp/Billing.Api.sln"), "Billing");
+        assert_eq!(domain_of("/temp/Auth.Service.sln"), "Identity");
+        assert_eq!(domain_of("/temp/IdentityServer.sln"), "Other");
+        assert_eq!(domain_of("/temp/Reporting.sln"), "Other");
+
+        let sln = Solution::new(PathBuf::from("/temp/Billing.Api.sln"), &MemoryFileLoader::new(), &Configuration::default());
+        assert_eq!(sln.domain(), "Unclassified");
+    }
+
+    #[test]
+    pub fn test_missing_mentioned_projects() {
+        use crate::io::MemoryFileLoader;
+
+        let sln_path = PathBuf::from("/temp/x.sln");
+        let mut file_loader = MemoryFileLoader::new();
+        file_loader.files.insert(sln_path.clone(), r#"
+Project("{FAE04EC0-301F-11D3-BF4B-00C04F79EFBC}") = "Ford", "Ford.csproj", "{1}"
+Project("{FAE04EC0-301F-11D3-BF4B-00C04F79EFBC}") = "Toyota", "sub\toyota.csproj", "{2}"
+Project("{FAE04EC0-301F-11D3-BF4B-00C04F79EFBC}") = "Deleted", "deleted\Deleted.csproj", "{3}"
+Project("{FAE04EC0-301F-11D3-BF4B-00C04F79EFBC}") = "Shared", "..\shared\Shared.csproj", "{4}"
+"#.to_owned());
+        let sln = Solution::new(&sln_path, &file_loader, &Configuration::default());
+
+        let found = vec!["/temp/ford.csproj", "/temp/sub/toyota.csproj", "/shared/Shared.csproj"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect::<HashSet<_>>();
+
+        let missing = sln.missing_mentioned_projects(&found);
+        assert_eq!(missing.len(), 1);
+        assert!(missing[0].ends_with("Deleted.csproj"));
+
+        assert_eq!(sln.missing_mentioned_projects(&HashSet::new()).len(), 4);
+    }
+
+    #[test]
+    pub fn test_case_mismatched_projects() {
+        use crate::io::MemoryFileLoader;
+
+        let sln_path = PathBuf::from("/temp/x.sln");
+        let mut file_loader = MemoryFileLoader::new();
+        file_loader.files.insert(sln_path.clone(), r#"
+Project("{FAE04EC0-301F-11D3-BF4B-00C04F79EFBC}") = "App1", "App1\App1.csproj", "{1}"
+Project("{FAE04EC0-301F-11D3-BF4B-00C04F79EFBC}") = "Toyota", "sub\toyota.csproj", "{2}"
+Project("{FAE04EC0-301F-11D3-BF4B-00C04F79EFBC}") = "Deleted", "deleted\Deleted.csproj", "{3}"
+"#.to_owned());
+        let sln = Solution::new(&sln_path, &file_loader, &Configuration::default());
+
+        let found = vec!["/temp/app1/app1.csproj", "/temp/sub/toyota.csproj"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect::<Vec<_>>();
+
+        let mismatches = sln.case_mismatched_projects(&found);
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].0.ends_with("App1/App1.csproj"));
+        assert_eq!(mismatches[0].1, &PathBuf::from("/temp/app1/app1.csproj"));
+
+        let found = vec![PathBuf::from("/temp/App1/App1.csproj")];
+        assert!(sln.case_mismatched_projects(&found).is_empty());
+    }
+
+    #[test]
+    pub fn test_shared_output_paths() {
+        let make_project = |path: &str, output_path: Option<&str>| Project {
+            file_info: FileInfo { path: PathBuf::from(path), ..Default::default() },
+            output_path: output_path.map(|p| p.to_owned()),
+            ..Default::default()
+        };
+
+        let sln = Solution {
+            projects: vec![
+                make_project("/temp/app/app.csproj", Some(r"..\build\Debug\")),
+                make_project("/temp/lib/lib.csproj", Some(r"..\Build\Debug")),
+                make_project("/temp/web/web.csproj", Some(r"bin\Debug\")),
+                make_project("/temp/core/core.csproj", None),
+            ],
+            ..Default::default()
+        };
+
+        let mut analysis = Analysis {
+            solution_directories: vec![SolutionDirectory { solutions: vec![sln], ..Default::default() }],
+            ..Default::default()
+        };
+
+        fn names(analysis: &Analysis) -> Vec<(String, Vec<&str>)> {
+            analysis.shared_output_paths().into_iter()
+                .map(|(path, projects)| (path, projects.iter().map(|p| p.file_info.filename_as_str()).collect()))
+                .collect()
+        }
+
+        analysis.case_sensitive_paths = false;
+        assert_eq!(names(&analysis), vec![("/temp/build/Debug".to_owned(), vec!["app.csproj", "lib.csproj"])]);
+
+        analysis.case_sensitive_paths = true;
+        assert!(names(&analysis).is_empty());
+    }
+
+    #[test]
+    pub fn shared_output_paths_expands_directory_properties() {
+        let make_project = |path: &str, output_path: &str| Project {
+            file_info: FileInfo { path: PathBuf::from(path), ..Default::default() },
+            output_path: Some(output_path.to_owned()),
+            ..Default::default()
+        };
+
+        let sln = Solution {
+            file_info: FileInfo { path: PathBuf::from("/temp/acme.sln"), ..Default::default() },
+            projects: vec![
+                make_project("/temp/app/app.csproj", r"$(SolutionDir)bin"),
+                make_project("/temp/lib/lib.csproj", r"$(SolutionDir)\bin"),
+                make_project("/temp/web/web.csproj", r"$(ProjectDir)..\bin"),
+                make_project("/temp/core/core.csproj", r"$(OutDir)"),
+            ],
+            ..Default::default()
+        };
+
+        let analysis = Analysis {
+            solution_directories: vec![SolutionDirectory { solutions: vec![sln], ..Default::default() }],
+            // Not in a solution, so its $(SolutionDir) is unknown.
+            unassociated_projects: vec![make_project("/temp/stray/stray.csproj", r"$(SolutionDir)bin")],
+            case_sensitive_paths: true,
+            ..Default::default()
+        };
+
+        let shared = analysis.shared_output_paths().into_iter()
+            .map(|(path, projects)| (path, projects.iter().map(|p| p.file_info.filename_as_str()).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        assert_eq!(shared, vec![("/temp/bin".to_owned(), vec!["app.csproj", "lib.csproj", "web.csproj"])]);
+    }
+
+    #[test]
+    pub fn package_class_breakdown_works() {
+        let make_proj = |path: &str, packages: &[(&str, &str, &str)]| {
+            let mut proj = make_project(path, OutputType::Library);
+            proj.packages = packages.iter()
+                .map(|(name, version, class)| Package::new(*name, *version, false, *class))
+                .collect();
+            proj
+        };
+
+        let sln = Solution {
+            projects: vec![
+                make_proj("/temp/web.csproj", &[
+                    ("Acme.Core", "1.0.0", "Ours"),
+                    ("Microsoft.Extensions.Logging", "6.0.0", "Microsoft"),
+                    ("Serilog", "2.10.0", "Third Party"),
+                ]),
+                // Other versions, or other cases, of the same package are not counted again.
+                make_proj("/temp/core.csproj", &[
+                    ("acme.core", "2.0.0", "Ours"),
+                    ("Microsoft.Extensions.Logging", "5.0.0", "Microsoft"),
+                    ("System.Text.Json", "6.0.0", "Microsoft"),
+                ]),
+            ],
+            ..Default::default()
+        };
+
+        let breakdown = sln.package_class_breakdown();
+        assert_eq!(breakdown.len(), 3);
+        assert_eq!(breakdown["Ours"], 25.0);
+        assert_eq!(breakdown["Microsoft"], 50.0);
+        assert_eq!(breakdown["Third Party"], 25.0);
+
+        let no_packages = Solution { projects: vec![make_proj("/temp/empty.csproj", &[])], ..Default::default() };
+        assert!(no_packages.package_class_breakdown().is_empty());
+        assert!(Solution::default().package_class_breakdown().is_empty());
+    }
+
+    #[test]
+    pub fn test_migration_progress() {
+        let make_sln = |versions: &[ProjectVersion]| {
+            Solution {
+                projects: versions.iter().enumerate().map(|(i, version)| {
+                    let mut proj = make_project(&format!("/temp/p{}.csproj", i), OutputType::Library);
+                    proj.version = *version;
+                    proj
+                }).collect(),
+                ..Default::default()
+            }
+        };
+
+        let mixed = make_sln(&[ProjectVersion::MicrosoftNetSdk, ProjectVersion::MicrosoftNetSdkWeb, ProjectVersion::OldStyle, ProjectVersion::Unknown]);
+        assert_eq!(mixed.migration_progress(), 0.5);
+        assert!(mixed.partially_migrated());
+
+        let migrated = make_sln(&[ProjectVersion::MicrosoftNetSdk, ProjectVersion::MicrosoftNetSdkWeb]);
+        assert_eq!(migrated.migration_progress(), 1.0);
+        assert!(!migrated.partially_migrated());
+
+        let old = make_sln(&[ProjectVersion::OldStyle, ProjectVersion::OldStyle]);
+        assert_eq!(old.migration_progress(), 0.0);
+        assert!(!old.partially_migrated());
+
+        let empty = make_sln(&[]);
+        assert_eq!(empty.migration_progress(), 1.0);
+        assert!(!empty.partially_migrated());
+    }
+
+    #[test]
+    pub fn test_coverage_ratio() {
+        let make_sln = |path: &str, linked: usize, orphaned: usize| {
+            Solution {
+                file_info: FileInfo { path: PathBuf::from(path), ..Default::default() },
+                projects: (0..linked + orphaned).map(|i| {
+                    let mut proj = make_project(&format!("/temp/p{}.csproj", i), OutputType::Library);
+                    proj.ownership = if i < linked { ProjectOwnership::Linked } else { ProjectOwnership::Orphaned };
+                    proj
+                }).collect(),
+                ..Default::default()
+            }
+        };
+
+        let empty = make_sln("/temp/empty.sln", 0, 0);
+        let full = make_sln("/temp/full.sln", 4, 0);
+        let half = make_sln("/temp/half.sln", 2, 2);
+        let poor = make_sln("/temp/poor.sln", 1, 3);
+        let none = make_sln("/temp/none.sln", 0, 2);
+
+        assert_eq!(empty.coverage_ratio(), 1.0);
+        assert_eq!(full.coverage_ratio(), 1.0);
+        assert_eq!(half.coverage_ratio(), 0.5);
+        assert_eq!(poor.coverage_ratio(), 0.25);
+        assert_eq!(none.coverage_ratio(), 0.0);
+
+        // Transitively required projects are not in the solution's directory tree.
+        let mut with_required = make_sln("/temp/required.sln", 1, 1);
+        with_required.projects[0].ownership = ProjectOwnership::TransitivelyRequired;
+        assert_eq!(with_required.coverage_ratio(), 0.0);
+
+        let analysis = Analysis {
+            solution_directories: vec![SolutionDirectory {
+                solutions: vec![empty, full, half, poor, none],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let low = analysis.low_coverage_solutions(0.5).iter()
+            .map(|sln| sln.file_info.filename_as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(low, vec!["poor.sln", "none.sln"]);
+        assert_eq!(analysis.low_coverage_solutions(1.0).len(), 3);
+        assert!(analysis.low_coverage_solutions(0.0).is_empty());
+    }
+
+    #[test]
+    pub fn test_aggregate_packages() {
+        let make = |path: &str, packages: &[(&str, &str)]| {
+            let mut proj = make_project(path, OutputType::Library);
+            proj.packages = packages.iter()
+                .map(|(name, version)| Package::new(*name, *version, false, "Third Party"))
+                .collect();
+            proj
+        };
+
+        let sln1 = Solution {
+            projects: vec![
+                make("/temp/a.csproj", &[("Newtonsoft.Json", "12.0.1"), ("Serilog", "2.10.0")]),
+                make("/temp/b.csproj", &[("Newtonsoft.Json", "12.0.1")]),
+            ],
+            ..Default::default()
+        };
+        let sln2 = Solution {
+            projects: vec![
+                // Also in sln1, so it should only be counted once.
+                make("/temp/a.csproj", &[("Newtonsoft.Json", "12.0.1"), ("Serilog", "2.10.0")]),
+                make("/temp/c.csproj", &[("Newtonsoft.Json", "13.0.1"), ("Serilog", "2.10.0"), ("Serilog", "2.10.0")]),
+            ],
+            ..Default::default()
+        };
+        let sd = SolutionDirectory { solutions: vec![sln1, sln2], ..Default::default() };
+
+        let aggregated = sd.aggregate_packages().into_iter()
+            .map(|(pkg, count)| (pkg.name, pkg.version, count))
+            .collect::<Vec<_>>();
+        assert_eq!(aggregated, vec![
+            ("Newtonsoft.Json".to_owned(), "12.0.1".to_owned(), 2),
+            ("Newtonsoft.Json".to_owned(), "13.0.1".to_owned(), 1),
+            ("Serilog".to_owned(), "2.10.0".to_owned(), 2),
+        ]);
+
+        assert!(SolutionDirectory::default().aggregate_packages().is_empty());
+    }
+
+    #[test]
+    pub fn find_ci_config_searches_up_to_git_root() {
+        let root = tempfile::tempdir().unwrap();
+        let repo = root.path().join("repo");
+        let sln_dir = repo.join("src").join("app");
+        fs::create_dir_all(&sln_dir).unwrap();
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        assert!(!SolutionDirectory::find_ci_config(&sln_dir, root.path()));
+
+        // Above the git root, so it does not count.
+        fs::write(root.path().join("azure-pipelines.yml"), "").unwrap();
+        assert!(!SolutionDirectory::find_ci_config(&sln_dir, root.path()));
+
+        fs::create_dir_all(repo.join(".github").join("workflows")).unwrap();
+        assert!(SolutionDirectory::find_ci_config(&sln_dir, root.path()));
+    }
+
+    #[test]
+    pub fn find_pinned_sdk_version_uses_nearest_global_json() {
+        use crate::io::MemoryFileLoader;
+        let mut file_loader = MemoryFileLoader::new();
+        let root = PathBuf::from("/repo");
+        let sln_dir = root.join("src").join("app");
+        assert_eq!(SolutionDirectory::find_pinned_sdk_version(&sln_dir, &root, &file_loader), None);
+
+        file_loader.files.insert(root.join("global.json"), r#"{
+            // Comments are allowed.
+            "sdk": { "version": "6.0.100", "rollForward": "latestFeature" }
+        }"#.to_owned());
+        assert_eq!(SolutionDirectory::find_pinned_sdk_version(&sln_dir, &root, &file_loader), Some("6.0.100".to_owned()));
+
+        file_loader.files.insert(root.join("src").join("global.json"), r#"{ "msbuild-sdks": { "My.Sdk": "1.0.0" } }"#.to_owned());
+        assert_eq!(SolutionDirectory::find_pinned_sdk_version(&sln_dir, &root, &file_loader), None);
+    }
+
+    #[test]
+    pub fn find_pinned_sdk_version_stops_at_ceiling() {
+        use crate::io::MemoryFileLoader;
+        let mut file_loader = MemoryFileLoader::new();
+        file_loader.files.insert(PathBuf::from("/global.json"), r#"{ "sdk": { "version": "3.1.400" } }"#.to_owned());
+        let sln_dir = PathBuf::from("/repo/app");
+        assert_eq!(SolutionDirectory::find_pinned_sdk_version(&sln_dir, Path::new("/repo"), &file_loader), None);
+        assert_eq!(SolutionDirectory::find_pinned_sdk_version(&sln_dir, Path::new("/"), &file_loader), Some("3.1.400".to_owned()));
+    }
+
+    #[test]
+    pub fn find_editorconfig_covers_nested_directories() {
+        use crate::io::MemoryFileLoader;
+        let mut file_loader = MemoryFileLoader::new();
+        let root = PathBuf::from("/repo");
+        let nested_dir = root.join("src").join("app");
+        assert!(!SolutionDirectory::find_editorconfig(&nested_dir, &root, &file_loader));
+
+        file_loader.files.insert(root.join("src").join(".editorconfig"), "root = true".to_owned());
+        assert!(SolutionDirectory::find_editorconfig(&nested_dir, &root, &file_loader));
+        assert!(SolutionDirectory::find_editorconfig(&root.join("src"), &root, &file_loader));
+        assert!(!SolutionDirectory::find_editorconfig(&root.join("tools"), &root, &file_loader));
+
+        // Above the ceiling, so it does not count.
+        file_loader.files.insert(PathBuf::from("/.editorconfig"), "".to_owned());
+        assert!(!SolutionDirectory::find_editorconfig(&root.join("tools"), &root, &file_loader));
+    }
+
+    #[test]
+    pub fn find_readme_when_present() {
+        use crate::io::MemoryFileLoader;
+        let mut file_loader = MemoryFileLoader::new();
+        let sln_dir = PathBuf::from("/repo/app");
+        file_loader.files.insert(sln_dir.join("readme.MD"), "# App".to_owned());
+        assert!(SolutionDirectory::find_readme(&sln_dir, &file_loader));
+
+        let mut file_loader = MemoryFileLoader::new();
+        file_loader.files.insert(sln_dir.join("README.txt"), "App".to_owned());
+        assert!(SolutionDirectory::find_readme(&sln_dir, &file_loader));
+    }
+
+    #[test]
+    pub fn find_readme_when_absent() {
+        use crate::io::MemoryFileLoader;
+        let mut file_loader = MemoryFileLoader::new();
+        let sln_dir = PathBuf::from("/repo/app");
+        assert!(!SolutionDirectory::find_readme(&sln_dir, &file_loader));
+
+        // Only the solution directory itself counts.
+        file_loader.files.insert(PathBuf::from("/repo/README.md"), "# Repo".to_owned());
+        file_loader.files.insert(sln_dir.join("docs").join("README.md"), "# Docs".to_owned());
+        file_loader.files.insert(sln_dir.join("README.html"), "".to_owned());
+        assert!(!SolutionDirectory::find_readme(&sln_dir, &file_loader));
+    }
+
+    #[test]
+    pub fn find_ci_config_stops_at_ceiling() {
+        let root = tempfile::tempdir().unwrap();
+        let scan_dir = root.path().join("scan");
+        let sln_dir = scan_dir.join("app");
+        fs::create_dir_all(&sln_dir).unwrap();
+        fs::write(root.path().join(".gitlab-ci.yml"), "").unwrap();
+        assert!(!SolutionDirectory::find_ci_config(&sln_dir, &scan_dir));
+
+        fs::write(sln_dir.join(".gitlab-ci.yml"), "").unwrap();
+        assert!(SolutionDirectory::find_ci_config(&sln_dir, &scan_dir));
+    }
+
+    fn make_filter_analysis() -> Analysis {
+        let make_sln = |path: &str| {
+            let mut sln = Solution::default();
+            sln.file_info.path = PathBuf::from(path);
+            sln.projects.push(make_project(&path.replace(".sln", ".csproj"), OutputType::Library));
+            sln
+        };
+
+        Analysis {
+            solution_directories: vec![
+                SolutionDirectory {
+                    directory: PathBuf::from("/temp/a"),
+                    solutions: vec![make_sln("/temp/a/Car.sln"), make_sln("/temp/a/Bike.sln")],
+                    ..Default::default()
+                },
+                SolutionDirectory {
+                    directory: PathBuf::from("/temp/b"),
+                    solutions: vec![make_sln("/temp/b/Bike.sln"), make_sln("/temp/b/Bus.sln")],
+                    ..Default::default()
+                },
+            ],
+            unassociated_projects: vec![make_project("/temp/stray.csproj", OutputType::Library)],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    pub fn retain_solution_keeps_unique_match() {
+        let mut analysis = make_filter_analysis();
+        analysis.retain_solution("car").unwrap();
+        assert_eq!(analysis.solution_directories.len(), 1);
+        assert_eq!(analysis.solution_directories[0].solutions.len(), 1);
+        assert_eq!(analysis.solution_directories[0].solutions[0].file_info.path, PathBuf::from("/temp/a/Car.sln"));
+        assert_eq!(analysis.projects().count(), 1);
+
+        let mut analysis = make_filter_analysis();
+        analysis.retain_solution("Bus.sln").unwrap();
+        assert_eq!(analysis.solution_directories[0].directory, PathBuf::from("/temp/b"));
+    }
+
+    #[test]
+    pub fn retain_solution_errors_on_no_match() {
+        let mut analysis = make_filter_analysis();
+        match analysis.retain_solution("Train") {
+            Err(DnLibError::SolutionFilterError(msg)) => assert!(msg.contains("Train")),
+            r => panic!("Unexpected result {:?}", r),
+        }
+        assert_eq!(analysis.num_solutions(), 4);
+    }
+
+    #[test]
+    pub fn retain_solution_errors_on_ambiguous_match() {
+        let mut analysis = make_filter_analysis();
+        match analysis.retain_solution("bike") {
+            Err(DnLibError::SolutionFilterError(msg)) => {
+                assert!(msg.contains("/temp/a/Bike.sln"));
+                assert!(msg.contains("/temp/b/Bike.sln"));
+            }
+            r => panic!("Unexpected result {:?}", r),
+        }
+        assert_eq!(analysis.num_solutions(), 4);
+    }
+
+    #[test]
+    pub fn test_version_project_mismatch() {
+        let make = |path: &str, version: ProjectVersion| {
+            let mut proj = make_project(path, OutputType::Library);
+            proj.version = version;
+            proj.ownership = ProjectOwnership::Linked;
+            proj
+        };
+
+        let sln = Solution {
+            version: VisualStudioVersion::extract("# Visual Studio 14").unwrap(),
+            projects: vec![make("/temp/a.csproj", ProjectVersion::OldStyle), make("/temp/b.csproj", ProjectVersion::MicrosoftNetSdk)],
+            ..Default::default()
+        };
+        assert!(sln.version_project_mismatch());
+
+        let sln = Solution {
+            version: VisualStudioVersion::extract("# Visual Studio 14").unwrap(),
+            projects: vec![make("/temp/a.csproj", ProjectVersion::OldStyle)],
+            ..Default::default()
+        };
+        assert!(!sln.version_project_mismatch());
+
+        let sln = Solution {
+            version: VisualStudioVersion::extract("# Visual Studio 15").unwrap(),
+            projects: vec![make("/temp/b.csproj", ProjectVersion::MicrosoftNetSdkWeb)],
+            ..Default::default()
+        };
+        assert!(!sln.version_project_mismatch());
+    }
+
+    #[test]
+    pub fn test_lang_version_consistent() {
+        let make = |path: &str, lang_version: Option<&str>| {
+            let mut proj = make_project(path, OutputType::Library);
+            proj.lang_version = lang_version.map(|lv| lv.to_owned());
+            proj
+        };
+
+        let sln = Solution {
+            projects: vec![make("/temp/a.csproj", Some("8.0")), make("/temp/b.csproj", Some("8.0"))],
+            ..Default::default()
+        };
+        assert!(sln.lang_version_consistent());
+        assert_eq!(sln.distinct_lang_versions(), vec![Some("8.0")]);
+
+        let sln = Solution {
+            projects: vec![make("/temp/a.csproj", Some("8.0")), make("/temp/b.csproj", Some("latest"))],
+            ..Default::default()
+        };
+        assert!(!sln.lang_version_consistent());
+        assert_eq!(sln.distinct_lang_versions(), vec![Some("8.0"), Some("latest")]);
+
+        // Unspecified is treated as a version in its own right.
+        let sln = Solution {
+            projects: vec![make("/temp/a.csproj", Some("8.0")), make("/temp/b.csproj", None)],
+            ..Default::default()
+        };
+        assert!(!sln.lang_version_consistent());
+        assert_eq!(sln.distinct_lang_versions(), vec![None, Some("8.0")]);
+    }
+
+    #[test]
+    pub fn test_root_namespace_collisions() {
+        let mut p1 = make_project("/temp/a/Foo.Core.csproj", OutputType::Library);
+        p1.root_namespace = Some("Foo".to_owned());
+        let mut p2 = make_project("/temp/b/Foo.Web.csproj", OutputType::Library);
+        p2.root_namespace = Some("Foo".to_owned());
+        // Defaults to the project name.
+        let p3 = make_project("/temp/c/Bar.csproj", OutputType::Library);
+
+        let sln = Solution { projects: vec![p1, p2, p3], ..Default::default() };
+        let analysis = Analysis {
+            solution_directories: vec![SolutionDirectory { solutions: vec![sln], ..Default::default() }],
+            ..Default::default()
+        };
+
+        let collisions = analysis.root_namespace_collisions();
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].0, "Foo");
+        let names = collisions[0].1.iter().map(|p| p.file_info.path.file_stem_as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["Foo.Core", "Foo.Web"]);
+    }
+
+    #[test]
+    pub fn test_package_shadowing_projects() {
+        let mut app = make_project("/temp/app/App.csproj", OutputType::Exe);
+        app.packages = vec![
+            Package::new("acme.core", "1.2.0", false, "Ours"),
+            Package::new("Newtonsoft.Json", "12.0.1", false, "Third Party"),
+        ];
+        // The assembly name, not the file name, is what the package is built from.
+        let mut core = make_project("/temp/core/Core.csproj", OutputType::Library);
+        core.assembly_name = Some("Acme.Core".to_owned());
+        // A project whose file name matches a package it references is not a shadow of itself.
+        let mut json = make_project("/temp/json/Newtonsoft.Json.csproj", OutputType::Library);
+        json.packages = vec![Package::new("Newtonsoft.Json", "12.0.1", false, "Third Party")];
+
+        let sln = Solution { projects: vec![app, core, json], ..Default::default() };
+        let analysis = Analysis {
+            solution_directories: vec![SolutionDirectory { solutions: vec![sln], ..Default::default() }],
+            ..Default::default()
+        };
+
+        let shadows = analysis.package_shadowing_projects().into_iter()
+            .map(|(proj, pkg, source)| (proj.file_info.filename_as_str(), pkg.name.as_str(), source.file_info.filename_as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(shadows, vec![
+            ("App.csproj", "Newtonsoft.Json", "Newtonsoft.Json.csproj"),
+            ("App.csproj", "acme.core", "Core.csproj"),
+        ]);
+    }
+
+    #[test]
+    pub fn test_packages_by_class() {
+        let mut p1 = make_project("/temp/a.csproj", OutputType::Library);
+        p1.packages = vec![
+            Package::new("Newtonsoft.Json", "12.0.1", false, "Third Party"),
+            Package::new("System.Memory", "4.5.0", false, "Microsoft"),
+        ];
+        let mut p2 = make_project("/temp/b.csproj", OutputType::Library);
+        p2.packages = vec![
+            Package::new("Newtonsoft.Json", "12.0.1", false, "Third Party"),
+            Package::new("Newtonsoft.Json", "13.0.1", false, "Third Party"),
+            Package::new("Acme.Core", "1.0.0", false, "Ours"),
+        ];
+
+        let sln = Solution { projects: vec![p1, p2], ..Default::default() };
+        let analysis = Analysis {
+            solution_directories: vec![SolutionDirectory { solutions: vec![sln], ..Default::default() }],
+            ..Default::default()
+        };
+
+        let groups = analysis.packages_by_class();
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups["Ours"], vec![&Package::new("Acme.Core", "1.0.0", false, "Ours")]);
+        assert_eq!(groups["Microsoft"], vec![&Package::new("System.Memory", "4.5.0", false, "Microsoft")]);
+        assert_eq!(groups["Third Party"], vec![
+            &Package::new("Newtonsoft.Json", "12.0.1", false, "Third Party"),
+            &Package::new("Newtonsoft.Json", "13.0.1", false, "Third Party"),
+        ]);
+    }
+
+    #[test]
+    pub fn test_test_framework_histogram() {
+        let make = |path: &str, test_framework: TestFramework| {
+            let mut proj = make_project(path, OutputType::Library);
+            proj.test_framework = test_framework;
+            proj
+        };
+
+        let sln = Solution {
+            projects: vec![
+                make("/temp/a.csproj", TestFramework::XUnit),
+                make("/temp/b.csproj", TestFramework::NUnit),
+                make("/temp/c.csproj", TestFramework::XUnit),
+                make("/temp/d.csproj", TestFramework::MSTest),
+                make("/temp/e.csproj", TestFramework::None),
+                make("/temp/f.csproj", TestFramework::None),
+            ],
+            ..Default::default()
+        };
+        let analysis = Analysis {
+            solution_directories: vec![SolutionDirectory { solutions: vec![sln], ..Default::default() }],
+            ..Default::default()
+        };
+
+        assert_eq!(analysis.test_framework_histogram(), vec![
+            (TestFramework::MSTest, 1),
+            (TestFramework::XUnit, 2),
+            (TestFramework::NUnit, 1),
+        ]);
+        assert_eq!(analysis.num_non_test_projects(), 2);
+    }
+
+    #[test]
+    pub fn num_projects_allowing_unsafe_code_works() {
+        let make = |path: &str, allows_unsafe_code: bool| {
+            let mut proj = make_project(path, OutputType::Library);
+            proj.allows_unsafe_code = allows_unsafe_code;
+            proj
+        };
+
+        let sln = Solution {
+            projects: vec![make("/temp/a.csproj", true), make("/temp/b.csproj", false)],
+            ..Default::default()
+        };
+        let analysis = Analysis {
+            solution_directories: vec![SolutionDirectory { solutions: vec![sln], ..Default::default() }],
+            unassociated_projects: vec![make("/temp/c.csproj", true)],
+            ..Default::default()
+        };
+
+        assert_eq!(analysis.num_projects_allowing_unsafe_code(), 2);
+    }
+
+    #[test]
+    pub fn test_output_type_histogram() {
+        let sln = Solution {
+            projects: vec![
+                make_project("/temp/a.csproj", OutputType::Library),
+                make_project("/temp/b.csproj", OutputType::Exe),
+                make_project("/temp/c.csproj", OutputType::Library),
+                make_project("/temp/d.csproj", OutputType::WinExe),
+            ],
+            ..Default::default()
+        };
+
+        let analysis = Analysis {
+            solution_directories: vec![SolutionDirectory { solutions: vec![sln], ..Default::default() }],
+            unassociated_projects: vec![make_project("/other/e.csproj", OutputType::Library)],
+            ..Default::default()
+        };
+
+        assert_eq!(analysis.output_type_histogram(), vec![
+            (OutputType::Library, 3),
+            (OutputType::WinExe, 1),
+            (OutputType::Exe, 1),
+        ]);
+
+        assert!(Analysis::default().output_type_histogram().is_empty());
+    }
+
+    #[test]
+    pub fn test_compare_package_versions() {
+        assert_eq!(compare_package_versions("1.2.3", "1.2.3"), Ordering::Equal);
+        assert_eq!(compare_package_versions("1.2", "1.2.0"), Ordering::Equal);
+        assert_eq!(compare_package_versions("1.10.0", "1.9.0"), Ordering::Greater);
+        assert_eq!(compare_package_versions("2.0.0", "11.0.2"), Ordering::Less);
+        assert_eq!(compare_package_versions("4.0.1-beta2", "4.0.1"), Ordering::Less);
+        assert_eq!(compare_package_versions("4.0.1-beta.10", "4.0.1-beta.2"), Ordering::Greater);
+    }
+
+    #[test]
+    pub fn test_potential_package_downgrades() {
+        // app -> lib -> core. App pins an older Newtonsoft than core needs, but
+        // a newer Serilog than lib uses, which is fine.
+        let make = |path: &str, children: &[&str], packages: &[(&str, &str)]| {
+            let mut proj = make_linked_project(path, children);
+            proj.packages = packages.iter()
+                .map(|(name, version)| Package::new(*name, *version, false, "Third Party"))
+                .collect();
+            proj
+        };
+
+        let sln = Solution {
+            projects: vec![
+                make("/temp/app.csproj", &["/temp/lib.csproj"], &[("Newtonsoft.Json", "11.0.2"), ("Serilog", "2.10.0")]),
+                make("/temp/lib.csproj", &["/temp/core.csproj"], &[("Serilog", "2.9.0")]),
+                make("/temp/core.csproj", &[], &[("newtonsoft.json", "12.0.1")]),
+            ],
+            ..Default::default()
+        };
+        let analysis = Analysis {
+            solution_directories: vec![SolutionDirectory { solutions: vec![sln], ..Default::default() }],
+            ..Default::default()
+        };
+
+        let downgrades = analysis.potential_package_downgrades();
+        assert_eq!(downgrades.len(), 1);
+        assert_eq!(downgrades[0].parent.file_info.path, PathBuf::from("/temp/app.csproj"));
+        assert_eq!(downgrades[0].child.file_info.path, PathBuf::from("/temp/core.csproj"));
+        assert_eq!(downgrades[0].package_name, "Newtonsoft.Json");
+        assert_eq!(downgrades[0].parent_version, "11.0.2");
+        assert_eq!(downgrades[0].child_version, "12.0.1");
+    }
+
+    #[test]
+    pub fn test_package_consolidation_report() {
+        let make = |path: &str, packages: &[(&str, &str)]| {
+            let mut proj = make_project(path, OutputType::Library);
+            proj.packages = packages.iter()
+                .map(|(name, version)| Package::new(*name, *version, false, "Third Party"))
+                .collect();
+            proj
+        };
+
+        let sln = Solution {
+            projects: vec![
+                make("/temp/a.csproj", &[("Newtonsoft.Json", "9.0.1"), ("Serilog", "2.10.0")]),
+                make("/temp/b.csproj", &[("Newtonsoft.Json", "12.0.1")]),
+                make("/temp/c.csproj", &[("newtonsoft.json", "12.0.1-beta1")]),
+                // Counts at its highest version, so is not behind.
+                make("/temp/d.csproj", &[("Newtonsoft.Json", "11.0.2"), ("Newtonsoft.Json", "12.0.1")]),
+                make("/temp/e.csproj", &[("Serilog", "")]),
+            ],
+            ..Default::default()
+        };
+        let analysis = Analysis {
+            solution_directories: vec![SolutionDirectory { solutions: vec![sln], ..Default::default() }],
+            unassociated_projects: vec![make("/temp/f.csproj", &[("Newtonsoft.Json", "10.0.3")])],
+            ..Default::default()
+        };
+
+        let report = analysis.package_consolidation_report();
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].package_name.to_lowercase(), "newtonsoft.json");
+        assert_eq!(report[0].max_version, "12.0.1");
+        assert_eq!(report[0].num_projects, 5);
+        assert_eq!(report[0].num_laggards, 3);
+        assert_eq!(report[1], PackageConsolidation { package_name: "Serilog", max_version: "2.10.0", num_projects: 1, num_laggards: 0 });
+    }
+
+    #[test]
+    pub fn test_transitively_required_projects() {
+        let root = tempfile::Builder::new().prefix("dnlib-temp-").rand_bytes(5).tempdir().unwrap();
+        let root_dir = root.path();
+
+        fs::write(root_dir.join("app.sln"), r#""app.csproj""#).unwrap();
+        fs::write(root_dir.join("app.csproj"), r#"<ProjectReference Include="lib\lib.csproj" />"#).unwrap();
+
+        // lib is not in the sln, but app needs it, and it in turn needs core.
+        fs::create_dir_all(root_dir.join("lib")).unwrap();
+        fs::write(root_dir.join("lib").join("lib.csproj"), r#"<ProjectReference Include="..\core\core.csproj" />"#).unwrap();
+        fs::create_dir_all(root_dir.join("core")).unwrap();
+        fs::write(root_dir.join("core").join("core.csproj"), "").unwrap();
+
+        // Nothing refers to this one, so it is just an orphan.
+        fs::write(root_dir.join("unused.csproj"), "").unwrap();
+
+        let config = Configuration {
+            input_directory: root_dir.to_owned(),
+            ..Configuration::default()
+        };
+        let analysis = Analysis::new(&config).unwrap();
+
+        let required = analysis.transitively_required_projects().iter()
+            .map(|(sln, proj)| (sln.file_info.filename_as_str(), proj.file_info.filename_as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(required, vec![("app.sln", "core.csproj"), ("app.sln", "lib.csproj")]);
+
+        let sln = &analysis.solution_directories[0].solutions[0];
+        let ownership = |name: &str| sln.projects.iter()
+            .find(|p| p.file_info.filename_as_str() == name)
+            .unwrap()
+            .ownership;
+        assert_eq!(ownership("app.csproj"), ProjectOwnership::Linked);
+        assert_eq!(ownership("lib.csproj"), ProjectOwnership::TransitivelyRequired);
+        assert_eq!(ownership("core.csproj"), ProjectOwnership::TransitivelyRequired);
+        assert_eq!(ownership("unused.csproj"), ProjectOwnership::Orphaned);
+    }
+
+    #[test]
+    pub fn test_single_solution_file() {
+        let temp_files = make_temporary_directory().unwrap();
+        let root_dir = temp_files.path();
+
+        let config = Configuration {
+            input_directory: root_dir.join("trucks").join("truck.sln"),
+            ..Configuration::default()
+        };
+        let analysis = Analysis::new(&config).unwrap();
+
+        assert_eq!(analysis.root_path, root_dir.join("trucks"));
+        assert_eq!(analysis.num_solutions(), 1);
+        let sln = &analysis.solution_directories[0].solutions[0];
+        assert_eq!(sln.file_info.filename_as_str(), "truck.sln");
+
+        let projects = analysis.projects()
+            .map(|p| p.file_info.filename_as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(projects, vec!["volvo.csproj"]);
+
+        // The car sln is at the root, so the walk finds the truck projects too,
+        // but they must not be attributed to it.
+        let config = Configuration {
+            input_directory: root_dir.join("car.sln"),
+            ..Configuration::default()
+        };
+        let analysis = Analysis::new(&config).unwrap();
+
+        assert_eq!(analysis.num_solutions(), 1);
+        let projects = analysis.projects()
+            .map(|p| p.file_info.filename_as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(projects, vec!["ford.csproj", "toyota.csproj"]);
+    }
+
+    #[test]
+    pub fn test_unreferenced_projects() {
+        let temp_files = make_temporary_directory().unwrap();
+        let root_dir = temp_files.path();
+
+        // A project in a directory tree with no solution anywhere near it.
+        let lonely_dir = root_dir.join("misc").join("tools");
+        fs::create_dir_all(&lonely_dir).unwrap();
+        File::create(lonely_dir.join("lonely.csproj")).unwrap();
+
+        let config = Configuration {
+            input_directory: root_dir.to_owned(),
+            ..Configuration::default()
+        };
+        let analysis = Analysis::new(&config).unwrap();
+
+        assert_eq!(analysis.unassociated_projects.len(), 1);
+        assert_eq!(analysis.unassociated_projects[0].file_info.path.filename_as_str(), "lonely.csproj");
+
+        let unreferenced = analysis.unreferenced_projects().iter()
+            .map(|p| p.file_info.path.filename_as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(unreferenced, vec!["bmw.csproj", "lonely.csproj", "mercedes.csproj", "renault.csproj"]);
+    }
+}
+
+#[cfg(test)]
+ mod project_tests {
+    use super::*;
+    use crate::io::MemoryFileLoader;
+
+    #[derive(Default)]
+    struct ProjectBuilder {
+         csproj_contents: String,
+         project_version: ProjectVersion,
+         packages_config_contents: Option<String>,
+         app_config_contents: Option<String>,
+         directory_packages_props_contents: Option<String>,
+         directory_build_props_contents: Option<String>,
+         other_files: Vec<PathBuf>,
+         configuration: Configuration,
+     }
+
+    impl ProjectBuilder {
+        fn new<S>(csproj_contents: S) -> Self
+        where S: Into<String>
+        {
+            ProjectBuilder {
+                csproj_contents: csproj_contents.into(),
+                .. ProjectBuilder::default()
+            }
+        }
+
+        fn with_packages_config(mut self, packages_config_contents: &str) -> Self {
+            self.packages_config_contents = Some(packages_config_contents.to_owned());
+            self
+        }
+
+        fn with_app_config(mut self, app_config_contents: &str) -> Self {
+            self.app_config_contents = Some(app_config_contents.to_owned());
+            self
+        }
+
+        fn with_directory_packages_props(mut self, contents: &str) -> Self {
+            self.directory_packages_props_contents = Some(contents.to_owned());
+            self
+        }
+
+        fn with_directory_build_props(mut self, contents: &str) -> Self {
+            self.directory_build_props_contents = Some(contents.to_owned());
+            self
+        }
+
+        fn with_other_file(mut self, filename: &str) -> Self {
+            self.other_files.push(PathBuf::from("/temp").join(filename));
+            self
+        }
+
+        fn with_configuration(mut self, configuration: Configuration) -> Self {
+            self.configuration = configuration;
+            self
         }
 
         fn web(mut self) -> Self {
@@ -1083,7 +4278,23 @@ mod analysis_tests {
                 file_loader.files.insert(pc_path, pcc);
             }
 
-            Project::new(&project_path, self.other_files, &file_loader, &Configuration::default())
+            if let Some(acc) = self.app_config_contents {
+                let ac_path = PathBuf::from("/temp/app.config");
+                self.other_files.push(ac_path.clone());
+                file_loader.files.insert(ac_path, acc);
+            }
+
+            // Directory.Packages.props is not an 'other' file, it is looked for in the directory
+            // tree, so put it in the root to make sure it is found from any directory.
+            if let Some(dpp) = self.directory_packages_props_contents {
+                file_loader.files.insert(PathBuf::from("/Directory.Packages.props"), dpp);
+            }
+
+            if let Some(dbp) = self.directory_build_props_contents {
+                file_loader.files.insert(PathBuf::from("/Directory.Build.props"), dbp);
+            }
+
+            Project::new(&project_path, self.other_files, &file_loader, &self.configuration)
         }
 
         fn add_sdk_prolog(contents: &str) -> String {
@@ -1145,6 +4356,131 @@ mod analysis_tests {
         assert_eq!(project.xml_doc, XmlDoc::Both);
     }
 
+    #[test]
+    pub fn extract_root_namespace_works() {
+        let project = ProjectBuilder::new(r#""#).build();
+        assert_eq!(project.root_namespace, None);
+        assert_eq!(project.effective_root_namespace(), "x");
+
+        let project = ProjectBuilder::new(r#"blah<RootNamespace>SuperService.Client</RootNamespace>blah"#).old().build();
+        assert_eq!(project.root_namespace, Some("SuperService.Client".to_owned()));
+        assert_eq!(project.effective_root_namespace(), "SuperService.Client");
+    }
+
+    #[test]
+    pub fn extract_assembly_name_works() {
+        let project = ProjectBuilder::new(r#""#).build();
+        assert_eq!(project.assembly_name, None);
+        assert_eq!(project.effective_assembly_name(), "x");
+
+        let project = ProjectBuilder::new(r#"blah<AssemblyName>Acme.Core</AssemblyName>blah"#).sdk().build();
+        assert_eq!(project.assembly_name, Some("Acme.Core".to_owned()));
+        assert_eq!(project.effective_assembly_name(), "Acme.Core");
+    }
+
+    #[test]
+    pub fn folder_name_matches_assembly_works() {
+        let make_project = |path: &str, assembly_name: Option<&str>| {
+            let mut project = Project::default();
+            project.file_info.path = PathBuf::from(path);
+            project.assembly_name = assembly_name.map(|n| n.to_owned());
+            project
+        };
+
+        assert!(make_project("/src/Acme.Core/Acme.Core.csproj", None).folder_name_matches_assembly());
+        assert!(make_project("/src/acme.core/Core.csproj", Some("Acme.Core")).folder_name_matches_assembly());
+        assert!(!make_project("/src/Core/Core.csproj", Some("Acme.Core")).folder_name_matches_assembly());
+        assert!(!make_project("/src/Lib/Acme.Core.csproj", None).folder_name_matches_assembly());
+    }
+
+    #[test]
+    pub fn extract_project_guid_works() {
+        let project = ProjectBuilder::new(r#""#).build();
+        assert_eq!(project.project_guid, None);
+
+        let project = ProjectBuilder::new(r#"blah<ProjectGuid>{A1B2C3D4-0000-4DF5-91F0-309BEEBAD82D}</ProjectGuid>blah"#).old().build();
+        assert_eq!(project.project_guid, Some("a1b2c3d4-0000-4df5-91f0-309beebad82d".to_owned()));
+    }
+
+    #[test]
+    pub fn covered_by_editorconfig_works() {
+        use crate::io::MemoryFileLoader;
+
+        let contents = r#"<Project Sdk="Microsoft.NET.Sdk"></Project>"#;
+        let csproj_files = vec![
+            PathBuf::from("/repo/src/app/App.csproj"),
+            PathBuf::from("/repo/src/lib/core/Core.csproj"),
+            PathBuf::from("/repo/tools/Tool.csproj"),
+        ];
+        let mut file_loader = MemoryFileLoader::new();
+        for path in &csproj_files {
+            file_loader.files.insert(path.clone(), contents.to_owned());
+        }
+        file_loader.files.insert(PathBuf::from("/repo/src/.editorconfig"), "root = true".to_owned());
+        let configuration = Configuration { input_directory: PathBuf::from("/repo"), ..Configuration::default() };
+        let paths = PathsToAnalyze { csproj_files, ..PathsToAnalyze::default() };
+        let analysis = Analysis::from_paths(&configuration, paths, file_loader).unwrap();
+
+        let covered = |path: &str| analysis.projects().find(|proj| proj.file_info.path == Path::new(path)).unwrap().covered_by_editorconfig;
+        assert!(covered("/repo/src/app/App.csproj"));
+        assert!(covered("/repo/src/lib/core/Core.csproj"));
+        assert!(!covered("/repo/tools/Tool.csproj"));
+    }
+
+    #[test]
+    pub fn analyzer_rule_severities_come_from_the_editorconfig() {
+        use crate::io::MemoryFileLoader;
+
+        let mut file_loader = MemoryFileLoader::new();
+        file_loader.files.insert(PathBuf::from("/repo/src/app/App.csproj"), r#"<Project Sdk="Microsoft.NET.Sdk"></Project>"#.to_owned());
+        file_loader.files.insert(PathBuf::from("/repo/.editorconfig"), "root = true\n\n[*.cs]\n\
+            dotnet_diagnostic.CA1822.severity = error\n\
+            dotnet_diagnostic.CA2007.severity = none\n".to_owned());
+        let configuration = Configuration { input_directory: PathBuf::from("/repo"), ..Configuration::default() };
+        let paths = PathsToAnalyze { csproj_files: vec![PathBuf::from("/repo/src/app/App.csproj")], ..PathsToAnalyze::default() };
+        let analysis = Analysis::from_paths(&configuration, paths, file_loader).unwrap();
+
+        let project = analysis.projects().next().unwrap();
+        let severities = project.analyzer_rule_severities.iter()
+            .map(|(rule, severity)| (rule.as_str(), severity.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(severities, vec![("CA1822", "error"), ("CA2007", "none")]);
+    }
+
+    #[test]
+    pub fn stable_id_does_not_depend_on_path() {
+        use crate::io::MemoryFileLoader;
+
+        let contents = r#"<Project Sdk="Microsoft.NET.Sdk">
+            <AssemblyName>Acme.Core</AssemblyName>
+            <RootNamespace>Acme</RootNamespace>
+            <ProjectGuid>{A1B2C3D4-0000-4DF5-91F0-309BEEBAD82D}</ProjectGuid>
+            </Project>"#;
+        let mut file_loader = MemoryFileLoader::new();
+        file_loader.files.insert(PathBuf::from("/old/Core.csproj"), contents.to_owned());
+        file_loader.files.insert(PathBuf::from("/new/place/Acme.Core.csproj"), contents.to_owned());
+        let configuration = Configuration::default();
+
+        let before = Project::new("/old/Core.csproj", vec![], &file_loader, &configuration);
+        let after = Project::new("/new/place/Acme.Core.csproj", vec![], &file_loader, &configuration);
+        assert_eq!(before.stable_id(), "Acme.Core|Acme|a1b2c3d4-0000-4df5-91f0-309beebad82d");
+        assert_eq!(before.stable_id(), after.stable_id());
+    }
+
+    #[test]
+    pub fn stable_id_differs_for_different_projects() {
+        let project = ProjectBuilder::new(r#""#).sdk().build();
+        assert_eq!(project.stable_id(), "x|x");
+
+        let a = ProjectBuilder::new(r#"<AssemblyName>Acme.Core</AssemblyName>"#).sdk().build();
+        let b = ProjectBuilder::new(r#"<AssemblyName>Acme.Web</AssemblyName>"#).sdk().build();
+        assert_ne!(a.stable_id(), b.stable_id());
+
+        let a = ProjectBuilder::new(r#"<ProjectGuid>{11111111-0000-0000-0000-000000000000}</ProjectGuid>"#).old().build();
+        let b = ProjectBuilder::new(r#"<ProjectGuid>{22222222-0000-0000-0000-000000000000}</ProjectGuid>"#).old().build();
+        assert_ne!(a.stable_id(), b.stable_id());
+    }
+
     #[test]
     pub fn extract_tt_file_works() {
         let project = ProjectBuilder::new(r#""#).build();
@@ -1176,9 +4512,32 @@ mod analysis_tests {
         let project = ProjectBuilder::new(r#"blah<EmbedAllSources>true</EmbedAllSources>blah"#).build();
         assert!(!project.embedded_debugging);
 
-        let project = ProjectBuilder::new(r#"blah<DebugType>embedded</DebugType>blah"
-            <EmbedAllSources>true</EmbedAllSources>blah"#).sdk().build();
-        assert!(project.embedded_debugging);
+        let project = ProjectBuilder::new(r#"blah<DebugType>embedded</DebugType>blah"
+            <EmbedAllSources>true</EmbedAllSources>blah"#).sdk().build();
+        assert!(project.embedded_debugging);
+    }
+
+    #[test]
+    pub fn extract_debug_type_works() {
+        let project = ProjectBuilder::new(r#""#).sdk().build();
+        assert_eq!(project.debug_type, None);
+        assert!(project.produces_portable_pdb);
+
+        let project = ProjectBuilder::new(r#"blah<DebugType>portable</DebugType>blah"#).sdk().build();
+        assert_eq!(project.debug_type, Some("portable".to_owned()));
+        assert!(project.produces_portable_pdb);
+
+        let project = ProjectBuilder::new(r#"blah<DebugType>embedded</DebugType>blah"#).sdk().build();
+        assert_eq!(project.debug_type, Some("embedded".to_owned()));
+        assert!(project.produces_portable_pdb);
+
+        let project = ProjectBuilder::new(r#"blah<DebugType>full</DebugType>blah"#).sdk().build();
+        assert_eq!(project.debug_type, Some("full".to_owned()));
+        assert!(!project.produces_portable_pdb);
+
+        // Old-style projects never produce portable PDBs.
+        let project = ProjectBuilder::new(r#"blah<DebugType>portable</DebugType>blah"#).old().build();
+        assert!(!project.produces_portable_pdb);
     }
 
     #[test]
@@ -1211,6 +4570,226 @@ mod analysis_tests {
         assert!(!project.auto_generate_binding_redirects);
     }
 
+    #[test]
+    pub fn extract_lang_version_works() {
+        let project = ProjectBuilder::new(r#""#).sdk().build();
+        assert_eq!(project.lang_version, None);
+
+        let project = ProjectBuilder::new(r#"blah<LangVersion>latest</LangVersion>blah"#).sdk().build();
+        assert_eq!(project.lang_version, Some("latest".to_owned()));
+    }
+
+    #[test]
+    pub fn extract_package_metadata_when_all_set() {
+        let project = ProjectBuilder::new(r#"
+            <PropertyGroup>
+                <Authors>Jane Smith, John Doe</Authors>
+                <Company>Acme Corp.</Company>
+                <Copyright>Copyright &#169; Acme  2020</Copyright>
+            </PropertyGroup>"#).sdk().build();
+        assert_eq!(project.company.as_deref(), Some("Acme Corp."));
+        assert_eq!(project.authors.as_deref(), Some("Jane Smith, John Doe"));
+        assert_eq!(project.copyright.as_deref(), Some("Copyright &#169; Acme  2020"));
+    }
+
+    #[test]
+    pub fn extract_package_metadata_ignores_case() {
+        let project = ProjectBuilder::new(r#"<PropertyGroup><company>Acme Corp.</company><AUTHORS>Jane Smith</AUTHORS></PropertyGroup>"#).sdk().build();
+        assert_eq!(project.company.as_deref(), Some("Acme Corp."));
+        assert_eq!(project.authors.as_deref(), Some("Jane Smith"));
+        assert_eq!(project.copyright, None);
+    }
+
+    #[test]
+    pub fn extract_package_metadata_when_none_set() {
+        let project = ProjectBuilder::new(r#"<PropertyGroup><PackageId>Acme</PackageId></PropertyGroup>"#).sdk().build();
+        assert_eq!(project.company, None);
+        assert_eq!(project.authors, None);
+        assert_eq!(project.copyright, None);
+    }
+
+    #[test]
+    pub fn extract_user_secrets_id_when_set() {
+        let project = ProjectBuilder::new(r#"<UserSecretsId>aspnet-Web-6A2B1F3C-0D4E-4F5A-9B8C-7D6E5F4A3B2C</UserSecretsId>"#).sdk().build();
+        assert_eq!(project.user_secrets_id.as_deref(), Some("aspnet-Web-6A2B1F3C-0D4E-4F5A-9B8C-7D6E5F4A3B2C"));
+    }
+
+    #[test]
+    pub fn extract_user_secrets_id_when_not_set() {
+        let project = ProjectBuilder::new(r#""#).sdk().build();
+        assert_eq!(project.user_secrets_id, None);
+
+        let project = ProjectBuilder::new(r#"<UserSecretsId> </UserSecretsId>"#).sdk().build();
+        assert_eq!(project.user_secrets_id, None);
+    }
+
+    #[test]
+    pub fn extract_output_path_works() {
+        let project = ProjectBuilder::new(r#""#).old().build();
+        assert_eq!(project.output_path, None);
+
+        let project = ProjectBuilder::new(r#"<OutputPath>..\build\Debug\</OutputPath><OutputPath>..\build\Release\</OutputPath>"#).old().build();
+        assert_eq!(project.output_path, Some(r"..\build\Debug\".to_owned()));
+
+        let project = ProjectBuilder::new(r#"<BaseOutputPath>..\artifacts</BaseOutputPath>"#).sdk().build();
+        assert_eq!(project.output_path, Some(r"..\artifacts".to_owned()));
+        assert_eq!(project.resolved_output_path(None), Some(PathBuf::from("/artifacts")));
+    }
+
+    #[test]
+    pub fn extract_platform_target_works() {
+        let project = ProjectBuilder::new(r#""#).build();
+        assert_eq!(project.platform_target, None);
+        assert!(!project.prefer_32bit);
+
+        let project = ProjectBuilder::new(r#"blah<PlatformTarget>x64</PlatformTarget>blah"#).build();
+        assert_eq!(project.platform_target, Some("x64".to_owned()));
+
+        let project = ProjectBuilder::new(r#"blah<PlatformTarget>AnyCPU</PlatformTarget>blah
+            <Prefer32Bit>true</Prefer32Bit>blah"#).build();
+        assert_eq!(project.platform_target, Some("AnyCPU".to_owned()));
+        assert!(project.prefer_32bit);
+
+        let project = ProjectBuilder::new(r#"blah<Prefer32Bit>false</Prefer32Bit>blah"#).build();
+        assert!(!project.prefer_32bit);
+    }
+
+    #[test]
+    pub fn extract_deterministic_works() {
+        let cases = [
+            ("", None),
+            ("blah<Deterministic>true</Deterministic>blah", Some(true)),
+            ("blah<Deterministic>True</Deterministic>blah", Some(true)),
+            ("blah<Deterministic>false</Deterministic>blah", Some(false)),
+            ("blah<Deterministic> false </Deterministic>blah", Some(false)),
+        ];
+
+        for (contents, expected) in &cases {
+            assert_eq!(ProjectBuilder::new(*contents).sdk().build().deterministic, *expected);
+            assert_eq!(ProjectBuilder::new(*contents).old().build().deterministic, *expected);
+        }
+    }
+
+    #[test]
+    pub fn extract_allows_unsafe_code_works() {
+        let cases = [
+            ("", false),
+            ("blah<AllowUnsafeBlocks>true</AllowUnsafeBlocks>blah", true),
+            ("blah<AllowUnsafeBlocks> TRUE </AllowUnsafeBlocks>blah", true),
+            ("blah<allowunsafeblocks>true</allowunsafeblocks>blah", true),
+            ("blah<AllowUnsafeBlocks>false</AllowUnsafeBlocks>blah", false),
+        ];
+
+        for (contents, expected) in &cases {
+            assert_eq!(ProjectBuilder::new(*contents).sdk().build().allows_unsafe_code, *expected, "{}", contents);
+        }
+    }
+
+    #[test]
+    pub fn extract_define_constants_works() {
+        let project = ProjectBuilder::new(r#""#).build();
+        assert!(project.define_constants.is_empty());
+
+        let project = ProjectBuilder::new(r#"blah<DefineConstants>DEBUG;TRACE;CUSTOM</DefineConstants>blah
+            <DefineConstants>TRACE</DefineConstants>blah
+            <DefineConstants>$(DefineConstants); OTHER </DefineConstants>blah"#).old().build();
+        assert_eq!(project.define_constants, vec!["CUSTOM", "DEBUG", "OTHER", "TRACE"]);
+    }
+
+    #[test]
+    pub fn extract_suppressed_analyzer_rules_works() {
+        let project = ProjectBuilder::new(r#""#).build();
+        assert!(project.suppressed_analyzer_rules.is_empty());
+
+        let project = ProjectBuilder::new(r#"blah<NoWarn>$(NoWarn);CA2000; CS0168</NoWarn>blah"#).build();
+        assert_eq!(project.suppressed_analyzer_rules, vec!["CA2000"]);
+
+        let project = ProjectBuilder::new(r#"blah<NoWarn>1591,ide0005</NoWarn>blah
+            <NoWarn>CA1822;CA2000</NoWarn>blah"#).old().build();
+        assert_eq!(project.suppressed_analyzer_rules, vec!["CA1822", "CA2000", "IDE0005"]);
+    }
+
+    #[test]
+    pub fn is_analyzer_rule_works() {
+        for code in &["CA2000", "IDE0005"] {
+            assert!(is_analyzer_rule(code), "{} should be an analyzer rule", code);
+        }
+
+        for code in &["CS0168", "1591", "CA", "CAX100", "NU1603", ""] {
+            assert!(!is_analyzer_rule(code), "{} should not be an analyzer rule", code);
+        }
+    }
+
+    #[test]
+    pub fn define_constants_for_distinguishes_configurations() {
+        let project = ProjectBuilder::new(r#"
+            <PropertyGroup>
+                <DefineConstants>COMMON</DefineConstants>
+            </PropertyGroup>
+            <PropertyGroup Condition=" '$(Configuration)|$(Platform)' == 'Debug|AnyCPU' ">
+                <DefineConstants>DEBUG;TRACE</DefineConstants>
+                <DocumentationFile>MyProject.xml</DocumentationFile>
+            </PropertyGroup>
+            <PropertyGroup Condition=" '$(Configuration)|$(Platform)' == 'Release|AnyCPU' ">
+                <DefineConstants>TRACE;OPTIMIZED</DefineConstants>
+            </PropertyGroup>"#).old().build();
+
+        assert_eq!(project.define_constants, vec!["COMMON", "DEBUG", "OPTIMIZED", "TRACE"]);
+        assert_eq!(project.define_constants_for("Debug"), vec!["COMMON", "DEBUG", "TRACE"]);
+        assert_eq!(project.define_constants_for("Release"), vec!["COMMON", "OPTIMIZED", "TRACE"]);
+        assert_eq!(project.xml_doc, XmlDoc::Debug);
+    }
+
+    #[test]
+    pub fn extract_xml_doc_uses_property_group_conditions() {
+        let project = ProjectBuilder::new(r#"
+            <PropertyGroup Condition="'$(Configuration)' == 'Release'">
+                <DocumentationFile>bin\Debug\MyProject.xml</DocumentationFile>
+            </PropertyGroup>"#).build();
+        assert_eq!(project.xml_doc, XmlDoc::Release);
+
+        let project = ProjectBuilder::new(r#"
+            <PropertyGroup>
+                <DocumentationFile>$(OutputPath)\MyProject.xml</DocumentationFile>
+            </PropertyGroup>"#).build();
+        assert_eq!(project.xml_doc, XmlDoc::Both);
+
+        let project = ProjectBuilder::new(r#"
+            <PropertyGroup Condition="'$(Configuration)' == 'Staging'">
+                <DocumentationFile>MyProject.xml</DocumentationFile>
+            </PropertyGroup>"#).build();
+        assert_eq!(project.xml_doc, XmlDoc::None);
+    }
+
+    #[test]
+    pub fn extract_fragile_reference_hint_paths_works() {
+        let project = ProjectBuilder::new(r#""#).old().build();
+        assert!(project.fragile_reference_hint_paths.is_empty());
+
+        // A GAC reference has no hint path at all.
+        let project = ProjectBuilder::new(r#"<Reference Include="System.Windows" />
+            <Reference Include="System.Data">
+                <Private>False</Private>
+            </Reference>"#).old().build();
+        assert!(project.fragile_reference_hint_paths.is_empty());
+
+        let project = ProjectBuilder::new(r#"<Reference Include="Newtonsoft.Json, Version=11.0.0.0, Culture=neutral">
+                <HintPath>..\packages\Newtonsoft.Json.11.0.2\lib\net45\Newtonsoft.Json.dll</HintPath>
+            </Reference>"#).old().build();
+        assert_eq!(project.fragile_reference_hint_paths, vec![r"..\packages\Newtonsoft.Json.11.0.2\lib\net45\Newtonsoft.Json.dll"]);
+
+        let project = ProjectBuilder::new(r#"<Reference Include="Core">
+                <HintPath>..\Core\bin\Debug\Core.dll</HintPath>
+            </Reference>
+            <Reference Include="Vendor">
+                <HintPath>C:\Vendor\Vendor.dll</HintPath>
+            </Reference>
+            <Reference Include="Local">
+                <HintPath>lib\Local.dll</HintPath>
+            </Reference>"#).old().build();
+        assert_eq!(project.fragile_reference_hint_paths, vec![r"..\Core\bin\Debug\Core.dll", r"C:\Vendor\Vendor.dll"]);
+    }
+
     #[test]
     pub fn extract_referenced_assemblies_works() {
         let project = ProjectBuilder::new(r#""#).build();
@@ -1244,6 +4823,47 @@ mod analysis_tests {
         assert_eq!(project.target_frameworks, vec!["net462", "net472"]);
     }
 
+    #[test]
+    pub fn literal_target_frameworks_are_not_variable() {
+        let project = ProjectBuilder::new(r#"<TargetFrameworks>net48;net6.0</TargetFrameworks>"#).sdk().build();
+        assert!(!project.framework_is_variable);
+        assert_eq!(project.resolved_target_frameworks, vec!["net48", "net6.0"]);
+    }
+
+    #[test]
+    pub fn unresolvable_variable_target_framework() {
+        let project = ProjectBuilder::new(r#"<TargetFramework>$(DefaultTargetFramework)</TargetFramework>"#).sdk().build();
+        assert!(project.framework_is_variable);
+        assert_eq!(project.target_frameworks, vec!["$(DefaultTargetFramework)"]);
+        assert_eq!(project.resolved_target_frameworks, vec!["$(DefaultTargetFramework)"]);
+    }
+
+    #[test]
+    pub fn variable_target_framework_is_resolved_from_directory_build_props() {
+        let project = ProjectBuilder::new(r#"<TargetFramework>$(DefaultTargetFramework)</TargetFramework>"#)
+            .sdk()
+            .with_directory_build_props(r#"<Project>
+                <PropertyGroup>
+                    <NetCurrent>net6.0</NetCurrent>
+                    <defaulttargetframework>$(NetCurrent)</defaulttargetframework>
+                </PropertyGroup>
+            </Project>"#)
+            .build();
+        assert!(project.framework_is_variable);
+        assert_eq!(project.target_frameworks, vec!["$(DefaultTargetFramework)"]);
+        assert_eq!(project.resolved_target_frameworks, vec!["net6.0"]);
+
+        // The project's own properties win, and a property can hold several frameworks.
+        let project = ProjectBuilder::new(r#"<PropertyGroup>
+                <LibraryFrameworks>net48;netstandard2.0</LibraryFrameworks>
+                <TargetFrameworks>$(LibraryFrameworks);net6.0</TargetFrameworks>
+            </PropertyGroup>"#)
+            .sdk()
+            .with_directory_build_props(r#"<PropertyGroup><LibraryFrameworks>net472</LibraryFrameworks></PropertyGroup>"#)
+            .build();
+        assert_eq!(project.resolved_target_frameworks, vec!["net48", "netstandard2.0", "net6.0"]);
+    }
+
     #[test]
     pub fn old_extract_target_frameworks_works() {
         let project = ProjectBuilder::new(r#""#).build();
@@ -1257,6 +4877,143 @@ mod analysis_tests {
         assert_eq!(project.target_frameworks, vec!["v4.6.2", "v4.7.2"]);
     }
 
+    #[test]
+    pub fn extract_imported_targets_for_custom_targets() {
+        let project = ProjectBuilder::new(r#"
+            <Import Project="$(MSBuildExtensionsPath)\$(MSBuildToolsVersion)\Microsoft.Common.props" />
+            <Import Project="$(MSBuildToolsPath)\Microsoft.CSharp.targets" />
+            <Import Project="$(VSToolsPath)\WebApplications\Microsoft.WebApplication.targets" Condition="'$(VSToolsPath)' != ''" />
+            <Import Project="..\build\custom.targets" />
+            <Import Project="..\packages\StyleCop.MSBuild.5.0.0\build\StyleCop.MSBuild.targets" Condition="Exists('foo')" />
+            "#).old().build();
+        assert_eq!(project.imported_targets, vec![
+            "..\\build\\custom.targets",
+            "..\\packages\\StyleCop.MSBuild.5.0.0\\build\\StyleCop.MSBuild.targets"
+        ]);
+    }
+
+    #[test]
+    pub fn extract_imported_targets_for_sdk_project_is_empty() {
+        let project = ProjectBuilder::new(r#"
+            <Import Project="Sdk.targets" Sdk="Microsoft.NET.Sdk" />
+            <PropertyGroup><TargetFramework>net6.0</TargetFramework></PropertyGroup>
+            "#).sdk().build();
+        assert!(project.imported_targets.is_empty());
+    }
+
+    #[test]
+    pub fn extract_has_packages_config_imports_for_packages_import() {
+        let project = ProjectBuilder::new(r#"
+            <Import Project="$(MSBuildToolsPath)\Microsoft.CSharp.targets" />
+            <Import Project="..\packages\StyleCop.MSBuild.5.0.0\build\StyleCop.MSBuild.targets" Condition="Exists('foo')" />
+            "#).old().build();
+        assert!(project.has_packages_config_imports);
+
+        let project = ProjectBuilder::new(r#"
+            <Import Project="..\packages\EntityFramework.6.2.0\build\EntityFramework.props" />
+            "#).old().build();
+        assert!(project.has_packages_config_imports);
+    }
+
+    #[test]
+    pub fn extract_has_packages_config_imports_for_clean_sdk_project() {
+        let project = ProjectBuilder::new(r#"
+            <Import Project="Sdk.targets" Sdk="Microsoft.NET.Sdk" />
+            <Import Project="..\build\mypackages\custom.targets" />
+            <PropertyGroup><TargetFramework>net6.0</TargetFramework></PropertyGroup>
+            "#).sdk().build();
+        assert!(!project.has_packages_config_imports);
+    }
+
+    #[test]
+    pub fn extract_central_package_management_optout_when_opted_out() {
+        let project = ProjectBuilder::new(r#"
+            <PropertyGroup><ManagePackageVersionsCentrally>false</ManagePackageVersionsCentrally></PropertyGroup>
+            <PackageReference Include="Newtonsoft.Json" Version="12.0.1" />
+            "#).sdk()
+            .with_directory_packages_props("<PropertyGroup><ManagePackageVersionsCentrally>true</ManagePackageVersionsCentrally></PropertyGroup>")
+            .build();
+        assert!(!project.central_package_management);
+        assert!(project.central_package_management_optout);
+    }
+
+    #[test]
+    pub fn extract_central_package_management_optout_when_not_opted_out() {
+        let project = ProjectBuilder::new(r#"<PackageReference Include="Newtonsoft.Json" />"#).sdk()
+            .with_directory_packages_props("<PropertyGroup><ManagePackageVersionsCentrally>true</ManagePackageVersionsCentrally></PropertyGroup>")
+            .build();
+        assert!(project.central_package_management);
+        assert!(!project.central_package_management_optout);
+    }
+
+    #[test]
+    pub fn extract_central_package_management_optout_without_cpm() {
+        // Turning it off is not an opt-out if it was never on.
+        let project = ProjectBuilder::new(r#"
+            <PropertyGroup><ManagePackageVersionsCentrally>false</ManagePackageVersionsCentrally></PropertyGroup>
+            "#).sdk().build();
+        assert!(!project.central_package_management);
+        assert!(!project.central_package_management_optout);
+
+        let project = ProjectBuilder::new(r#"
+            <PropertyGroup><ManagePackageVersionsCentrally>true</ManagePackageVersionsCentrally></PropertyGroup>
+            "#).sdk().build();
+        assert!(project.central_package_management);
+        assert!(!project.central_package_management_optout);
+    }
+
+    #[test]
+    pub fn extract_has_wildcard_includes_works() {
+        let project = ProjectBuilder::new(r#"<Compile Include="Program.cs" />
+            <Content Include="Views\Home\Index.cshtml" />"#).old().build();
+        assert!(!project.has_wildcard_includes);
+
+        let project = ProjectBuilder::new(r#"<Compile Include="Program.cs" />
+            <Compile Include="**\*.cs" />"#).old().build();
+        assert!(project.has_wildcard_includes);
+
+        let project = ProjectBuilder::new(r#"<None Include="Scripts\*.js" />"#).old().build();
+        assert!(project.has_wildcard_includes);
+
+        // SDK projects glob by default, so this is not interesting.
+        let project = ProjectBuilder::new(r#"<Compile Include="**\*.cs" />"#).sdk().build();
+        assert!(!project.has_wildcard_includes);
+    }
+
+    #[test]
+    pub fn binding_redirect_count_counts_redirects_in_app_config() {
+        let project = ProjectBuilder::new(r#""#).old().build();
+        assert_eq!(project.binding_redirect_count, 0);
+
+        let project = ProjectBuilder::new(r#""#).old().with_app_config(r#"<configuration>
+  <runtime>
+    <assemblyBinding xmlns="urn:schemas-microsoft-com:asm.v1">
+      <dependentAssembly>
+        <assemblyIdentity name="Newtonsoft.Json" publicKeyToken="30ad4fe6b2a6aeed" culture="neutral" />
+        <bindingRedirect oldVersion="0.0.0.0-12.0.0.0" newVersion="12.0.0.0" />
+      </dependentAssembly>
+      <dependentAssembly>
+        <assemblyIdentity name="System.Net.Http" publicKeyToken="b03f5f7f11d50a3a" culture="neutral" />
+        <bindingRedirect oldVersion="0.0.0.0-4.2.0.0" newVersion="4.2.0.0" />
+      </dependentAssembly>
+      <dependentAssembly>
+        <assemblyIdentity name="Unity" publicKeyToken="6d32ff45e0ccc69f" culture="neutral" />
+        <bindingRedirect oldVersion="0.0.0.0-4.0.1.0" newVersion="4.0.1.0" />
+      </dependentAssembly>
+    </assemblyBinding>
+  </runtime>
+</configuration>"#).build();
+        assert_eq!(project.binding_redirect_count, 3);
+
+        let project = ProjectBuilder::new(r#""#).old().with_app_config(r#"<configuration>
+  <appSettings>
+    <add key="Mode" value="Test" />
+  </appSettings>
+</configuration>"#).build();
+        assert_eq!(project.app_config, FileStatus::OnDiskOnly);
+        assert_eq!(project.binding_redirect_count, 0);
+    }
+
     #[test]
     pub fn has_packages_config_not_present() {
         let project = ProjectBuilder::new(r#""#).build();
@@ -1281,6 +5038,45 @@ mod analysis_tests {
         assert_eq!(project.packages_config, FileStatus::InProjectFileAndOnDisk);
     }
 
+    #[test]
+    pub fn has_global_usings_not_present() {
+        let project = ProjectBuilder::new(r#""#).sdk().build();
+        assert!(!project.has_global_usings);
+    }
+
+    #[test]
+    pub fn has_global_usings_on_disk() {
+        let project = ProjectBuilder::new(r#""#).sdk().with_other_file("GlobalUsings.cs").build();
+        assert!(project.has_global_usings);
+    }
+
+    #[test]
+    pub fn uses_obsolete_project_json_when_on_disk() {
+        let project = ProjectBuilder::new(r#""#).old().with_other_file("project.json").build();
+        assert_eq!(project.project_json, FileStatus::OnDiskOnly);
+        assert!(project.uses_obsolete_project_json);
+    }
+
+    #[test]
+    pub fn uses_obsolete_project_json_not_present() {
+        let project = ProjectBuilder::new(r#""#).old().build();
+        assert!(!project.uses_obsolete_project_json);
+
+        // Only mentioning it in the project file does not count.
+        let project = ProjectBuilder::new(r#"<None Include="project.json" />"#).old().build();
+        assert_eq!(project.project_json, FileStatus::InProjectFileOnly);
+        assert!(!project.uses_obsolete_project_json);
+    }
+
+    #[test]
+    pub fn has_global_usings_from_using_items() {
+        let project = ProjectBuilder::new(r#"<ItemGroup>
+            <Using Include="System.Text.Json" />
+            <Using Include="Xunit" />
+            </ItemGroup>"#).sdk().build();
+        assert!(project.has_global_usings);
+    }
+
     #[test]
     pub fn extract_packages_sdk_one_line() {
         let project = ProjectBuilder::new(r#""#).sdk().build();
@@ -1290,6 +5086,86 @@ mod analysis_tests {
         assert_eq!(project.packages, vec![Package::new("Unity", "4.0.1", false, "Third Party")]);
     }
 
+    #[test]
+    pub fn extract_packages_flags_missing_version_without_cpm() {
+        let project = ProjectBuilder::new(r#"
+            <PackageReference Include="Serilog" />
+            <PackageReference Include="Unity" Version="4.0.1" />"#).sdk().build();
+        assert_eq!(project.packages, vec![
+            Package { missing_version: true, ..Package::new("Serilog", "", false, "Third Party") },
+            Package::new("Unity", "4.0.1", false, "Third Party"),
+        ]);
+
+        let project = ProjectBuilder::new(r#"
+            <ManagePackageVersionsCentrally>true</ManagePackageVersionsCentrally>
+            <PackageReference Include="Serilog" />"#).sdk().build();
+        assert_eq!(project.packages, vec![Package::new("Serilog", "", false, "Third Party")]);
+    }
+
+    #[test]
+    pub fn extract_packages_applies_default_package_class() {
+        let configuration = Configuration {
+            package_groups: vec![crate::configuration::PackageGroup::new("Microsoft", "^Microsoft\\..*")],
+            default_package_class: "Other".to_owned(),
+            ..Configuration::default()
+        };
+
+        let project = ProjectBuilder::new(r#"
+            <PackageReference Include="Microsoft.Extensions.Logging" Version="3.1.0" />
+            <PackageReference Include="Unity" Version="4.0.1" />"#)
+            .sdk().with_configuration(configuration.clone()).build();
+
+        assert_eq!(project.packages, vec![
+            Package::new("Microsoft.Extensions.Logging", "3.1.0", false, "Microsoft"),
+            Package::new("Unity", "4.0.1", false, "Other"),
+        ]);
+
+        let mut sln = Solution::default();
+        sln.projects.push(project);
+        let analysis = Analysis {
+            solution_directories: vec![SolutionDirectory { solutions: vec![sln], ..Default::default() }],
+            ..Default::default()
+        };
+        assert_eq!(analysis.unclassified_packages(&configuration), vec!["Unity"]);
+
+        // The default groups end with a catch-all, so nothing is unclassified.
+        assert!(analysis.unclassified_packages(&Configuration::default()).is_empty());
+    }
+
+    #[test]
+    pub fn package_group_usage_finds_unmatched_groups() {
+        let configuration = Configuration {
+            package_groups: vec![
+                PackageGroup::new("Microsoft", "^Microsoft\\..*"),
+                PackageGroup::new("Telerik", "^Telerik\\..*"),
+                // Shadowed by the first group for Microsoft packages.
+                PackageGroup::new("Logging", "Logging"),
+            ],
+            ..Configuration::default()
+        };
+
+        let project = ProjectBuilder::new(r#"
+            <PackageReference Include="Microsoft.Extensions.Logging" Version="3.1.0" />
+            <PackageReference Include="Microsoft.Extensions.Logging" Version="3.1.1" />
+            <PackageReference Include="Microsoft.Extensions.Http" Version="3.1.0" />
+            <PackageReference Include="Serilog" Version="2.10.0" />"#)
+            .sdk().with_configuration(configuration.clone()).build();
+
+        let mut sln = Solution::default();
+        sln.projects.push(project);
+        let analysis = Analysis {
+            solution_directories: vec![SolutionDirectory { solutions: vec![sln], ..Default::default() }],
+            ..Default::default()
+        };
+
+        let usage = analysis.package_group_usage(&configuration).into_iter()
+            .map(|(pkg_group, count)| (pkg_group.name.as_str(), count))
+            .collect::<Vec<_>>();
+        assert_eq!(usage, vec![("Microsoft", 2), ("Telerik", 0), ("Logging", 0)]);
+        assert_eq!(analysis.unmatched_package_groups(&configuration), vec!["Telerik", "Logging"]);
+        assert_eq!(analysis.unclassified_packages(&configuration), vec!["Serilog"]);
+    }
+
     #[test]
     pub fn extract_packages_sdk_one_line_sorts() {
         let project = ProjectBuilder::new(
@@ -1340,6 +5216,25 @@ mod analysis_tests {
             ]);
     }
 
+    #[test]
+    pub fn extract_intra_project_package_conflicts_works() {
+        let project = ProjectBuilder::new(
+            r#"
+            blah<PackageReference Include="Automapper" Version="3.1.5" />blah
+            blah<PackageReference Include="Unity" Version="4.0.1" />blah
+            blah<PackageReference Include="Automapper" Version="4.0.0" />blah
+            blah<PackageReference Include="Unity" Version="4.0.1" />blah
+            "#
+            ).sdk().build();
+
+        assert_eq!(project.intra_project_package_conflicts, vec![
+            ("Automapper".to_owned(), vec!["3.1.5".to_owned(), "4.0.0".to_owned()])
+            ]);
+
+        let project = ProjectBuilder::new(r#"<PackageReference Include="Unity" Version="4.0.1" />"#).sdk().build();
+        assert!(project.intra_project_package_conflicts.is_empty());
+    }
+
     #[test]
     pub fn extract_packages_sdk_multi_line() {
         let project = ProjectBuilder::new(
@@ -1450,6 +5345,57 @@ mod analysis_tests {
         ]);
     }
 
+    #[test]
+    pub fn extract_packages_old_flags_framework_mismatch() {
+        let project = ProjectBuilder::new(r#"
+            <TargetFrameworkVersion>v4.6.2</TargetFrameworkVersion>
+            Include="packages.config" />"#).old()
+            .with_packages_config(r#"
+            <package id="Castle.Core" version="4.3.1" targetFramework="net461" />
+            <package id="Owin" version="1.0" targetFramework="net462" />
+            <package id="Unity" version="4.0.1" />
+            "#).build();
+        assert_eq!(project.packages, vec![
+            Package { framework_mismatch: true, ..Package::new("Castle.Core", "4.3.1", false, "Third Party") },
+            Package::new("Owin", "1.0", false, "Microsoft"),
+            Package::new("Unity", "4.0.1", false, "Third Party"),
+        ]);
+    }
+
+    #[test]
+    pub fn extract_packages_old_compares_against_the_resolved_framework() {
+        let packages_config = r#"
+            <package id="Castle.Core" version="4.3.1" targetFramework="net461" />
+            <package id="Owin" version="1.0" targetFramework="net462" />
+            "#;
+        let project = ProjectBuilder::new(r#"
+            <TargetFrameworkVersion>$(FrameworkVersion)</TargetFrameworkVersion>
+            Include="packages.config" />"#).old()
+            .with_packages_config(packages_config)
+            .with_directory_build_props(r#"<PropertyGroup><FrameworkVersion>v4.6.2</FrameworkVersion></PropertyGroup>"#)
+            .build();
+        assert_eq!(project.packages, vec![
+            Package { framework_mismatch: true, ..Package::new("Castle.Core", "4.3.1", false, "Third Party") },
+            Package::new("Owin", "1.0", false, "Microsoft"),
+        ]);
+
+        // An unresolved framework is unknown, so nothing is flagged.
+        let project = ProjectBuilder::new(r#"
+            <TargetFrameworkVersion>$(FrameworkVersion)</TargetFrameworkVersion>
+            Include="packages.config" />"#).old()
+            .with_packages_config(packages_config)
+            .build();
+        assert!(project.packages.iter().all(|pkg| !pkg.framework_mismatch));
+    }
+
+    #[test]
+    pub fn short_framework_name_converts_old_style_versions() {
+        assert_eq!(short_framework_name("v4.6.2"), "net462");
+        assert_eq!(short_framework_name("v4.0"), "net40");
+        assert_eq!(short_framework_name("net48"), "net48");
+        assert_eq!(short_framework_name(" NetStandard2.0 "), "netstandard2.0");
+    }
+
     #[test]
     pub fn extract_test_framework_mstest() {
         let project = ProjectBuilder::new(r#"<PackageReference Include="MSTest.TestFramework" Version="4.0.1" />"#)
@@ -1471,6 +5417,56 @@ mod analysis_tests {
         assert_eq!(project.test_framework, TestFramework::NUnit);
     }
 
+    #[test]
+    pub fn extract_test_framework_mstest_v1_assembly() {
+        let project = ProjectBuilder::new(r#"<Reference Include="Microsoft.VisualStudio.QualityTools.UnitTestFramework" />"#)
+            .old().build();
+        assert_eq!(project.test_framework, TestFramework::MSTest);
+
+        let project = ProjectBuilder::new(r#"<Reference Include="Microsoft.VisualStudio.QualityTools.UnitTestFramework, Version=10.1.0.0, Culture=neutral, PublicKeyToken=b03f5f7f11d50a3a, processorArchitecture=MSIL" />"#)
+            .old().build();
+        assert_eq!(project.test_framework, TestFramework::MSTest);
+
+        let project = ProjectBuilder::new(r#"<Reference Include="Microsoft.VisualStudio.QualityTools.CodedUITestFramework" />"#)
+            .old().build();
+        assert_eq!(project.test_framework, TestFramework::None);
+    }
+
+    #[test]
+    pub fn extract_test_project_misconfigured_when_adapter_missing() {
+        let project = ProjectBuilder::new(r#"
+            <PackageReference Include="xunit.core" Version="2.4.1" />
+            <PackageReference Include="Microsoft.NET.Test.Sdk" Version="17.0.0" />"#)
+            .sdk().build();
+        assert!(project.test_project_misconfigured);
+
+        let project = ProjectBuilder::new(r#"
+            <PackageReference Include="NUnit.Core" Version="3.13.2" />
+            <PackageReference Include="NUnit3TestAdapter" Version="4.2.1" />"#)
+            .sdk().build();
+        assert!(project.test_project_misconfigured);
+    }
+
+    #[test]
+    pub fn extract_test_project_misconfigured_when_properly_configured() {
+        let project = ProjectBuilder::new(r#"
+            <PackageReference Include="xunit.core" Version="2.4.1" />
+            <PackageReference Include="xunit.runner.visualstudio" Version="2.4.3" />
+            <PackageReference Include="Microsoft.NET.Test.Sdk" Version="17.0.0" />"#)
+            .sdk().build();
+        assert!(!project.test_project_misconfigured);
+
+        // Not a test project at all.
+        let project = ProjectBuilder::new(r#"<PackageReference Include="Unity" Version="4.0.1" />"#)
+            .sdk().build();
+        assert!(!project.test_project_misconfigured);
+
+        // Old-style projects are not checked.
+        let project = ProjectBuilder::new(r#"<Reference Include="Microsoft.VisualStudio.QualityTools.UnitTestFramework" />"#)
+            .old().build();
+        assert!(!project.test_project_misconfigured);
+    }
+
     #[test]
     pub fn extract_test_framework_none() {
         let project = ProjectBuilder::new(r#"<PackageReference Include="MSTestNotMatched" Version="4.0.1" />"#)
@@ -1478,6 +5474,69 @@ mod analysis_tests {
         assert_eq!(project.test_framework, TestFramework::None);
     }
 
+    #[test]
+    pub fn is_isolated_ignores_framework_references() {
+        let project = ProjectBuilder::new(r#""#).sdk().build();
+        assert!(project.is_isolated());
+
+        let project = ProjectBuilder::new(r#"<Reference Include="System" />
+            <Reference Include="System.Xml.Linq" />
+            <Reference Include="Microsoft.CSharp" />"#).old().build();
+        assert!(project.is_isolated());
+    }
+
+    #[test]
+    pub fn is_isolated_false_for_project_with_references() {
+        let project = ProjectBuilder::new(r#"<PackageReference Include="Serilog" Version="2.10.0" />"#)
+            .sdk().build();
+        assert!(!project.is_isolated());
+
+        let project = ProjectBuilder::new(r#"<ProjectReference Include="..\Core\Core.csproj" />"#)
+            .sdk().build();
+        assert!(!project.is_isolated());
+
+        let project = ProjectBuilder::new(r#"<Reference Include="System" />
+            <Reference Include="log4net, Version=1.2.10.0, Culture=neutral" />"#).old().build();
+        assert!(!project.is_isolated());
+    }
+
+    #[test]
+    pub fn packages_by_framework_expands_unconditional_references() {
+        let project = ProjectBuilder::new(r#"
+            <TargetFrameworks>net462;netstandard2.0</TargetFrameworks>
+            <ItemGroup>
+                <PackageReference Include="Serilog" Version="2.10.0" />
+            </ItemGroup>
+            <ItemGroup Condition=" '$(TargetFramework)' == 'net462' ">
+                <PackageReference Include="System.ValueTuple" Version="4.5.0" />
+            </ItemGroup>
+            <ItemGroup>
+                <PackageReference Include="Microsoft.Bcl.AsyncInterfaces" Version="5.0.0" Condition="'$(TargetFramework)'=='netstandard2.0'">
+                    <PrivateAssets>all</PrivateAssets>
+                </PackageReference>
+            </ItemGroup>"#).sdk().build();
+
+        let triples = project.packages_by_framework().into_iter()
+            .map(|(tf, pkg)| (tf, pkg.name.as_str(), pkg.version.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(triples, vec![
+            ("net462", "Serilog", "2.10.0"),
+            ("net462", "System.ValueTuple", "4.5.0"),
+            ("netstandard2.0", "Microsoft.Bcl.AsyncInterfaces", "5.0.0"),
+            ("netstandard2.0", "Serilog", "2.10.0"),
+        ]);
+    }
+
+    #[test]
+    pub fn packages_by_framework_empty_for_single_target() {
+        let project = ProjectBuilder::new(r#"
+            <TargetFramework>net462</TargetFramework>
+            <ItemGroup>
+                <PackageReference Include="Serilog" Version="2.10.0" />
+            </ItemGroup>"#).sdk().build();
+        assert!(project.packages_by_framework().is_empty());
+    }
+
     #[test]
     pub fn extract_uses_specflow_works() {
         let project = ProjectBuilder::new(r#"<PackageReference Include="NUnit.Core" Version="4.0.1" />"#)
@@ -1489,6 +5548,31 @@ mod analysis_tests {
         assert!(project.uses_specflow);
     }
 
+    #[test]
+    pub fn extract_uses_source_link_package_only() {
+        let project = ProjectBuilder::new(r#"<PackageReference Include="Microsoft.SourceLink.GitHub" Version="1.0.0" PrivateAssets="All" />"#)
+            .sdk().build();
+        assert!(project.uses_source_link);
+    }
+
+    #[test]
+    pub fn extract_uses_source_link_property_only() {
+        let project = ProjectBuilder::new(r#"blah<PublishRepositoryUrl>true</PublishRepositoryUrl>blah"#)
+            .sdk().build();
+        assert!(project.uses_source_link);
+
+        let project = ProjectBuilder::new(r#"blah<EmbedUntrackedSources>true</EmbedUntrackedSources>blah"#)
+            .sdk().build();
+        assert!(project.uses_source_link);
+    }
+
+    #[test]
+    pub fn extract_uses_source_link_none() {
+        let project = ProjectBuilder::new(r#"<PackageReference Include="Microsoft.Extensions.Logging" Version="3.1.0" />
+            <PublishRepositoryUrl>false</PublishRepositoryUrl>"#)
+            .sdk().build();
+        assert!(!project.uses_source_link);
+    }
 
     /// These tests run against the embedded example SDK-style project.
     /// They are an extra sanity-check that we really got it right "in the real world".
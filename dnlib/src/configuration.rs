@@ -5,24 +5,55 @@ use std::collections::HashMap;
 use regex::Regex;
 use serde::{Serialize, Deserialize};
 use serde_json;
-use serde_regex;
 use log::{info, warn};
 
+use crate::enums::{DiagnosticCategory, LabelMode, Palette, RedundantEdgeStyle, Severity};
+use crate::errors::{DnLibError, DnLibResult};
+use crate::io::PathExtensions;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "RawPackageGroup")]
 pub struct PackageGroup {
     pub name: String,
-    #[serde(with = "serde_regex")]
-    pub regex: Regex,
+    /// The regex that package names are matched against, as written in the configuration.
+    #[serde(rename = "regex")]
+    pub pattern: String,
+    /// The compiled `pattern`, or None if it is not a valid regex. Such groups never
+    /// match anything; `Configuration::validate` reports them.
+    #[serde(skip)]
+    regex: Option<Regex>,
+}
+
+/// The serialized form of a `PackageGroup`. The regex is compiled after loading,
+/// so that a bad pattern does not make the whole configuration file unreadable.
+#[derive(Deserialize)]
+struct RawPackageGroup {
+    name: String,
+    regex: String,
+}
+
+impl From<RawPackageGroup> for PackageGroup {
+    fn from(raw: RawPackageGroup) -> Self {
+        PackageGroup::new(raw.name, raw.regex)
+    }
 }
 
 impl PackageGroup {
-    fn new<N, R>(name: N, regex: R) -> Self
+    pub fn new<N, R>(name: N, regex: R) -> Self
     where N: Into<String>,
           R: AsRef<str>
     {
         PackageGroup {
             name: name.into(),
-            regex: Regex::new(regex.as_ref()).unwrap(),
+            pattern: regex.as_ref().to_owned(),
+            regex: Regex::new(regex.as_ref()).ok(),
+        }
+    }
+
+    pub fn is_match(&self, package_name: &str) -> bool {
+        match self.regex {
+            Some(ref regex) => regex.is_match(package_name),
+            None => false,
         }
     }
 }
@@ -33,12 +64,262 @@ pub struct Configuration {
     pub package_groups: Vec<PackageGroup>,
     #[serde(default)]
     pub abbreviations: HashMap<String, Vec<String>>,
+    /// Package classes to rename when they are written to the output, e.g.
+    /// "Third Party" to "External". Classes not in the map are written as they are.
+    #[serde(default)]
+    pub class_aliases: HashMap<String, String>,
     #[serde(default)]
     pub input_directory: PathBuf,
     #[serde(default)]
     pub output_directory: PathBuf,
     #[serde(default)]
     pub abbreviate_on_graphs: bool,
+    /// If set, this is used as the label of the DOT graphs and of their root node,
+    /// instead of the path that was scanned.
+    #[serde(default)]
+    pub graph_title: Option<String>,
+    /// If set, each CSV file is limited to this many rows (plus a marker row
+    /// indicating that truncation occurred).
+    #[serde(default)]
+    pub max_csv_rows: Option<usize>,
+    /// If set, the package matrix CSV is limited to this many package columns.
+    #[serde(default)]
+    pub max_csv_columns: Option<usize>,
+    /// If set, the rows of the solutions-to-projects CSV are sorted by this column.
+    #[serde(default)]
+    pub sort_by: Option<String>,
+    /// Whether `sort_by` sorts in descending rather than ascending order.
+    #[serde(default)]
+    pub sort_descending: bool,
+    /// If set, the whole scan is abandoned if it takes longer than this many seconds.
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+    /// If true, CSV files are only written if their contents have changed since
+    /// the previous run. This avoids churn when the output is kept under source control.
+    #[serde(default)]
+    pub only_write_changed: bool,
+    /// If true, rows are appended to existing CSV files instead of overwriting them,
+    /// so that the results of several runs (e.g. over different scan roots) can be
+    /// aggregated. The header is only written when a file is created, and it is an
+    /// error if an existing file has a different header. `only_write_changed` has
+    /// no effect when appending, and tables cut short by `max_csv_rows` do not get
+    /// a TRUNCATED marker row, since it would end up in the middle of the file.
+    #[serde(default)]
+    pub append_csv: bool,
+    /// If true, the raw list of files found by the directory walk is written to
+    /// paths.csv. This is useful when diagnosing why a file was or was not analyzed.
+    #[serde(default)]
+    pub dump_paths: bool,
+    /// If true, every file read during the analysis is written to manifest.csv, with
+    /// its kind and whether it could be read, for auditing what the tool touched.
+    #[serde(default)]
+    pub write_manifest: bool,
+    /// If true, the number of files processed per second is printed at the end of
+    /// the run, to help track performance regressions.
+    #[serde(default)]
+    pub benchmark: bool,
+    /// If true, the analysis is printed as a tree on stdout instead of writing
+    /// any output files.
+    #[serde(default)]
+    pub print_tree: bool,
+    /// If true, nothing is printed on stdout.
+    #[serde(default)]
+    pub quiet: bool,
+    /// If set, the outputs only cover the solution with this name (with or without
+    /// the ".sln" extension). The whole directory is still analyzed, so references
+    /// out of the solution are resolved.
+    #[serde(default)]
+    pub solution_filter: Option<String>,
+    /// Glob patterns for solution directories to leave out of all the outputs. They are
+    /// removed after the analysis; see `Analysis::exclude_solution_directories`.
+    #[serde(default)]
+    pub excluded_solution_directories: Vec<String>,
+    /// If true, the analysis is run and its diagnostics are summarised, but no
+    /// output files are written. The exit code says whether problems were found.
+    #[serde(default)]
+    pub validate_only: bool,
+    /// Like `validate_only`, but logging is turned off and the diagnostics are printed
+    /// as a single JSON object (see `DiagnosticsReport`), for use from scripts.
+    #[serde(default)]
+    pub porcelain: bool,
+    /// If set, the analysis is run and every project that references the package with
+    /// this name is printed, with the version it uses, instead of writing any output files.
+    #[serde(default)]
+    pub package_query: Option<String>,
+    /// Diagnostics less severe than this are left out of diagnostics.csv and do not
+    /// count towards the `validate_only` exit code. The default keeps everything.
+    #[serde(default)]
+    pub min_severity: Severity,
+    /// If not empty, the `validate_only` exit code only reports problems when there
+    /// are diagnostics in one of these categories. Otherwise any diagnostic counts.
+    #[serde(default)]
+    pub fail_on: Vec<DiagnosticCategory>,
+    /// Whether paths that differ only in case refer to different files. Windows and
+    /// macOS filesystems are normally case-insensitive, Linux ones are not.
+    #[serde(default = "default_case_sensitive_paths")]
+    pub case_sensitive_paths: bool,
+    /// If true, the CSV files are written.
+    #[serde(default = "default_true")]
+    pub write_csv: bool,
+    /// If true, the DOT files (which Graphviz can render) of the project graphs are written.
+    #[serde(default = "default_true")]
+    pub write_dot: bool,
+    /// Solutions whose coverage ratio (the fraction of their on-disk projects that
+    /// they actually mention) is below this are reported as having low coverage.
+    #[serde(default = "default_min_solution_coverage")]
+    pub min_solution_coverage: f64,
+    /// The colors used in the DOT files.
+    #[serde(default)]
+    pub palette: Palette,
+    /// What the solution and project nodes in the graphs are labelled with.
+    #[serde(default)]
+    pub label_mode: LabelMode,
+    /// How the redundant edges are drawn in the DOT files of the project graphs.
+    #[serde(default)]
+    pub redundant_edge_style: RedundantEdgeStyle,
+    /// If true, directory_packages.csv is written, listing the packages used by each
+    /// solution directory and how many of its projects use them.
+    #[serde(default)]
+    pub write_directory_packages: bool,
+    /// If true, the solution and project CSVs include the time each file was last modified.
+    #[serde(default)]
+    pub include_modified_times: bool,
+    /// If true, the path columns of the CSVs are written as `file://` URIs, which
+    /// editors can turn into clickable links.
+    #[serde(default)]
+    pub file_uris: bool,
+    /// If true, projects that cannot be associated with any solution are not warned
+    /// about. They are still reported in the outputs.
+    #[serde(default)]
+    pub suppress_orphan_warnings: bool,
+    /// If set, all the output files are written into this zip archive instead of
+    /// into the `output_directory`.
+    #[serde(default)]
+    pub zip_output: Option<PathBuf>,
+    /// If true, DGML files (which Visual Studio can open) are written alongside the DOT files.
+    #[serde(default)]
+    pub write_dgml: bool,
+    /// If true, all output files are gzip-compressed and have ".gz" appended to their names.
+    #[serde(default)]
+    pub gzip_output: bool,
+    /// If true, a DOT file is written for each solution showing the references
+    /// between its components (the top-level directories under the solution).
+    #[serde(default)]
+    pub write_component_graphs: bool,
+    /// Names of files, such as "project.json", that should not be treated as files
+    /// of interest even though they normally would be.
+    #[serde(default)]
+    pub exclude_files: Vec<String>,
+    /// The class given to packages that do not match any of the `package_groups`.
+    #[serde(default = "default_package_class")]
+    pub default_package_class: String,
+    /// Name and regex pairs used to assign each solution to a domain, by matching
+    /// the regex against the sln file name. The first match wins.
+    #[serde(default)]
+    pub solution_classifiers: Vec<PackageGroup>,
+    /// The domain given to solutions that do not match any of the `solution_classifiers`.
+    #[serde(default = "default_solution_domain")]
+    pub default_solution_domain: String,
+    /// If true, packages that do not match any of the `package_groups` are listed
+    /// in a warning, so that the groups can be extended to cover them.
+    #[serde(default)]
+    pub warn_unclassified: bool,
+    /// Glob patterns for files and directories that should not be scanned at all.
+    /// Patterns in a `.dnscanignore` file in the input directory are added to these.
+    #[serde(default)]
+    pub ignore_patterns: Vec<String>,
+    /// How often, in seconds, to log the number of files found so far while walking
+    /// the input directory. 0 turns the progress messages off.
+    #[serde(default = "default_walk_progress_seconds")]
+    pub walk_progress_seconds: u64,
+    /// If true, the directories at each level of the input directory tree are read
+    /// in parallel. This can speed up the scan of a slow disk. The files found are
+    /// the same either way.
+    #[serde(default)]
+    pub parallel_walk: bool,
+    /// If true, projects that are referenced by a solution or project but which lie
+    /// outside the scanned directory tree are loaded and analyzed too.
+    #[serde(default)]
+    pub follow_external_refs: bool,
+    /// If set, `follow_external_refs` only loads projects under this directory.
+    /// A relative path is relative to the scanned directory.
+    #[serde(default)]
+    pub external_refs_ceiling: Option<PathBuf>,
+    /// If true, an extra DOT file is written showing projects colored by the dominant
+    /// class of their packages, with dependencies on package-heavy projects emphasized.
+    #[serde(default)]
+    pub write_package_class_graph: bool,
+    /// If true, an extra DOT file is written showing the packages each project uses
+    /// as well as the project references.
+    #[serde(default)]
+    pub write_package_graph: bool,
+    /// Package classes, e.g. "Microsoft", whose packages are left out of the package graph.
+    #[serde(default)]
+    pub hidden_package_classes: Vec<String>,
+    /// If set, an extra DOT file is written showing only the projects that directly
+    /// reference this package, and the project references between them.
+    #[serde(default)]
+    pub package_consumers_graph: Option<String>,
+    /// If true, the project graph is also written as a plain text adjacency list,
+    /// one "node -> child1,child2" line per node, for grep-based queries.
+    #[serde(default)]
+    pub write_adjacency_list: bool,
+    /// If true, the analysis is written to analysis.json so that the outputs can
+    /// be regenerated later (see `load_analysis`) without scanning again.
+    #[serde(default)]
+    pub save_analysis: bool,
+    /// If true, the analysis is also written to analysis.pb as a Protocol Buffers
+    /// message, following the schema in dnlib/proto/analysis.proto.
+    #[serde(default)]
+    pub write_protobuf: bool,
+    /// If set, the analysis is read from this file, previously written because of
+    /// `save_analysis`, instead of scanning the `input_directory`.
+    #[serde(default)]
+    pub load_analysis: Option<PathBuf>,
+    /// If set, a JSON file mapping package names to their latest version on the feed.
+    /// Packages behind that version are reported in available_updates.csv.
+    #[serde(default)]
+    pub feed_index: Option<PathBuf>,
+    /// The number of threads used to load solution files. If not specified,
+    /// rayon's global thread pool is used.
+    #[serde(default)]
+    pub solution_threads: Option<usize>,
+    /// The number of threads used to load project files. If not specified,
+    /// rayon's global thread pool is used.
+    #[serde(default)]
+    pub project_threads: Option<usize>,
+    /// The maximum number of git operations (reading the repository information for
+    /// a solution directory) that may run at the same time.
+    #[serde(default = "default_max_git_operations")]
+    pub max_git_operations: usize,
+}
+
+fn default_case_sensitive_paths() -> bool {
+    cfg!(not(any(windows, target_os = "macos")))
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_package_class() -> String {
+    "Third Party".to_owned()
+}
+
+fn default_solution_domain() -> String {
+    "Unclassified".to_owned()
+}
+
+fn default_walk_progress_seconds() -> u64 {
+    10
+}
+
+fn default_min_solution_coverage() -> f64 {
+    0.5
+}
+
+fn default_max_git_operations() -> usize {
+    4
 }
 
 const DEFAULT_OUTPUT_DIR: &str = "dnscan-output";
@@ -58,9 +339,67 @@ impl Default for Configuration {
                 PackageGroup::new("Third Party", r#".*"#),
             ],
             abbreviations: abbrevs,
+            class_aliases: HashMap::new(),
             output_directory: DEFAULT_OUTPUT_DIR.into(),
             input_directory: "".into(),
-            abbreviate_on_graphs: true
+            abbreviate_on_graphs: true,
+            graph_title: None,
+            max_csv_rows: None,
+            max_csv_columns: None,
+            sort_by: None,
+            sort_descending: false,
+            timeout_seconds: None,
+            only_write_changed: false,
+            append_csv: false,
+            dump_paths: false,
+            write_manifest: false,
+            benchmark: false,
+            print_tree: false,
+            quiet: false,
+            validate_only: false,
+            porcelain: false,
+            package_query: None,
+            excluded_solution_directories: vec![],
+            min_severity: Severity::Info,
+            fail_on: vec![],
+            solution_filter: None,
+            case_sensitive_paths: default_case_sensitive_paths(),
+            write_csv: true,
+            write_dot: true,
+            min_solution_coverage: default_min_solution_coverage(),
+            palette: Palette::Default,
+            label_mode: LabelMode::Stem,
+            redundant_edge_style: RedundantEdgeStyle::Red,
+            write_dgml: false,
+            gzip_output: false,
+            write_component_graphs: false,
+            write_directory_packages: false,
+            include_modified_times: false,
+            file_uris: false,
+            suppress_orphan_warnings: false,
+            zip_output: None,
+            exclude_files: vec![],
+            default_package_class: default_package_class(),
+            warn_unclassified: false,
+            solution_classifiers: vec![],
+            default_solution_domain: default_solution_domain(),
+            ignore_patterns: vec![],
+            walk_progress_seconds: default_walk_progress_seconds(),
+            parallel_walk: false,
+            follow_external_refs: false,
+            external_refs_ceiling: None,
+            write_package_class_graph: false,
+            write_package_graph: false,
+            hidden_package_classes: vec![],
+            package_consumers_graph: None,
+            write_adjacency_list: false,
+            save_analysis: false,
+            write_protobuf: false,
+            load_analysis: None,
+            feed_index: None,
+            solution_threads: None,
+            project_threads: None,
+            max_git_operations: default_max_git_operations(),
         }
     }
 }
@@ -96,6 +435,104 @@ impl Configuration {
         Configuration::default()
     }
 
+    /// Returns the name of the first package group whose regex matches the package
+    /// name, or None if the package is not in any group.
+    pub fn package_group_for(&self, package_name: &str) -> Option<&str> {
+        self.package_groups.iter()
+            .find(|pkg_group| pkg_group.is_match(package_name))
+            .map(|pkg_group| pkg_group.name.as_str())
+    }
+
+    /// Returns the name that the package class should be written out as, taking
+    /// the `class_aliases` into account.
+    pub fn package_class_alias<'a>(&'a self, class: &'a str) -> &'a str {
+        self.class_aliases.get(class).map_or(class, |alias| alias.as_str())
+    }
+
+    /// Checks the configuration for mistakes that would otherwise only show up part way
+    /// through the analysis, or not at all. The error names the offending field.
+    pub fn validate(&self) -> DnLibResult<()> {
+        Self::validate_groups("package_groups", &self.package_groups)?;
+        Self::validate_groups("solution_classifiers", &self.solution_classifiers)?;
+
+        if !(0.0..=1.0).contains(&self.min_solution_coverage) {
+            return Err(config_error(format!("min_solution_coverage: {} is not between 0 and 1", self.min_solution_coverage)));
+        }
+
+        if self.solution_threads == Some(0) {
+            return Err(config_error("solution_threads: must be at least 1"));
+        }
+
+        if self.project_threads == Some(0) {
+            return Err(config_error("project_threads: must be at least 1"));
+        }
+
+        if self.max_git_operations == 0 {
+            return Err(config_error("max_git_operations: must be at least 1"));
+        }
+
+        if self.append_csv && self.gzip_output {
+            return Err(config_error("append_csv: cannot be combined with gzip_output"));
+        }
+
+        if self.append_csv && self.zip_output.is_some() {
+            return Err(config_error("append_csv: cannot be combined with zip_output"));
+        }
+
+        Self::validate_writable_directory(&self.output_directory)
+            .map_err(|msg| config_error(format!("output_directory: {:?} {}", self.output_directory, msg)))
+    }
+
+    fn validate_groups(field: &str, groups: &[PackageGroup]) -> DnLibResult<()> {
+        for (idx, group) in groups.iter().enumerate() {
+            if group.name.trim().is_empty() {
+                return Err(config_error(format!("{}[{}]: the name is empty", field, idx)));
+            }
+
+            if let Err(e) = Regex::new(&group.pattern) {
+                return Err(DnLibError::RegexError(format!("{}[{}] ({}): invalid regex: {}", field, idx, group.name, e)));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The output directory is created if necessary, so it is enough for its nearest
+    /// existing ancestor to be a writable directory.
+    fn validate_writable_directory(dir: &Path) -> Result<(), &'static str> {
+        let existing = dir.ancestors()
+            .map(|d| if d.as_os_str().is_empty() { Path::new(".") } else { d })
+            .find(|d| d.exists());
+
+        match existing.map(fs::metadata) {
+            None => Err("has no existing parent directory"),
+            Some(Err(_)) => Err("cannot be accessed"),
+            Some(Ok(ref md)) if !md.is_dir() => Err("is not a directory"),
+            Some(Ok(ref md)) if md.permissions().readonly() => Err("is not writable"),
+            Some(Ok(_)) => Ok(()),
+        }
+    }
+
+    /// Returns the name of the first solution classifier whose regex matches the
+    /// sln file name, or the `default_solution_domain` if none do.
+    pub fn solution_domain_for(&self, sln_filename: &str) -> &str {
+        self.solution_classifiers.iter()
+            .find(|classifier| classifier.is_match(sln_filename))
+            .map(|classifier| classifier.name.as_str())
+            .unwrap_or(&self.default_solution_domain)
+    }
+
+    /// The directory that the scan starts from. This is normally the `input_directory`,
+    /// but that is allowed to be a single sln file, in which case it is the directory
+    /// containing the sln.
+    pub fn scan_directory(&self) -> &Path {
+        if self.input_directory.is_sln_file() {
+            self.input_directory.parent_dir()
+        } else {
+            &self.input_directory
+        }
+    }
+
     pub fn to_string(&self) -> String {
         serde_json::to_string_pretty(self).unwrap()
     }
@@ -123,3 +560,73 @@ impl Configuration {
         }
     }
 }
+
+fn config_error<S: Into<String>>(msg: S) -> DnLibError {
+    DnLibError::ConfigError(msg.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn validate_accepts_the_default_configuration() {
+        let configuration = Configuration {
+            output_directory: std::env::temp_dir().join("dnscan-output"),
+            ..Configuration::default()
+        };
+        assert!(configuration.validate().is_ok());
+    }
+
+    #[test]
+    pub fn validate_rejects_an_invalid_regex() {
+        let configuration = Configuration {
+            package_groups: vec![PackageGroup::new("Ours", "^Acme\\."), PackageGroup::new("Broken", "^(Foo")],
+            output_directory: std::env::temp_dir(),
+            ..Configuration::default()
+        };
+
+        let err = configuration.validate().unwrap_err();
+        assert!(matches!(err, DnLibError::RegexError(_)));
+        let msg = err.to_string();
+        assert!(msg.starts_with("package_groups[1] (Broken): invalid regex"), "{}", msg);
+        assert_eq!(configuration.package_group_for("Acme.Core"), Some("Ours"));
+        assert_eq!(configuration.package_group_for("Foo"), None);
+    }
+
+    #[test]
+    pub fn invalid_regex_does_not_prevent_loading() {
+        let configuration: Configuration = serde_json::from_str(r#"{ "package_groups": [{ "name": "Broken", "regex": "^(Foo" }] }"#).unwrap();
+        assert_eq!(configuration.package_groups[0].pattern, "^(Foo");
+        assert!(configuration.validate().is_err());
+    }
+
+    #[test]
+    pub fn validate_rejects_append_with_compressed_output() {
+        let configuration = Configuration {
+            output_directory: std::env::temp_dir(),
+            append_csv: true,
+            gzip_output: true,
+            ..Configuration::default()
+        };
+
+        let err = configuration.validate().unwrap_err();
+        assert!(matches!(err, DnLibError::ConfigError(_)));
+        assert!(err.to_string().starts_with("append_csv:"), "{}", err);
+    }
+
+    #[test]
+    pub fn validate_rejects_an_output_directory_under_a_file() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let configuration = Configuration {
+            output_directory: file.path().join("out"),
+            ..Configuration::default()
+        };
+
+        let err = configuration.validate().unwrap_err();
+        assert!(matches!(err, DnLibError::ConfigError(_)));
+        let msg = err.to_string();
+        assert!(msg.starts_with("output_directory:"), "{}", msg);
+        assert!(msg.ends_with("is not a directory"), "{}", msg);
+    }
+}
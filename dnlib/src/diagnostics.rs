@@ -0,0 +1,446 @@
+use crate::analysis::{Analysis, Project, Solution};
+use crate::enums::{DiagnosticCategory, FileStatus, Severity};
+use crate::errors::DnLibResult;
+use crate::io::PathExtensions;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A single finding from one of the checks, in a consistent shape so that the
+/// results of all the checks can be reported together.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Diagnostic {
+    /// Serialized as its `cli_name`, so that scripts can use the same names as `--fail-on`.
+    #[serde(serialize_with = "serialize_cli_name")]
+    pub category: DiagnosticCategory,
+    pub severity: Severity,
+    /// The path of the solution the finding relates to, if any.
+    pub solution: Option<String>,
+    /// The path of the project the finding relates to, if any.
+    pub project: Option<String>,
+    /// The 1-based line in the file that the finding was detected in, if the check can
+    /// pinpoint it: the solution file for findings about the solution's contents, otherwise
+    /// the project file.
+    pub line: Option<usize>,
+    pub detail: String,
+}
+
+fn serialize_cli_name<S: serde::Serializer>(category: &DiagnosticCategory, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(category.cli_name())
+}
+
+impl Diagnostic {
+    fn new(category: DiagnosticCategory, severity: Severity, sln: Option<&Solution>, proj: Option<&Project>, detail: String) -> Self {
+        Diagnostic {
+            category,
+            severity,
+            solution: sln.map(|s| s.file_info.path_as_str().to_owned()),
+            project: proj.map(|p| p.file_info.path_as_str().to_owned()),
+            line: None,
+            detail,
+        }
+    }
+}
+
+/// Converts a byte offset within `contents` into a 1-based line number.
+pub fn line_number(contents: &str, offset: usize) -> usize {
+    let offset = offset.min(contents.len());
+    contents.as_bytes()[..offset].iter().filter(|&&b| b == b'\n').count() + 1
+}
+
+/// Runs all the individual checks over the analysis and gathers their findings,
+/// ordered by category and then by solution and project.
+pub fn collect_diagnostics(analysis: &Analysis) -> Vec<Diagnostic> {
+    let mut result = vec![];
+    result.extend(orphaned_projects(analysis));
+    result.extend(dangling_references(analysis));
+    result.extend(version_conflicts(analysis));
+    result.extend(end_of_life_frameworks(analysis));
+    result.extend(mixed_package_styles(analysis));
+    result.extend(missing_package_versions(analysis));
+    result.extend(package_framework_mismatches(analysis));
+    result.sort();
+    result
+}
+
+/// A machine-readable summary of the diagnostics, for scripts such as pre-commit
+/// hooks. It is written as a single JSON object by `--porcelain`. Fields may be added
+/// but will not be renamed or removed, so scripts can rely on them.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsReport<'a> {
+    pub solutions: usize,
+    pub projects: usize,
+    /// The total number of diagnostics.
+    pub problems: usize,
+    /// The number of diagnostics in each category that has any, keyed by the
+    /// category's `cli_name`, e.g. "dangling-refs".
+    pub counts: BTreeMap<&'static str, usize>,
+    pub diagnostics: &'a [Diagnostic],
+}
+
+impl<'a> DiagnosticsReport<'a> {
+    pub fn new(analysis: &Analysis, diagnostics: &'a [Diagnostic]) -> Self {
+        let mut counts = BTreeMap::new();
+        for diag in diagnostics {
+            *counts.entry(diag.category.cli_name()).or_insert(0) += 1;
+        }
+
+        DiagnosticsReport {
+            solutions: analysis.num_solutions(),
+            projects: analysis.projects().count(),
+            problems: diagnostics.len(),
+            counts,
+            diagnostics,
+        }
+    }
+
+    pub fn to_json(&self) -> DnLibResult<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+/// Keeps only the diagnostics that are at least as severe as `min_severity`.
+pub fn filter_by_severity(diagnostics: Vec<Diagnostic>, min_severity: Severity) -> Vec<Diagnostic> {
+    diagnostics.into_iter().filter(|d| d.severity >= min_severity).collect()
+}
+
+/// Every project paired with the solution it belongs to. Projects that could not be
+/// associated with a solution are paired with None.
+fn projects_with_solutions(analysis: &Analysis) -> impl Iterator<Item = (Option<&Solution>, &Project)> {
+    analysis.solution_directories.iter()
+        .flat_map(|sd| sd.solutions.iter())
+        .flat_map(|sln| sln.projects.iter().map(move |proj| (Some(sln), proj)))
+        .chain(analysis.unassociated_projects.iter().map(|proj| (None, proj)))
+}
+
+fn orphaned_projects(analysis: &Analysis) -> Vec<Diagnostic> {
+    analysis.solution_directories.iter()
+        .flat_map(|sd| sd.solutions.iter())
+        .flat_map(|sln| sln.orphaned_projects().map(move |proj| Diagnostic::new(
+            DiagnosticCategory::OrphanedProject,
+            Severity::Warning,
+            Some(sln),
+            Some(proj),
+            "Project is in the solution's directory tree but is not mentioned in the solution".to_owned(),
+        )))
+        .collect()
+}
+
+fn dangling_references(analysis: &Analysis) -> Vec<Diagnostic> {
+    analysis.missing_projects().into_iter()
+        .map(|(sln, path)| Diagnostic {
+            project: Some(path.as_str().to_owned()),
+            line: sln.mention_offset(path).map(|offset| line_number(&sln.file_info.contents, offset)),
+            ..Diagnostic::new(
+                DiagnosticCategory::DanglingReference,
+                Severity::Error,
+                Some(sln),
+                None,
+                "Project is mentioned in the solution but does not exist on disk".to_owned(),
+            )
+        })
+        .collect()
+}
+
+fn version_conflicts(analysis: &Analysis) -> Vec<Diagnostic> {
+    let intra_project = projects_with_solutions(analysis)
+        .flat_map(|(sln, proj)| proj.intra_project_package_conflicts.iter().map(move |(name, versions)| Diagnostic::new(
+            DiagnosticCategory::VersionConflict,
+            Severity::Error,
+            sln,
+            Some(proj),
+            format!("Package {} is referenced at several versions: {}", name, versions.join(", ")),
+        )));
+
+    let downgrades = analysis.potential_package_downgrades().into_iter()
+        .map(|d| Diagnostic::new(
+            DiagnosticCategory::VersionConflict,
+            Severity::Warning,
+            None,
+            Some(d.parent),
+            format!("Package {} is referenced at {} but {} requires {}",
+                d.package_name, d.parent_version, d.child.file_info.filename_as_str(), d.child_version),
+        ));
+
+    intra_project.chain(downgrades).collect()
+}
+
+fn end_of_life_frameworks(analysis: &Analysis) -> Vec<Diagnostic> {
+    projects_with_solutions(analysis)
+        .flat_map(|(sln, proj)| proj.resolved_target_frameworks.iter()
+            .filter(|tf| is_end_of_life_framework(tf))
+            .map(move |tf| Diagnostic::new(
+                DiagnosticCategory::EndOfLifeFramework,
+                Severity::Warning,
+                sln,
+                Some(proj),
+                format!("Target framework {} is no longer supported", tf),
+            )))
+        .collect()
+}
+
+fn mixed_package_styles(analysis: &Analysis) -> Vec<Diagnostic> {
+    lazy_static! {
+        static ref PACKAGE_REFERENCE_REGEX: Regex = Regex::new(r#"<PackageReference\s"#).unwrap();
+    }
+
+    projects_with_solutions(analysis)
+        .filter(|(_, proj)| {
+            proj.packages_config != FileStatus::Unknown
+                && proj.packages_config != FileStatus::NotPresent
+        })
+        .filter_map(|(sln, proj)| {
+            let contents = &proj.file_info.contents;
+            PACKAGE_REFERENCE_REGEX.find(contents).map(|m| Diagnostic {
+                line: Some(line_number(contents, m.start())),
+                ..Diagnostic::new(
+                    DiagnosticCategory::MixedPackageStyles,
+                    Severity::Warning,
+                    sln,
+                    Some(proj),
+                    "Project has both PackageReferences and a packages.config file".to_owned(),
+                )
+            })
+        })
+        .collect()
+}
+
+fn missing_package_versions(analysis: &Analysis) -> Vec<Diagnostic> {
+    projects_with_solutions(analysis)
+        .flat_map(|(sln, proj)| proj.packages.iter()
+            .filter(|pkg| pkg.missing_version)
+            .map(move |pkg| Diagnostic::new(
+                DiagnosticCategory::MissingPackageVersion,
+                Severity::Warning,
+                sln,
+                Some(proj),
+                format!("Package {} is referenced without a version", pkg.name),
+            )))
+        .collect()
+}
+
+fn package_framework_mismatches(analysis: &Analysis) -> Vec<Diagnostic> {
+    projects_with_solutions(analysis)
+        .flat_map(|(sln, proj)| proj.packages.iter()
+            .filter(|pkg| pkg.framework_mismatch)
+            .map(move |pkg| Diagnostic::new(
+                DiagnosticCategory::PackageFrameworkMismatch,
+                Severity::Warning,
+                sln,
+                Some(proj),
+                format!("Package {} in packages.config was installed for a different framework than {}",
+                    pkg.name, proj.resolved_target_frameworks.join(", ")),
+            )))
+        .collect()
+}
+
+/// Returns true if the target framework, in either SDK-style ("net452", "netcoreapp3.1")
+/// or old-style ("v4.5.2") form, is one that Microsoft no longer supports.
+pub fn is_end_of_life_framework(target_framework: &str) -> bool {
+    lazy_static! {
+        static ref NET_FRAMEWORK_REGEX: Regex = Regex::new(r#"^(?:net(?P<tfm>[1-4]\d{1,2})|v(?P<ver>\d(?:\.\d)+))$"#).unwrap();
+        static ref NET_CORE_REGEX: Regex = Regex::new(r#"^(?:netcoreapp\d|net[567]\.)"#).unwrap();
+    }
+
+    let tf = target_framework.trim().to_lowercase();
+
+    if NET_CORE_REGEX.is_match(&tf) {
+        return true;
+    }
+
+    // .Net Framework 4.6.2 and later are still supported.
+    NET_FRAMEWORK_REGEX.captures(&tf)
+        .map(|cap| {
+            let ver = cap.name("tfm").or_else(|| cap.name("ver")).unwrap().as_str();
+            let digits = ver.replace('.', "");
+            let padded = format!("{:0<3}", digits);
+            padded.as_str() < "462"
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{Package, SolutionDirectory};
+    use crate::configuration::Configuration;
+    use crate::enums::ProjectOwnership;
+    use crate::io::MemoryFileLoader;
+    use std::path::PathBuf;
+
+    fn make_project(path: &str, ownership: ProjectOwnership) -> Project {
+        let mut proj = Project::default();
+        proj.file_info.path = PathBuf::from(path);
+        proj.ownership = ownership;
+        proj
+    }
+
+    fn make_analysis() -> Analysis {
+        let mut eol = make_project("/temp/eol.csproj", ProjectOwnership::Linked);
+        // The end of life framework is only known once the property is resolved.
+        eol.target_frameworks = vec!["$(LegacyFramework)".to_owned(), "net48".to_owned()];
+        eol.framework_is_variable = true;
+        eol.resolved_target_frameworks = vec!["net452".to_owned(), "net48".to_owned()];
+
+        let mut mixed = make_project("/temp/mixed.csproj", ProjectOwnership::Linked);
+        mixed.packages_config = FileStatus::OnDiskOnly;
+        mixed.file_info.contents = "<Project>\n  <ItemGroup>\n    <PackageReference Include=\"Unity\" Version=\"4.0.1\" />".to_owned();
+
+        let mut conflicted = make_project("/temp/conflicted.csproj", ProjectOwnership::Linked);
+        conflicted.packages = vec![Package::new("Unity", "4.0.1", false, ""), Package::new("Unity", "5.0.0", false, "")];
+        conflicted.intra_project_package_conflicts = vec![("Unity".to_owned(), vec!["4.0.1".to_owned(), "5.0.0".to_owned()])];
+
+        let orphan = make_project("/temp/orphan.csproj", ProjectOwnership::Orphaned);
+
+        let sln_path = PathBuf::from("/temp/car.sln");
+        let mut file_loader = MemoryFileLoader::new();
+        file_loader.files.insert(sln_path.clone(),
+            "\"eol.csproj\"\n\"mixed.csproj\"\n\"conflicted.csproj\"\n\"deleted.csproj\"".to_owned());
+        let mut sln = Solution::new(&sln_path, &file_loader, &Configuration::default());
+        sln.projects = vec![eol, mixed, conflicted, orphan];
+
+        let mut analysis = Analysis::default();
+        analysis.paths_analyzed.csproj_files = sln.projects.iter().map(|p| p.file_info.path.clone()).collect();
+        analysis.solution_directories = vec![SolutionDirectory { solutions: vec![sln], ..Default::default() }];
+        analysis
+    }
+
+    fn find(diagnostics: &[Diagnostic], category: DiagnosticCategory) -> Vec<&Diagnostic> {
+        diagnostics.iter().filter(|d| d.category == category).collect()
+    }
+
+    #[test]
+    pub fn collect_diagnostics_includes_each_category() {
+        let diagnostics = collect_diagnostics(&make_analysis());
+        assert_eq!(diagnostics.len(), 5);
+
+        let orphans = find(&diagnostics, DiagnosticCategory::OrphanedProject);
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].severity, Severity::Warning);
+        assert_eq!(orphans[0].solution.as_deref(), Some("/temp/car.sln"));
+        assert_eq!(orphans[0].project.as_deref(), Some("/temp/orphan.csproj"));
+
+        let dangling = find(&diagnostics, DiagnosticCategory::DanglingReference);
+        assert_eq!(dangling.len(), 1);
+        assert_eq!(dangling[0].severity, Severity::Error);
+        assert_eq!(dangling[0].project.as_deref(), Some("/temp/deleted.csproj"));
+        assert_eq!(dangling[0].line, Some(4));
+
+        let conflicts = find(&diagnostics, DiagnosticCategory::VersionConflict);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].project.as_deref(), Some("/temp/conflicted.csproj"));
+        assert!(conflicts[0].detail.contains("4.0.1, 5.0.0"));
+
+        let eol = find(&diagnostics, DiagnosticCategory::EndOfLifeFramework);
+        assert_eq!(eol.len(), 1);
+        assert_eq!(eol[0].project.as_deref(), Some("/temp/eol.csproj"));
+        assert!(eol[0].detail.contains("net452"));
+
+        let mixed = find(&diagnostics, DiagnosticCategory::MixedPackageStyles);
+        assert_eq!(mixed.len(), 1);
+        assert_eq!(mixed[0].project.as_deref(), Some("/temp/mixed.csproj"));
+        assert_eq!(mixed[0].line, Some(3));
+
+        assert_eq!(orphans[0].line, None);
+    }
+
+    #[test]
+    pub fn diagnostics_report_has_the_expected_keys() {
+        let analysis = make_analysis();
+        let diagnostics = collect_diagnostics(&analysis);
+        let json = DiagnosticsReport::new(&analysis, &diagnostics).to_json().unwrap();
+        let report: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        let keys = report.as_object().unwrap().keys().map(|k| k.as_str()).collect::<Vec<_>>();
+        assert_eq!(keys, vec!["counts", "diagnostics", "problems", "projects", "solutions"]);
+        assert_eq!(report["solutions"], 1);
+        assert_eq!(report["projects"], 4);
+        assert_eq!(report["problems"], 5);
+        assert_eq!(report["counts"]["dangling-refs"], 1);
+        assert_eq!(report["counts"]["orphaned-projects"], 1);
+        assert!(report["counts"].get("missing-package-versions").is_none());
+
+        let dangling = report["diagnostics"].as_array().unwrap().iter()
+            .find(|d| d["category"] == "dangling-refs")
+            .unwrap();
+        assert_eq!(dangling["severity"], "Error");
+        assert_eq!(dangling["project"], "/temp/deleted.csproj");
+        assert_eq!(dangling["line"], 4);
+    }
+
+    #[test]
+    pub fn collect_diagnostics_reports_missing_package_versions() {
+        let mut proj = make_project("/temp/versionless.csproj", ProjectOwnership::Linked);
+        proj.packages = vec![
+            Package { missing_version: true, ..Package::new("Serilog", "", false, "") },
+            Package::new("Unity", "4.0.1", false, ""),
+        ];
+
+        let analysis = Analysis { unassociated_projects: vec![proj], ..Default::default() };
+        let diagnostics = collect_diagnostics(&analysis);
+        let missing = find(&diagnostics, DiagnosticCategory::MissingPackageVersion);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].project.as_deref(), Some("/temp/versionless.csproj"));
+        assert!(missing[0].detail.contains("Serilog"));
+    }
+
+    #[test]
+    pub fn collect_diagnostics_reports_package_framework_mismatches() {
+        let mut proj = make_project("/temp/retargeted.csproj", ProjectOwnership::Linked);
+        proj.target_frameworks = vec!["v4.6.2".to_owned()];
+        proj.resolved_target_frameworks = proj.target_frameworks.clone();
+        proj.packages = vec![
+            Package { framework_mismatch: true, ..Package::new("Castle.Core", "4.3.1", false, "") },
+            Package::new("Owin", "1.0", false, ""),
+        ];
+
+        let analysis = Analysis { unassociated_projects: vec![proj], ..Default::default() };
+        let diagnostics = collect_diagnostics(&analysis);
+        let mismatches = find(&diagnostics, DiagnosticCategory::PackageFrameworkMismatch);
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].project.as_deref(), Some("/temp/retargeted.csproj"));
+        assert!(mismatches[0].detail.contains("Castle.Core"));
+    }
+
+    #[test]
+    pub fn collect_diagnostics_on_empty_analysis_is_empty() {
+        assert!(collect_diagnostics(&Analysis::default()).is_empty());
+    }
+
+    #[test]
+    pub fn filter_by_severity_works() {
+        let make = |severity| Diagnostic::new(DiagnosticCategory::OrphanedProject, severity, None, None, String::new());
+        let diagnostics = vec![make(Severity::Info), make(Severity::Warning), make(Severity::Error)];
+
+        assert_eq!(filter_by_severity(diagnostics.clone(), Severity::Info).len(), 3);
+
+        let severities = filter_by_severity(diagnostics.clone(), Severity::Warning).into_iter().map(|d| d.severity).collect::<Vec<_>>();
+        assert_eq!(severities, vec![Severity::Warning, Severity::Error]);
+
+        let severities = filter_by_severity(diagnostics, Severity::Error).into_iter().map(|d| d.severity).collect::<Vec<_>>();
+        assert_eq!(severities, vec![Severity::Error]);
+    }
+
+    #[test]
+    pub fn line_number_works() {
+        let contents = "first\nsecond\r\nthird";
+        assert_eq!(line_number(contents, 0), 1);
+        assert_eq!(line_number(contents, 5), 1);
+        assert_eq!(line_number(contents, 6), 2);
+        assert_eq!(line_number(contents, contents.find("third").unwrap()), 3);
+        assert_eq!(line_number(contents, 1000), 3);
+        assert_eq!(line_number("", 0), 1);
+    }
+
+    #[test]
+    pub fn is_end_of_life_framework_works() {
+        for tf in &["net20", "net35", "net40", "net45", "net452", "net461", "v4.5.2", "v4.6.1", "netcoreapp2.1", "netcoreapp3.1", "net5.0", "net7.0"] {
+            assert!(is_end_of_life_framework(tf), "{} should be end of life", tf);
+        }
+
+        for tf in &["net462", "net472", "net48", "v4.6.2", "v4.8", "netstandard2.0", "net8.0", "net8.0-windows", "net10.0", "net11.0", ""] {
+            assert!(!is_end_of_life_framework(tf), "{} should be supported", tf);
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use crate::io::FileLoader;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// The parts of an `.editorconfig` file that affect C# analyzers. This is not a full
+/// EditorConfig implementation: only sections that apply to every .cs file, such as
+/// `[*]`, `[*.cs]` and `[*.{cs,vb}]`, are considered.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EditorConfig {
+    /// True if the file sets `root = true`, so files above it are not consulted.
+    pub is_root: bool,
+
+    /// The severity set for each rule by a `dotnet_diagnostic.<rule>.severity`
+    /// property, e.g. "CA1822" => "error". Later settings win, as in the file.
+    pub rule_severities: BTreeMap<String, String>,
+}
+
+impl EditorConfig {
+    pub fn parse(contents: &str) -> Self {
+        let mut result = EditorConfig::default();
+        // Properties before the first section are in the preamble, where only `root` is allowed.
+        let mut section: Option<bool> = None;
+
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                section = Some(Self::section_applies_to_cs_files(&line[1..line.len() - 1]));
+                continue;
+            }
+
+            let (key, value) = match line.find('=') {
+                Some(idx) => (line[..idx].trim().to_lowercase(), line[idx + 1..].trim()),
+                None => continue,
+            };
+
+            match section {
+                None if key == "root" => result.is_root = value.eq_ignore_ascii_case("true"),
+                Some(true) => {
+                    if let Some(rule) = key.strip_prefix("dotnet_diagnostic.").and_then(|k| k.strip_suffix(".severity")) {
+                        result.rule_severities.insert(rule.to_uppercase(), value.to_lowercase());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        result
+    }
+
+    fn section_applies_to_cs_files(glob: &str) -> bool {
+        let glob = glob.trim();
+        if glob == "*" || glob.eq_ignore_ascii_case("*.cs") {
+            return true;
+        }
+
+        glob.strip_prefix("*.{")
+            .and_then(|g| g.strip_suffix('}'))
+            .map_or(false, |exts| exts.split(',').any(|ext| ext.trim().eq_ignore_ascii_case("cs")))
+    }
+}
+
+/// The `.editorconfig` files read during an analysis. Many projects share the same
+/// ancestors, so each directory's file is read and parsed only once.
+#[derive(Debug, Default)]
+pub struct EditorConfigCache {
+    configs: HashMap<PathBuf, Option<EditorConfig>>,
+}
+
+impl EditorConfigCache {
+    fn get<L: FileLoader>(&mut self, directory: &Path, file_loader: &L) -> Option<&EditorConfig> {
+        self.configs.entry(directory.to_owned())
+            .or_insert_with(|| file_loader.read_to_string(&directory.join(".editorconfig")).ok().map(|c| EditorConfig::parse(&c)))
+            .as_ref()
+    }
+
+    /// Works out the analyzer rule severities that apply to the C# files in `directory`,
+    /// by combining the `.editorconfig` files in it and its parents. Nearer files take
+    /// precedence. The search stops at a file with `root = true`, at the root of the git
+    /// repository, or at `ceiling_dir`. Returns None if no `.editorconfig` was found.
+    pub fn analyzer_rule_severities<L: FileLoader>(
+        &mut self,
+        directory: &Path,
+        ceiling_dir: &Path,
+        file_loader: &L,
+    ) -> Option<BTreeMap<String, String>> {
+        let mut result: Option<BTreeMap<String, String>> = None;
+
+        for dir in directory.ancestors() {
+            if let Some(config) = self.get(dir, file_loader) {
+                let severities = result.get_or_insert_with(BTreeMap::new);
+                for (rule, severity) in &config.rule_severities {
+                    severities.entry(rule.clone()).or_insert_with(|| severity.clone());
+                }
+
+                if config.is_root {
+                    break;
+                }
+            }
+
+            if dir == ceiling_dir || dir.join(".git").exists() {
+                break;
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::MemoryFileLoader;
+    use std::cell::RefCell;
+    use std::io;
+    use std::rc::Rc;
+
+    fn severities(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(rule, severity)| (rule.to_string(), severity.to_string())).collect()
+    }
+
+    #[test]
+    pub fn parse_reads_rule_severities_from_cs_sections() {
+        let config = EditorConfig::parse(r#"
+            # Top-most EditorConfig file
+            root = true
+
+            [*]
+            indent_style = space
+            dotnet_diagnostic.CA1822.severity = suggestion
+
+            [*.cs]
+            dotnet_diagnostic.CA1822.severity = error
+            dotnet_diagnostic.ide0005.severity = Warning
+
+            [*.{cs,vb}]
+            dotnet_diagnostic.CA2007.severity = none
+
+            [*.md]
+            dotnet_diagnostic.CA1000.severity = error
+            "#);
+
+        assert!(config.is_root);
+        assert_eq!(config.rule_severities, severities(&[("CA1822", "error"), ("CA2007", "none"), ("IDE0005", "warning")]));
+    }
+
+    #[test]
+    pub fn parse_ignores_rules_in_the_preamble() {
+        let config = EditorConfig::parse("dotnet_diagnostic.CA1822.severity = error\n[*.cs]\n");
+        assert!(!config.is_root);
+        assert!(config.rule_severities.is_empty());
+    }
+
+    #[test]
+    pub fn nearer_editorconfigs_take_precedence() {
+        let mut file_loader = MemoryFileLoader::new();
+        file_loader.files.insert(PathBuf::from("/repo/.editorconfig"),
+            "root = true\n[*.cs]\ndotnet_diagnostic.CA1822.severity = error\ndotnet_diagnostic.CA2007.severity = warning\n".to_owned());
+        file_loader.files.insert(PathBuf::from("/repo/src/tests/.editorconfig"),
+            "[*.cs]\ndotnet_diagnostic.CA2007.severity = none\n".to_owned());
+        // Above the root = true file, so it does not count.
+        file_loader.files.insert(PathBuf::from("/.editorconfig"),
+            "[*.cs]\ndotnet_diagnostic.CA1000.severity = error\n".to_owned());
+
+        let mut cache = EditorConfigCache::default();
+        let ceiling = Path::new("/");
+        assert_eq!(cache.analyzer_rule_severities(Path::new("/repo/src/app"), ceiling, &file_loader),
+            Some(severities(&[("CA1822", "error"), ("CA2007", "warning")])));
+        assert_eq!(cache.analyzer_rule_severities(Path::new("/repo/src/tests/unit"), ceiling, &file_loader),
+            Some(severities(&[("CA1822", "error"), ("CA2007", "none")])));
+        assert_eq!(cache.analyzer_rule_severities(Path::new("/other"), Path::new("/other"), &file_loader), None);
+    }
+
+    /// Counts the attempts to read each file.
+    #[derive(Clone)]
+    struct CountingFileLoader {
+        inner: MemoryFileLoader,
+        reads: Rc<RefCell<Vec<PathBuf>>>,
+    }
+
+    impl FileLoader for CountingFileLoader {
+        fn read_to_string(&self, path: &Path) -> io::Result<String> {
+            self.reads.borrow_mut().push(path.to_owned());
+            self.inner.read_to_string(path)
+        }
+    }
+
+    #[test]
+    pub fn each_editorconfig_is_read_once() {
+        let mut inner = MemoryFileLoader::new();
+        inner.files.insert(PathBuf::from("/repo/.editorconfig"),
+            "root = true\n[*.cs]\ndotnet_diagnostic.CA1822.severity = error\n".to_owned());
+        let file_loader = CountingFileLoader { inner, reads: Rc::default() };
+
+        let mut cache = EditorConfigCache::default();
+        for dir in &["/repo/src/app", "/repo/src/lib", "/repo/src"] {
+            let result = cache.analyzer_rule_severities(Path::new(dir), Path::new("/"), &file_loader);
+            assert_eq!(result, Some(severities(&[("CA1822", "error")])));
+        }
+
+        let mut reads = file_loader.reads.borrow().clone();
+        let num_reads = reads.len();
+        reads.sort();
+        reads.dedup();
+        assert_eq!(num_reads, reads.len());
+    }
+}
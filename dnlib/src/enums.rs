@@ -2,10 +2,13 @@ use std::fmt;
 
 use lazy_static::lazy_static;
 use regex::Regex;
-use strum_macros::{AsRefStr};
+use strum::IntoEnumIterator;
+use strum_macros::{AsRefStr, EnumIter};
 use smart_default::SmartDefault;
+use serde::{Serialize, Deserialize};
+use crate::property_groups::PropertyGroup;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault, Serialize, Deserialize)]
 pub enum FileStatus {
     #[default]
     Unknown,
@@ -33,7 +36,10 @@ pub enum InterestingFile {
     PackagesConfig,
 
     /// The project.json (obsolete, should be removed)
-    ProjectJson
+    ProjectJson,
+
+    /// The GlobalUsings.cs file (C# 10 global using directives).
+    GlobalUsings
 }
 
 impl AsRef<str> for InterestingFile {
@@ -44,7 +50,8 @@ impl AsRef<str> for InterestingFile {
             InterestingFile::AppSettingsJson => "appsettings.json",
             InterestingFile::PackageJson => "package.json",
             InterestingFile::PackagesConfig => "packages.config",
-            InterestingFile::ProjectJson => "project.json"
+            InterestingFile::ProjectJson => "project.json",
+            InterestingFile::GlobalUsings => "GlobalUsings.cs"
         }
     }
 }
@@ -61,6 +68,7 @@ impl std::str::FromStr for InterestingFile {
             "package.json" => Ok(InterestingFile::PackageJson),
             "packages.config" => Ok(InterestingFile::PackagesConfig),
             "project.json" => Ok(InterestingFile::ProjectJson),
+            "globalusings.cs" => Ok(InterestingFile::GlobalUsings),
             _ => Err(())
         }
     }
@@ -72,7 +80,7 @@ impl fmt::Display for InterestingFile {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault, Serialize, Deserialize)]
 pub enum OutputType {
     #[default]
     Unknown,
@@ -102,15 +110,164 @@ impl OutputType {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault, Serialize, Deserialize)]
 pub enum ProjectOwnership {
     #[default]
     Unknown,
     Linked,
     Orphaned,
+
+    /// The project is not mentioned in the solution, but is reachable via
+    /// `<ProjectReference>` chains from projects that are, so it needs to be
+    /// added to the solution.
+    TransitivelyRequired,
+}
+
+/// Why an orphaned project was associated with its solution, given that the
+/// solution does not mention it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, Serialize, Deserialize)]
+pub enum OrphanReason {
+    /// The project is in the same directory as the sln file.
+    SameDirectory,
+
+    /// The project is in an immediate subdirectory of the sln file's directory.
+    ParentDirectory,
+}
+
+/// How serious a diagnostic finding is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault, Serialize, Deserialize)]
+pub enum Severity {
+    /// Worth knowing about, but not necessarily a problem.
+    #[default]
+    Info,
+
+    /// Likely to cause problems or confusion, and should be tidied up.
+    Warning,
+
+    /// Will cause, or is already causing, broken builds.
+    Error,
+}
+
+/// The kind of check that produced a diagnostic finding.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, EnumIter, SmartDefault, Serialize, Deserialize)]
+pub enum DiagnosticCategory {
+    /// A project in a solution's directory tree that the solution does not mention.
+    #[default]
+    OrphanedProject,
+
+    /// A project mentioned in a solution that does not exist on disk.
+    DanglingReference,
+
+    /// A package referenced at different versions, either within one project or
+    /// across a project reference.
+    VersionConflict,
+
+    /// A project targeting a framework that is no longer supported by Microsoft.
+    EndOfLifeFramework,
+
+    /// A project that has both `<PackageReference>`s and a packages.config file.
+    MixedPackageStyles,
+
+    /// A `<PackageReference>` with no version, in a project that does not use
+    /// central package management.
+    MissingPackageVersion,
+
+    /// A package in packages.config whose `targetFramework` is not one the project
+    /// targets, typically because the project was retargeted.
+    PackageFrameworkMismatch,
+}
+
+impl DiagnosticCategory {
+    /// The name used for the category on the command line, e.g. "dangling-refs".
+    pub fn cli_name(&self) -> &'static str {
+        match self {
+            DiagnosticCategory::OrphanedProject => "orphaned-projects",
+            DiagnosticCategory::DanglingReference => "dangling-refs",
+            DiagnosticCategory::VersionConflict => "version-conflicts",
+            DiagnosticCategory::EndOfLifeFramework => "eol-frameworks",
+            DiagnosticCategory::MixedPackageStyles => "mixed-package-styles",
+            DiagnosticCategory::MissingPackageVersion => "missing-package-versions",
+            DiagnosticCategory::PackageFrameworkMismatch => "package-framework-mismatches",
+        }
+    }
+}
+
+impl std::str::FromStr for DiagnosticCategory {
+    type Err = String;
+
+    /// Parses the `cli_name` of a category, ignoring case.
+    fn from_str(s: &str) -> Result<DiagnosticCategory, Self::Err> {
+        DiagnosticCategory::iter()
+            .find(|category| category.cli_name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                let valid = DiagnosticCategory::iter().map(|c| c.cli_name()).collect::<Vec<_>>();
+                format!("unknown diagnostic category '{}', expected one of {}", s.trim(), valid.join(", "))
+            })
+    }
+}
+
+/// The set of colors used when drawing graphs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault, Serialize, Deserialize)]
+pub enum Palette {
+    /// The original X11 colors.
+    #[default]
+    Default,
+
+    /// The color-blind-friendly palette recommended by the Color Universal Design
+    /// Organization (Okabe and Ito).
+    Cud,
+}
+
+/// What the solution and project nodes on the graphs are labelled with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault, Serialize, Deserialize)]
+pub enum LabelMode {
+    /// The file stem, e.g. "Payments.Api".
+    #[default]
+    Stem,
+
+    /// The path relative to the scan directory, which is unique even when two
+    /// projects share a stem.
+    Relative,
+
+    /// The full path.
+    Full,
+}
+
+/// How the redundant edges, which transitive reduction removed from a project
+/// graph, are drawn in the DOT files.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault, Serialize, Deserialize)]
+pub enum RedundantEdgeStyle {
+    /// The edges are left out.
+    Hidden,
+
+    /// Dashed, in the same color as the other edges.
+    Dashed,
+
+    /// Thick, red and dotted, so that they stand out.
+    #[default]
+    Red,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault)]
+/// Where a project sits in the dependency graph of its solution.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault, Serialize, Deserialize)]
+pub enum GraphRole {
+    #[default]
+    Unknown,
+
+    /// Nothing references the project, but it references other projects.
+    Root,
+
+    /// The project references nothing, but other projects reference it.
+    Leaf,
+
+    /// The project neither references nor is referenced by anything.
+    Isolated,
+
+    /// The project both references and is referenced by other projects.
+    Internal,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault, Serialize, Deserialize)]
 pub enum ProjectVersion {
     #[default]
     Unknown,
@@ -156,7 +313,33 @@ impl fmt::Display for ProjectVersion {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault)]
+/// The line endings used in a file. Files that mix them cause noisy diffs, and
+/// usually mean that `.gitattributes` is not normalizing them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault, Serialize, Deserialize)]
+pub enum LineEnding {
+    /// The file has no line endings at all, e.g. because it is empty.
+    #[default]
+    None,
+    Crlf,
+    Lf,
+    Mixed,
+}
+
+impl LineEnding {
+    pub fn extract(file_contents: &str) -> LineEnding {
+        let lf_count = file_contents.matches('\n').count();
+        let crlf_count = file_contents.matches("\r\n").count();
+
+        match (crlf_count, lf_count) {
+            (_, 0) => LineEnding::None,
+            (0, _) => LineEnding::Lf,
+            (crlf, lf) if crlf == lf => LineEnding::Crlf,
+            _ => LineEnding::Mixed,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault, Serialize, Deserialize)]
 pub enum TestFramework {
     #[default]
     None,
@@ -165,7 +348,7 @@ pub enum TestFramework {
     NUnit,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault, Serialize, Deserialize)]
 pub enum VisualStudioVersion {
     #[default]
     Unknown,
@@ -188,7 +371,7 @@ impl VisualStudioVersion {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, AsRefStr, SmartDefault, Serialize, Deserialize)]
 pub enum XmlDoc {
     #[default]
     Unknown,
@@ -207,13 +390,33 @@ pub enum XmlDoc {
 }
 
 impl XmlDoc {
+    /// Works out which configurations generate XML documentation. A `DocumentationFile`
+    /// in a Debug or Release conditioned property group applies to that configuration only.
+    /// An unconditional one is attributed by its path (bin\Debug\ or bin\Release\), and
+    /// failing that is assumed to apply to both.
     pub fn extract(project_file_contents: &str) -> XmlDoc {
         lazy_static! {
-            static ref DEBUG_RE: Regex = Regex::new(r##"<DocumentationFile>bin\\[Dd]ebug\\.*?\.xml</DocumentationFile>"##).unwrap();
-            static ref RELEASE_RE: Regex = Regex::new(r##"<DocumentationFile>bin\\[Rr]elease\\.*?\.xml</DocumentationFile>"##).unwrap();
+            static ref DEBUG_RE: Regex = Regex::new(r##"^bin\\[Dd]ebug\\"##).unwrap();
+            static ref RELEASE_RE: Regex = Regex::new(r##"^bin\\[Rr]elease\\"##).unwrap();
+        }
+
+        let mut debug = false;
+        let mut release = false;
+
+        for group in PropertyGroup::parse_all(project_file_contents) {
+            for doc_file in group.values("DocumentationFile").map(str::trim).filter(|v| !v.is_empty()) {
+                match group.configuration() {
+                    Some(c) if c.eq_ignore_ascii_case("Debug") => debug = true,
+                    Some(c) if c.eq_ignore_ascii_case("Release") => release = true,
+                    Some(_) => {},
+                    None if DEBUG_RE.is_match(doc_file) => debug = true,
+                    None if RELEASE_RE.is_match(doc_file) => release = true,
+                    None => { debug = true; release = true; }
+                }
+            }
         }
 
-        match (DEBUG_RE.is_match(project_file_contents), RELEASE_RE.is_match(project_file_contents)) {
+        match (debug, release) {
             (true, true) => XmlDoc::Both,
             (true, false) => XmlDoc::Debug,
             (false, true) => XmlDoc::Release,
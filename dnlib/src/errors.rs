@@ -1,15 +1,30 @@
 use std::error::Error;
+use std::path::{Path, PathBuf};
 use std::{io, fmt};
 
 #[derive(Debug)]
 pub enum DnLibError {
-    // An IO error occurred, for example when reading a file.
+    // An IO error occurred other than reading a file, for example when writing an output.
     IoError(String),
+    // A file could not be read.
+    FileReadError { path: PathBuf, message: String },
     // A directory walk error occurred. This may happen when scanning
     // the input directory for interesting files.
     WalkError(String),
     // A Git error occurred.
     GitError(String),
+    // A regular expression could not be compiled.
+    RegexError(String),
+    // A rayon thread pool could not be created.
+    ThreadPoolError(String),
+    // A saved analysis could not be written or read.
+    JsonError(String),
+    // A Protocol Buffers message could not be decoded.
+    ProtobufError(String),
+    // The configuration is invalid. The message names the offending field.
+    ConfigError(String),
+    // The solution filter did not match exactly one solution.
+    SolutionFilterError(String),
 }
 
 impl Error for DnLibError {
@@ -22,12 +37,26 @@ impl fmt::Display for DnLibError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             DnLibError::IoError(ref s) => write!(f, "{}", s),
+            DnLibError::FileReadError { ref path, ref message } => write!(f, "Could not read {:?}: {}", path, message),
             DnLibError::WalkError(ref s) => write!(f, "{}", s),
             DnLibError::GitError(ref s) => write!(f, "{}", s),
+            DnLibError::RegexError(ref s) => write!(f, "{}", s),
+            DnLibError::ThreadPoolError(ref s) => write!(f, "{}", s),
+            DnLibError::JsonError(ref s) => write!(f, "{}", s),
+            DnLibError::ProtobufError(ref s) => write!(f, "{}", s),
+            DnLibError::ConfigError(ref s) => write!(f, "{}", s),
+            DnLibError::SolutionFilterError(ref s) => write!(f, "{}", s),
         }
     }
 }
 
+impl DnLibError {
+    /// Wraps an error from reading the file at `path`, so that callers know which file failed.
+    pub fn file_read<P: AsRef<Path>>(path: P, err: io::Error) -> DnLibError {
+        DnLibError::FileReadError { path: path.as_ref().to_owned(), message: err.to_string() }
+    }
+}
+
 impl From<io::Error> for DnLibError {
     fn from(err: io::Error) -> DnLibError {
         DnLibError::IoError(err.to_string())
@@ -46,4 +75,28 @@ impl From<git2::Error> for DnLibError {
     }
 }
 
+impl From<regex::Error> for DnLibError {
+    fn from(err: regex::Error) -> DnLibError {
+        DnLibError::RegexError(err.to_string())
+    }
+}
+
+impl From<rayon::ThreadPoolBuildError> for DnLibError {
+    fn from(err: rayon::ThreadPoolBuildError) -> DnLibError {
+        DnLibError::ThreadPoolError(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for DnLibError {
+    fn from(err: serde_json::Error) -> DnLibError {
+        DnLibError::JsonError(err.to_string())
+    }
+}
+
+impl From<prost::DecodeError> for DnLibError {
+    fn from(err: prost::DecodeError) -> DnLibError {
+        DnLibError::ProtobufError(err.to_string())
+    }
+}
+
 pub type DnLibResult<T> = std::result::Result<T, DnLibError>;
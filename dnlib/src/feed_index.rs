@@ -0,0 +1,155 @@
+use crate::analysis::{compare_package_versions, Analysis, Package, Project};
+use crate::errors::{DnLibError, DnLibResult};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use unicase::UniCase;
+
+/// The latest version of each package on a feed, read from a JSON object mapping
+/// package names to versions, e.g. `{ "Serilog": "2.10.0" }`. This allows available
+/// updates to be found without going to the network. Package names are matched
+/// ignoring case, as NuGet does.
+#[derive(Debug, Default, Clone)]
+pub struct FeedIndex {
+    latest_versions: HashMap<UniCase<String>, String>,
+}
+
+impl FeedIndex {
+    /// Reads a feed index from a JSON file.
+    pub fn load<P: AsRef<Path>>(path: P) -> DnLibResult<Self> {
+        let path = path.as_ref();
+        let reader = BufReader::new(File::open(path).map_err(|e| DnLibError::file_read(path, e))?);
+        let latest_versions: HashMap<String, String> = serde_json::from_reader(reader)?;
+        Ok(latest_versions.into_iter().collect())
+    }
+
+    pub fn latest_version(&self, package_name: &str) -> Option<&str> {
+        self.latest_versions.get(&UniCase::new(package_name.to_owned())).map(|v| v.as_str())
+    }
+
+    /// Returns the latest version of the package if it is newer than the version
+    /// the package is referenced at. Packages without a version are not compared,
+    /// since NuGet decides what they resolve to.
+    pub fn update_for(&self, pkg: &Package) -> Option<String> {
+        if pkg.version.is_empty() {
+            return None;
+        }
+
+        self.latest_version(&pkg.name)
+            .filter(|latest| compare_package_versions(&pkg.version, latest) == Ordering::Less)
+            .map(|latest| latest.to_owned())
+    }
+}
+
+impl<N: Into<String>, V: Into<String>> std::iter::FromIterator<(N, V)> for FeedIndex {
+    fn from_iter<I: IntoIterator<Item = (N, V)>>(iter: I) -> Self {
+        FeedIndex {
+            latest_versions: iter.into_iter()
+                .map(|(name, version)| (UniCase::new(name.into()), version.into()))
+                .collect(),
+        }
+    }
+}
+
+/// Sets `update_available` on every package in the analysis that is behind the
+/// version in the feed index.
+pub fn flag_available_updates(analysis: &mut Analysis, feed_index: &FeedIndex) {
+    let projects = analysis.solution_directories.iter_mut()
+        .flat_map(|sd| sd.solutions.iter_mut())
+        .flat_map(|sln| sln.projects.iter_mut())
+        .chain(analysis.unassociated_projects.iter_mut());
+
+    for proj in projects {
+        for pkg in &mut proj.packages {
+            pkg.update_available = feed_index.update_for(pkg);
+        }
+    }
+}
+
+/// Returns every package that has an update available, with the project that
+/// references it, ordered by project path and then package.
+pub fn available_updates(analysis: &Analysis) -> Vec<(&Project, &Package)> {
+    let mut result = analysis.projects()
+        .flat_map(|proj| proj.packages.iter().map(move |pkg| (proj, pkg)))
+        .filter(|(_, pkg)| pkg.update_available.is_some())
+        .collect::<Vec<_>>();
+
+    result.sort_by(|a, b| a.0.file_info.path.cmp(&b.0.file_info.path).then_with(|| a.1.cmp(b.1)));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn make_feed_index() -> FeedIndex {
+        vec![("Serilog", "2.10.0"), ("Unity", "5.11.1"), ("Castle.Core", "4.4.1")].into_iter().collect()
+    }
+
+    fn make_project(path: &str, packages: Vec<Package>) -> Project {
+        let mut proj = Project::default();
+        proj.file_info.path = PathBuf::from(path);
+        proj.packages = packages;
+        proj
+    }
+
+    #[test]
+    pub fn update_for_only_returns_newer_versions() {
+        let feed_index = make_feed_index();
+        assert_eq!(feed_index.update_for(&Package::new("Serilog", "2.8.0", false, "")), Some("2.10.0".to_owned()));
+        assert_eq!(feed_index.update_for(&Package::new("Serilog", "2.10.0-dev1", false, "")), Some("2.10.0".to_owned()));
+        assert_eq!(feed_index.update_for(&Package::new("Serilog", "2.10.0", false, "")), None);
+        assert_eq!(feed_index.update_for(&Package::new("Serilog", "3.0.0", false, "")), None);
+        assert_eq!(feed_index.update_for(&Package::new("Serilog", "", false, "")), None);
+        assert_eq!(feed_index.update_for(&Package::new("Newtonsoft.Json", "9.0.1", false, "")), None);
+    }
+
+    #[test]
+    pub fn package_names_are_matched_ignoring_case() {
+        let feed_index = make_feed_index();
+        assert_eq!(feed_index.latest_version("castle.core"), Some("4.4.1"));
+        assert_eq!(feed_index.update_for(&Package::new("UNITY", "4.0.1", false, "")), Some("5.11.1".to_owned()));
+    }
+
+    #[test]
+    pub fn flag_available_updates_joins_the_index_against_all_projects() {
+        let mut analysis = Analysis::default();
+        analysis.unassociated_projects = vec![
+            make_project("/temp/b.csproj", vec![
+                Package::new("Serilog", "2.10.0", false, ""),
+                Package::new("Unity", "4.0.1", false, ""),
+            ]),
+            make_project("/temp/a.csproj", vec![
+                Package::new("Castle.Core", "4.3.1", false, ""),
+                Package::new("Moq", "4.7.0", true, ""),
+            ]),
+        ];
+
+        flag_available_updates(&mut analysis, &make_feed_index());
+
+        let updates = available_updates(&analysis).into_iter()
+            .map(|(proj, pkg)| (proj.file_info.path.to_str().unwrap(), pkg.name.as_str(), pkg.update_available.as_deref().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(updates, vec![
+            ("/temp/a.csproj", "Castle.Core", "4.4.1"),
+            ("/temp/b.csproj", "Unity", "5.11.1"),
+        ]);
+    }
+
+    #[test]
+    pub fn load_reads_a_json_object() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("feed.json");
+        std::fs::write(&path, r#"{ "Serilog": "2.10.0", "Unity": "5.11.1" }"#).unwrap();
+
+        let feed_index = FeedIndex::load(&path).unwrap();
+        assert_eq!(feed_index.latest_version("Serilog"), Some("2.10.0"));
+        assert_eq!(feed_index.latest_version("Unity"), Some("5.11.1"));
+
+        std::fs::write(&path, "[1, 2]").unwrap();
+        assert!(matches!(FeedIndex::load(&path), Err(DnLibError::JsonError(_))));
+    }
+}
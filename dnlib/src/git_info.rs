@@ -1,9 +1,12 @@
 use std::path::Path;
 use std::ffi::OsStr;
+use std::fs;
+use std::sync::{Condvar, Mutex};
 use crate::errors::DnLibResult;
 use git2::{Repository, RepositoryOpenFlags, Remote};
+use serde::{Serialize, Deserialize};
 
-#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 /// Represents information about the Git repository.
 pub struct GitInfo {
     pub branch: String,
@@ -14,6 +17,61 @@ pub struct GitInfo {
     pub author_email: String,
     pub remote_name: String,
     pub remote_url: String,
+    pub is_submodule: bool,
+}
+
+/// Limits the number of git operations that are in flight at once. Each one opens
+/// a repository and reads its history, so running one for every solution directory
+/// at the same time can overwhelm the machine on a large tree. All git access
+/// should go through `run`.
+#[derive(Debug)]
+pub struct GitExecutor {
+    max_in_flight: usize,
+    in_flight: Mutex<usize>,
+    finished: Condvar,
+}
+
+impl GitExecutor {
+    /// Creates an executor that runs at most `max_in_flight` operations at once.
+    /// A limit of 0 is treated as 1.
+    pub fn new(max_in_flight: usize) -> Self {
+        GitExecutor {
+            max_in_flight: max_in_flight.max(1),
+            in_flight: Mutex::new(0),
+            finished: Condvar::new(),
+        }
+    }
+
+    /// Runs `op`, first waiting until fewer than `max_in_flight` operations are running.
+    pub fn run<F, R>(&self, op: F) -> R
+    where F: FnOnce() -> R
+    {
+        let _permit = self.acquire();
+        op()
+    }
+
+    fn acquire(&self) -> Permit<'_> {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        while *in_flight >= self.max_in_flight {
+            in_flight = self.finished.wait(in_flight).unwrap();
+        }
+
+        *in_flight += 1;
+        Permit { executor: self }
+    }
+}
+
+/// Releases its slot in the executor when dropped, even if the operation panics.
+struct Permit<'a> {
+    executor: &'a GitExecutor,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        let mut in_flight = self.executor.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        *in_flight -= 1;
+        self.executor.finished.notify_one();
+    }
 }
 
 impl GitInfo {
@@ -40,6 +98,8 @@ impl GitInfo {
         gi.author = head_commit.author().name().unwrap_or_default().to_owned();
         gi.author_email = head_commit.author().email().unwrap_or_default().to_owned();
 
+        gi.is_submodule = repo.workdir().map(Self::is_submodule).unwrap_or_default();
+
         if let Some(remote) = Self::get_remote(&repo) {
             gi.remote_name = remote.name().unwrap_or_default().to_owned();
             gi.remote_url = remote.url().unwrap_or_default().to_owned();
@@ -72,6 +132,34 @@ impl GitInfo {
         Ok("".to_owned())
     }
 
+    /// Determines whether `workdir`, the root of a working tree, is a submodule of
+    /// another repository. A submodule has a `.git` file pointing into its parent's
+    /// `.git/modules` directory instead of a `.git` directory of its own (worktrees
+    /// also have a `.git` file, but it points elsewhere). Failing that, we look for
+    /// the directory in the nearest `.gitmodules` above it.
+    fn is_submodule(workdir: &Path) -> bool {
+        if let Ok(contents) = fs::read_to_string(workdir.join(".git")) {
+            let gitdir = contents.trim().trim_start_matches("gitdir:").trim().replace('\\', "/");
+            if gitdir.split('/').any(|component| component == "modules") {
+                return true;
+            }
+        }
+
+        for ancestor in workdir.ancestors().skip(1) {
+            if let Ok(contents) = fs::read_to_string(ancestor.join(".gitmodules")) {
+                let relative_path = workdir.strip_prefix(ancestor).unwrap_or(workdir)
+                    .to_string_lossy()
+                    .replace('\\', "/");
+
+                return contents.lines()
+                    .filter_map(|line| line.split_once('='))
+                    .any(|(key, value)| key.trim() == "path" && value.trim().trim_end_matches('/') == relative_path);
+            }
+        }
+
+        false
+    }
+
     fn get_remote(repo: &Repository) -> Option<Remote> {
         if let Ok(remote_names) = repo.remotes() {
             for remote_name in &remote_names {
@@ -86,3 +174,75 @@ impl GitInfo {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn git_executor_never_exceeds_its_limit() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let executor = Arc::new(GitExecutor::new(2));
+        let current = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let handles = (0..8).map(|i| {
+            let (executor, current, peak) = (executor.clone(), current.clone(), peak.clone());
+            std::thread::spawn(move || executor.run(|| {
+                let now = current.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(20));
+                current.fetch_sub(1, Ordering::SeqCst);
+                i
+            }))
+        }).collect::<Vec<_>>();
+
+        let results = handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>();
+        assert_eq!(results, (0..8).collect::<Vec<_>>());
+        assert!(peak.load(Ordering::SeqCst) <= 2, "peak was {}", peak.load(Ordering::SeqCst));
+        assert_eq!(current.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    pub fn git_executor_treats_zero_as_one() {
+        let executor = GitExecutor::new(0);
+        assert_eq!(executor.run(|| 42), 42);
+        assert_eq!(*executor.in_flight.lock().unwrap(), 0);
+    }
+
+    #[test]
+    pub fn is_submodule_for_git_file_pointing_into_modules() {
+        let root = tempfile::tempdir().unwrap();
+        let sub = root.path().join("libs").join("common");
+        fs::create_dir_all(&sub).unwrap();
+        fs::write(sub.join(".git"), "gitdir: ../../.git/modules/libs/common\n").unwrap();
+        assert!(GitInfo::is_submodule(&sub));
+    }
+
+    #[test]
+    pub fn is_submodule_for_ordinary_repo_and_worktree() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join(".git")).unwrap();
+        assert!(!GitInfo::is_submodule(root.path()));
+
+        let worktree = tempfile::tempdir().unwrap();
+        fs::write(worktree.path().join(".git"), "gitdir: /src/main/.git/worktrees/feature\n").unwrap();
+        assert!(!GitInfo::is_submodule(worktree.path()));
+    }
+
+    #[test]
+    pub fn is_submodule_for_path_in_gitmodules() {
+        let root = tempfile::tempdir().unwrap();
+        let sub = root.path().join("libs").join("common");
+        fs::create_dir_all(&sub).unwrap();
+        fs::write(root.path().join(".gitmodules"), "[submodule \"common\"]\n\tpath = libs/common\n\turl = https://example.com/common.git\n").unwrap();
+        assert!(GitInfo::is_submodule(&sub));
+
+        let other = root.path().join("libs").join("other");
+        fs::create_dir_all(&other).unwrap();
+        assert!(!GitInfo::is_submodule(&other));
+    }
+}
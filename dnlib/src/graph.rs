@@ -1,7 +1,10 @@
-use crate::analysis::{Analysis, SolutionDirectory, Solution, Project};
+use crate::analysis::{Analysis, SolutionDirectory, Solution, Project, Package};
+use crate::configuration::Configuration;
+use crate::enums::{LabelMode, Palette};
 use crate::io::PathExtensions;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt;
+use std::path::Path;
 use bitflags::bitflags;
 
 use petgraph::prelude::*;
@@ -16,12 +19,17 @@ pub enum Node<'a> {
     SolutionDirectory(&'a SolutionDirectory),
     Solution(&'a Solution),
     Project(&'a Project),
+    Package(&'a Package),
 }
 
 /// This library generates directed graphs of `Node` with indexes that are stable
 /// across removals and unweighted edges.
 pub type DnGraph<'a> = StableGraph<Node<'a>, (), Directed, u32>;
 
+/// A graph of the components of a solution (see `Project::component`). The edge
+/// weights are the number of project references between the two components.
+pub type ComponentGraph = StableGraph<String, usize, Directed, u32>;
+
 bitflags! {
     pub struct GraphFlags: u32 {
         const ANALYSIS_ROOT = 0b00000001;
@@ -42,25 +50,54 @@ impl<'a> fmt::Debug for Node<'a> {
             Node::SolutionDirectory(ref sd) => write!(f, "{}", sd.directory.display()),
             Node::Solution(ref sln) => write!(f, "{}", sln.file_info.path.display()),
             Node::Project(ref proj) => write!(f, "{:?}", proj),
+            Node::Package(pkg) => write!(f, "{:?}", pkg),
         }
     }
 }
 
 impl<'a> fmt::Display for Node<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            Node::Analysis(ref an) => write!(f, "{} (root dir)", an.root_path.display()),
-            Node::SolutionDirectory(ref sd) => write!(f, "{} (sln dir)", sd.directory.file_stem_as_str()),
-            Node::Solution(ref sln) => write!(f, "{}", sln.file_info.path.file_stem_as_str()),
-            Node::Project(ref proj) => write!(f, "{}", proj.file_info.path.file_stem_as_str()),
-        }
+        write!(f, "{}", self.label(LabelMode::Stem, Path::new("")))
     }
 }
 
 impl<'a> Node<'a> {
-    pub fn dot_attributes(&self) -> &'static str {
+    /// The label of the node. Solution directories, solutions and projects are
+    /// labelled according to `label_mode`; relative paths are relative to `root`,
+    /// and paths outside `root` are shown in full.
+    pub fn label(&self, label_mode: LabelMode, root: &Path) -> String {
+        let path_label = |path: &Path| match label_mode {
+            LabelMode::Stem => path.file_stem_as_str().to_owned(),
+            LabelMode::Relative => path.strip_prefix(root).unwrap_or(path).display().to_string(),
+            LabelMode::Full => path.display().to_string(),
+        };
+
+        match *self {
+            Node::Analysis(an) => format!("{} (root dir)", an.root_path.display()),
+            Node::SolutionDirectory(sd) => format!("{} (sln dir)", path_label(&sd.directory)),
+            Node::Solution(sln) => path_label(&sln.file_info.path),
+            Node::Project(proj) => path_label(&proj.file_info.path),
+            Node::Package(pkg) => format!("{} {}", pkg.name, pkg.version),
+        }
+    }
+
+    pub fn dot_attributes(&self, palette: Palette) -> &'static str {
         use crate::enums::ProjectOwnership;
 
+        if palette == Palette::Cud {
+            // The shapes are the same as the default palette, so they remain a
+            // secondary cue.
+            return match *self {
+                Node::Analysis(_) => "shape=invhouse,style=filled,fillcolor=\"#F0E442\",penwidth=3",
+                Node::SolutionDirectory(_) => "shape=octagon,style=filled,fillcolor=\"#009E73\",penwidth=3",
+                Node::Solution(_) => "shape=ellipse,style=filled,fillcolor=\"#BBBBBB\",penwidth=3",
+                Node::Project(p) if p.ownership == ProjectOwnership::Orphaned => "shape=rectangle,style=\"filled,rounded\",fillcolor=\"#D55E00\"",
+                Node::Project(p) if p.ownership == ProjectOwnership::TransitivelyRequired => "shape=rectangle,style=\"filled,rounded\",fillcolor=\"#56B4E9\"",
+                Node::Project(_) => "shape=rectangle,style=rounded",
+                Node::Package(_) => "shape=note,style=filled,fillcolor=\"#E69F00\"",
+            };
+        }
+
         // [color=blue,,fontcolor=red]"
 
         // We are using X11 colors.
@@ -72,21 +109,120 @@ impl<'a> Node<'a> {
             Node::SolutionDirectory(_) => "shape=octagon,style=filled,fillcolor=turquoise,penwidth=3",
             Node::Solution(_) => "shape=ellipse,style=filled,fillcolor=grey,penwidth=3",
             Node::Project(ref p) if p.ownership == ProjectOwnership::Orphaned => "shape=rectangle,style=\"filled,rounded\",fillcolor=firebrick1",
+            Node::Project(p) if p.ownership == ProjectOwnership::TransitivelyRequired => "shape=rectangle,style=\"filled,rounded\",fillcolor=orange",
             Node::Project(_) => "shape=rectangle,style=rounded",
+            Node::Package(_) => "shape=note,style=filled,fillcolor=lightyellow",
         }
     }
+
+    /// The DOT attributes used when drawing the package-class view of the graph.
+    /// Project nodes are filled with a color for their dominant package class and
+    /// drawn with a thicker border the more packages they use. Other nodes are
+    /// drawn as normal.
+    pub fn package_class_dot_attributes(&self, configuration: &Configuration) -> String {
+        const CLASS_COLORS: [&str; 6] = ["lightblue", "palegreen", "khaki", "plum", "lightsalmon", "lightgrey"];
+
+        match *self {
+            Node::Project(p) => {
+                let fill_color = dominant_package_class(p)
+                    .and_then(|class| package_class_index(configuration, class))
+                    .map_or("white", |idx| CLASS_COLORS[idx % CLASS_COLORS.len()]);
+                let pen_width = 1 + (package_weight(p) / 5).min(7);
+
+                format!("shape=rectangle,style=\"filled,rounded\",fillcolor={},penwidth={}", fill_color, pen_width)
+            },
+            _ => self.dot_attributes(configuration.palette).to_owned(),
+        }
+    }
+
+    /// Returns true if the node is a project that directly references the named
+    /// package. Package names are compared ignoring case, as NuGet does.
+    pub fn references_package(&self, package_name: &str) -> bool {
+        match *self {
+            Node::Project(proj) => proj.packages.iter().any(|pkg| pkg.name.eq_ignore_ascii_case(package_name)),
+            _ => false,
+        }
+    }
+
+    /// The DGML category of the node. The categories are styled to match the
+    /// colors used by `dot_attributes`.
+    pub fn dgml_category(&self) -> &'static str {
+        use crate::enums::ProjectOwnership;
+
+        match *self {
+            Node::Analysis(_) => "Analysis",
+            Node::SolutionDirectory(_) => "SolutionDirectory",
+            Node::Solution(_) => "Solution",
+            Node::Project(p) if p.ownership == ProjectOwnership::Orphaned => "OrphanedProject",
+            Node::Project(p) if p.ownership == ProjectOwnership::TransitivelyRequired => "TransitivelyRequiredProject",
+            Node::Project(_) => "Project",
+            Node::Package(_) => "Package",
+        }
+    }
+}
+
+/// The number of (non-development) packages that a project uses. This is a rough
+/// measure of how heavily the project depends on external code.
+pub fn package_weight(project: &Project) -> usize {
+    project.packages.iter().filter(|pkg| !pkg.development).count()
+}
+
+/// The class that most of a project's (non-development) packages belong to. Ties
+/// are broken alphabetically. Returns None if the project uses no packages.
+pub fn dominant_package_class(project: &Project) -> Option<&str> {
+    let mut counts = BTreeMap::<&str, usize>::new();
+    for pkg in project.packages.iter().filter(|pkg| !pkg.development) {
+        *counts.entry(&pkg.class).or_default() += 1;
+    }
+
+    let mut dominant: Option<(&str, usize)> = None;
+    for (class, count) in counts {
+        match dominant {
+            Some((_, best)) if best >= count => {},
+            _ => dominant = Some((class, count)),
+        }
+    }
+
+    dominant.map(|(class, _)| class)
+}
+
+/// Returns true if `parent` is a project with few packages that depends on a
+/// `child` that uses many more of them. These are the edges the package-class
+/// view emphasizes.
+pub fn is_heavy_dependency(parent: &Project, child: &Project) -> bool {
+    const HEAVY_PACKAGE_WEIGHT: usize = 5;
+
+    let child_weight = package_weight(child);
+    child_weight >= HEAVY_PACKAGE_WEIGHT && child_weight > 2 * package_weight(parent)
+}
+
+/// The position of the class in the configured package groups, ignoring duplicate names.
+fn package_class_index(configuration: &Configuration, class: &str) -> Option<usize> {
+    let mut names = Vec::<&str>::new();
+    for group in &configuration.package_groups {
+        if !names.contains(&group.name.as_str()) {
+            names.push(&group.name);
+        }
+    }
+
+    names.iter().position(|name| *name == class)
 }
 
 /// Construct a graph of the entire analysis results.
 /// There are no relationships between the solutions in this graph.
 /// It can be used to find redundant project references.
-pub fn make_project_graph(
-    analysis: &Analysis,
-    graph_flags: GraphFlags
+/// If `GraphFlags::PACKAGES` is set then each package is added as a node, with an edge
+/// from every project that uses it, except for packages in the `hidden_package_classes`.
+pub fn make_project_graph<'a>(
+    analysis: &'a Analysis,
+    graph_flags: GraphFlags,
+    hidden_package_classes: &[String],
     )
--> DnGraph
+-> DnGraph<'a>
 {
     let mut graph = DnGraph::default();
+    // Packages are shared by all the projects that use the same version of them.
+    let mut pkg_node_mapping = HashMap::new();
 
     let analysis_node_idx = if graph_flags.contains(GraphFlags::ANALYSIS_ROOT) {
         Some(graph.add_node(Node::Analysis(analysis)))
@@ -134,15 +270,44 @@ pub fn make_project_graph(
                     }
                 }
             }
+
+            if graph_flags.contains(GraphFlags::PACKAGES) {
+                for proj in &sln.projects {
+                    let visible_packages = proj.packages.iter()
+                        .filter(|pkg| !hidden_package_classes.iter().any(|class| class.eq_ignore_ascii_case(&pkg.class)));
+
+                    for pkg in visible_packages {
+                        let pkg_node_idx = *pkg_node_mapping.entry((pkg.name.as_str(), pkg.version.as_str()))
+                            .or_insert_with(|| graph.add_node(Node::Package(pkg)));
+                        graph.add_edge(proj_node_mapping[proj], pkg_node_idx, ());
+                    }
+                }
+            }
         }
     }
 
     graph
 }
 
+/// Construct a graph of just the projects that directly reference the named package,
+/// and the project references between them. This shows the impact of upgrading it.
+pub fn make_package_consumers_graph<'a>(analysis: &'a Analysis, package_name: &str) -> DnGraph<'a> {
+    let mut graph = make_project_graph(analysis, GraphFlags::PROJECTS, &[]);
+    retain_package_consumers(&mut graph, package_name);
+    graph
+}
+
+/// Removes every node from the graph except the projects that directly reference
+/// the named package. Edges to and from the removed nodes go with them.
+pub fn retain_package_consumers(graph: &mut DnGraph, package_name: &str) {
+    graph.retain_nodes(|g, node_idx| g[node_idx].references_package(package_name));
+}
+
 /// Construct a set of graphs, one graph for each solution in the analysis results.
-pub fn make_project_graphs(analysis: &Analysis) -> HashMap<&Solution, DnGraph> {
-    let mut results = HashMap::default();
+/// The graphs are keyed by solution in sorted order, so that anything written
+/// from them comes out in the same order on every run.
+pub fn make_project_graphs(analysis: &Analysis) -> BTreeMap<&Solution, DnGraph> {
+    let mut results = BTreeMap::default();
 
     for sd in &analysis.solution_directories {
         for sln in &sd.solutions {
@@ -181,6 +346,36 @@ pub fn make_project_graphs(analysis: &Analysis) -> HashMap<&Solution, DnGraph> {
     results
 }
 
+/// Construct a graph of the components of a solution. References between projects
+/// in the same component are not included.
+pub fn make_component_graph(sln: &Solution) -> ComponentGraph {
+    let mut graph = ComponentGraph::default();
+
+    let components = sln.projects.iter()
+        .map(|proj| proj.component(sln))
+        .collect::<BTreeSet<_>>();
+    let component_node_mapping = components.into_iter()
+        .map(|component| (component.clone(), graph.add_node(component)))
+        .collect::<HashMap<_, _>>();
+
+    for proj in &sln.projects {
+        let source_idx = component_node_mapping[&proj.component(sln)];
+        for child in proj.get_child_projects(sln) {
+            let target_idx = component_node_mapping[&child.component(sln)];
+            if source_idx == target_idx {
+                continue;
+            }
+
+            match graph.find_edge(source_idx, target_idx) {
+                Some(edge_idx) => graph[edge_idx] += 1,
+                None => { graph.add_edge(source_idx, target_idx, 1); },
+            }
+        }
+    }
+
+    graph
+}
+
 // fn add_proj<'a>(graph: &'a mut DnGraph<'a>, sln: &'a Solution, sln_node_idx: NodeIndex<u32>)
 // {
 //     // Get all projects and add them to the graph as nodes.
@@ -376,6 +571,291 @@ mod tests {
     }
 
 
+    mod palette_tests {
+        use super::*;
+        use crate::enums::ProjectOwnership;
+
+        fn make_project(ownership: ProjectOwnership) -> Project {
+            let mut project = Project::default();
+            project.ownership = ownership;
+            project
+        }
+
+        fn fill_colors(palette: Palette) -> Vec<String> {
+            let analysis = Analysis::default();
+            let sd = SolutionDirectory::default();
+            let sln = Solution::default();
+            let linked = make_project(ProjectOwnership::Linked);
+            let orphaned = make_project(ProjectOwnership::Orphaned);
+            let required = make_project(ProjectOwnership::TransitivelyRequired);
+
+            let nodes = [
+                Node::Analysis(&analysis),
+                Node::SolutionDirectory(&sd),
+                Node::Solution(&sln),
+                Node::Project(&linked),
+                Node::Project(&orphaned),
+                Node::Project(&required),
+            ];
+
+            nodes.iter()
+                .map(|node| {
+                    let attrs = node.dot_attributes(palette);
+                    attrs.split(',')
+                        .find(|attr| attr.starts_with("fillcolor="))
+                        .map_or("", |attr| &attr["fillcolor=".len()..])
+                        .to_owned()
+                })
+                .collect()
+        }
+
+        #[test]
+        pub fn default_palette_uses_x11_colors() {
+            assert_eq!(fill_colors(Palette::Default), vec!["gold", "turquoise", "grey", "", "firebrick1", "orange"]);
+        }
+
+        #[test]
+        pub fn cud_palette_uses_color_blind_safe_colors() {
+            assert_eq!(fill_colors(Palette::Cud), vec![
+                "\"#F0E442\"", "\"#009E73\"", "\"#BBBBBB\"", "", "\"#D55E00\"", "\"#56B4E9\"",
+            ]);
+        }
+
+        #[test]
+        pub fn palettes_use_the_same_shapes() {
+            let sln = Solution::default();
+            let orphaned = make_project(ProjectOwnership::Orphaned);
+            for node in &[Node::Solution(&sln), Node::Project(&orphaned)] {
+                let shape = |palette| node.dot_attributes(palette).split(',').next().unwrap();
+                assert_eq!(shape(Palette::Default), shape(Palette::Cud));
+            }
+        }
+    }
+
+    mod label_tests {
+        use super::*;
+        use std::path::PathBuf;
+
+        fn make_project(path: &str) -> Project {
+            let mut project = Project::default();
+            project.file_info.path = PathBuf::from(path);
+            project
+        }
+
+        fn label(node: Node, label_mode: LabelMode) -> String {
+            node.label(label_mode, Path::new("/src/repo"))
+        }
+
+        #[test]
+        pub fn stem_mode_uses_the_file_stem() {
+            let project = make_project("/src/repo/car/engine/Engine.csproj");
+            assert_eq!(label(Node::Project(&project), LabelMode::Stem), "Engine");
+            assert_eq!(Node::Project(&project).to_string(), "Engine");
+        }
+
+        #[test]
+        pub fn relative_mode_uses_the_path_relative_to_the_root() {
+            let project = make_project("/src/repo/car/engine/Engine.csproj");
+            assert_eq!(label(Node::Project(&project), LabelMode::Relative), "car/engine/Engine.csproj");
+
+            let sd = SolutionDirectory { directory: PathBuf::from("/src/repo/car"), ..Default::default() };
+            assert_eq!(label(Node::SolutionDirectory(&sd), LabelMode::Relative), "car (sln dir)");
+        }
+
+        #[test]
+        pub fn relative_mode_uses_the_full_path_outside_the_root() {
+            let project = make_project("/elsewhere/Engine.csproj");
+            assert_eq!(label(Node::Project(&project), LabelMode::Relative), "/elsewhere/Engine.csproj");
+        }
+
+        #[test]
+        pub fn full_mode_uses_the_full_path() {
+            let project = make_project("/src/repo/car/engine/Engine.csproj");
+            assert_eq!(label(Node::Project(&project), LabelMode::Full), "/src/repo/car/engine/Engine.csproj");
+        }
+
+        #[test]
+        pub fn label_mode_does_not_affect_packages() {
+            let pkg = Package::new("Serilog", "2.10.0", false, "Third Party");
+            assert_eq!(label(Node::Package(&pkg), LabelMode::Full), "Serilog 2.10.0");
+        }
+    }
+
+    mod package_class_tests {
+        use super::*;
+        use crate::analysis::Package;
+
+        fn make_project(packages: &[(&str, &str)]) -> Project {
+            let mut project = Project::default();
+            project.packages = packages.iter()
+                .map(|(name, class)| Package::new(*name, "1.0.0", false, *class))
+                .collect();
+            project
+        }
+
+        #[test]
+        pub fn package_weight_ignores_development_packages() {
+            let mut project = make_project(&[("A", "Microsoft"), ("B", "Third Party")]);
+            project.packages.push(Package::new("C", "1.0.0", true, "Third Party"));
+            assert_eq!(package_weight(&project), 2);
+        }
+
+        #[test]
+        pub fn dominant_package_class_works() {
+            assert_eq!(dominant_package_class(&make_project(&[])), None);
+
+            let project = make_project(&[("A", "Third Party"), ("B", "Microsoft"), ("C", "Third Party")]);
+            assert_eq!(dominant_package_class(&project), Some("Third Party"));
+
+            // Ties are broken alphabetically.
+            let project = make_project(&[("A", "Third Party"), ("B", "Microsoft")]);
+            assert_eq!(dominant_package_class(&project), Some("Microsoft"));
+        }
+
+        #[test]
+        pub fn package_class_dot_attributes_reflect_weights() {
+            let configuration = Configuration::default();
+
+            let light = make_project(&[("A", "Microsoft")]);
+            let heavy = make_project(&[
+                ("A", "Third Party"), ("B", "Third Party"), ("C", "Third Party"), ("D", "Third Party"),
+                ("E", "Third Party"), ("F", "Third Party"), ("G", "Third Party"), ("H", "Third Party"),
+                ("I", "Third Party"), ("J", "Third Party"), ("K", "Microsoft"),
+            ]);
+            let none = make_project(&[]);
+
+            // The default configuration has "Third Party" first, then "Microsoft".
+            assert_eq!(Node::Project(&light).package_class_dot_attributes(&configuration),
+                "shape=rectangle,style=\"filled,rounded\",fillcolor=palegreen,penwidth=1");
+            assert_eq!(Node::Project(&heavy).package_class_dot_attributes(&configuration),
+                "shape=rectangle,style=\"filled,rounded\",fillcolor=lightblue,penwidth=3");
+            assert_eq!(Node::Project(&none).package_class_dot_attributes(&configuration),
+                "shape=rectangle,style=\"filled,rounded\",fillcolor=white,penwidth=1");
+
+            assert!(is_heavy_dependency(&light, &heavy));
+            assert!(!is_heavy_dependency(&heavy, &light));
+            assert!(!is_heavy_dependency(&none, &light));
+        }
+    }
+
+    mod package_graph_tests {
+        use super::*;
+        use crate::enums::ProjectOwnership;
+
+        fn make_analysis() -> Analysis {
+            let make_project = |path: &str, packages: &[(&str, &str)]| {
+                let mut project = Project::default();
+                project.file_info.path = path.into();
+                project.ownership = ProjectOwnership::Linked;
+                project.packages = packages.iter()
+                    .map(|(name, class)| Package::new(*name, "1.0.0", false, *class))
+                    .collect();
+                project
+            };
+
+            let mut sln = Solution::default();
+            sln.projects = vec![
+                make_project("/temp/app.csproj", &[("Acme.Core", "Ours"), ("Serilog", "Third Party"), ("System.Memory", "Microsoft")]),
+                make_project("/temp/web.csproj", &[("Serilog", "Third Party"), ("Microsoft.AspNetCore", "Microsoft")]),
+            ];
+
+            Analysis {
+                solution_directories: vec![SolutionDirectory { solutions: vec![sln], ..Default::default() }],
+                ..Default::default()
+            }
+        }
+
+        fn package_names(graph: &DnGraph) -> Vec<String> {
+            let mut names = graph.node_indices()
+                .filter_map(|idx| match graph[idx] {
+                    Node::Package(pkg) => Some(pkg.name.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        }
+
+        #[test]
+        pub fn packages_are_only_added_when_requested() {
+            let analysis = make_analysis();
+            let graph = make_project_graph(&analysis, GraphFlags::PROJECTS, &[]);
+            assert!(package_names(&graph).is_empty());
+
+            let graph = make_project_graph(&analysis, GraphFlags::PROJECTS | GraphFlags::PACKAGES, &[]);
+            assert_eq!(package_names(&graph), vec!["Acme.Core", "Microsoft.AspNetCore", "Serilog", "System.Memory"]);
+            // Serilog is shared by both projects.
+            assert_eq!(graph.node_count(), 3 + 4);
+            assert_eq!(graph.edge_count(), 2 + 5);
+        }
+
+        #[test]
+        pub fn hidden_package_classes_are_omitted() {
+            let analysis = make_analysis();
+            let hidden = vec!["microsoft".to_owned()];
+            let graph = make_project_graph(&analysis, GraphFlags::PROJECTS | GraphFlags::PACKAGES, &hidden);
+            assert_eq!(package_names(&graph), vec!["Acme.Core", "Serilog"]);
+            assert_eq!(graph.node_count(), 3 + 2);
+            assert_eq!(graph.edge_count(), 2 + 3);
+        }
+
+        fn project_paths(graph: &DnGraph) -> Vec<String> {
+            let mut paths = graph.node_indices()
+                .map(|idx| match graph[idx] {
+                    Node::Project(proj) => proj.file_info.path_as_str().to_owned(),
+                    ref node => panic!("unexpected node {:?}", node),
+                })
+                .collect::<Vec<_>>();
+            paths.sort();
+            paths
+        }
+
+        #[test]
+        pub fn package_consumers_graph_only_has_consumers() {
+            let analysis = make_analysis();
+            let graph = make_package_consumers_graph(&analysis, "acme.core");
+            assert_eq!(project_paths(&graph), vec!["/temp/app.csproj"]);
+
+            let graph = make_package_consumers_graph(&analysis, "Serilog");
+            assert_eq!(project_paths(&graph), vec!["/temp/app.csproj", "/temp/web.csproj"]);
+            // The edges from the solution went with it.
+            assert_eq!(graph.edge_count(), 0);
+
+            let graph = make_package_consumers_graph(&analysis, "Unity");
+            assert_eq!(graph.node_count(), 0);
+        }
+
+        #[test]
+        pub fn retain_package_consumers_keeps_edges_between_consumers() {
+            let make_project = |path: &str, packages: &[&str]| {
+                let mut project = Project::default();
+                project.file_info.path = path.into();
+                project.packages = packages.iter().map(|name| Package::new(*name, "1.0.0", false, "")).collect();
+                project
+            };
+
+            let sln = Solution::default();
+            let app = make_project("/temp/app.csproj", &["Serilog"]);
+            let lib = make_project("/temp/lib.csproj", &["Serilog", "Unity"]);
+            let core = make_project("/temp/core.csproj", &["Unity"]);
+
+            let mut graph = DnGraph::default();
+            let sln_idx = graph.add_node(Node::Solution(&sln));
+            let app_idx = graph.add_node(Node::Project(&app));
+            let lib_idx = graph.add_node(Node::Project(&lib));
+            let core_idx = graph.add_node(Node::Project(&core));
+            graph.add_edge(sln_idx, app_idx, ());
+            graph.add_edge(app_idx, lib_idx, ());
+            graph.add_edge(app_idx, core_idx, ());
+            graph.add_edge(lib_idx, core_idx, ());
+
+            retain_package_consumers(&mut graph, "Serilog");
+            assert_eq!(project_paths(&graph), vec!["/temp/app.csproj", "/temp/lib.csproj"]);
+            assert_eq!(graph.edge_count(), 1);
+            assert!(graph.contains_edge(app_idx, lib_idx));
+        }
+    }
+
     mod tred_tests {
         use super::*;
 
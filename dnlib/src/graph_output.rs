@@ -1,11 +1,14 @@
 use crate::configuration::Configuration;
+use crate::enums::RedundantEdgeStyle;
 use crate::errors::DnLibResult;
-use crate::graph::DnGraph;
+use crate::graph::{is_heavy_dependency, ComponentGraph, DnGraph, Node};
 use std::collections::HashSet;
 use std::fs::{self, File};
 use std::io::{BufWriter, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use log::info;
 use petgraph::prelude::*;
 use petgraph::visit::{IntoEdgeReferences, IntoNodeReferences};
@@ -16,17 +19,35 @@ pub fn write_project_dot_file<P: AsRef<Path>>(
     graph: &DnGraph,
     removed_edges: &HashSet<(NodeIndex, NodeIndex)>,
 ) -> DnLibResult<()>
+{
+    let (path, mut writer) = create_output_file(configuration, filename, "dot")?;
+    write_project_dot(&mut writer, configuration, graph, removed_edges)?;
+    info!("Wrote {:?}", path);
+    Ok(())
+}
+
+/// Creates a file in the output directory with the given extension, compressing
+/// it if the configuration says so. The file is finished when the writer is dropped.
+fn create_output_file<P: AsRef<Path>>(
+    configuration: &Configuration,
+    filename: P,
+    extension: &str,
+) -> DnLibResult<(PathBuf, Box<dyn Write>)>
 {
     let mut path = configuration.output_directory.clone();
     fs::create_dir_all(&path)?;
     path.push(filename);
-    path.set_extension("dot");
+    path.set_extension(extension);
 
-    let file = File::create(&path)?;
-    let mut writer = BufWriter::new(file);
-    write_project_dot(&mut writer, configuration, graph, removed_edges)?;
-    info!("Wrote {:?}", path);
-    Ok(())
+    if configuration.gzip_output {
+        path.set_extension(format!("{}.gz", extension));
+        let file = File::create(&path)?;
+        let writer = GzEncoder::new(BufWriter::new(file), Compression::default());
+        Ok((path, Box::new(writer)))
+    } else {
+        let file = File::create(&path)?;
+        Ok((path, Box::new(BufWriter::new(file))))
+    }
 }
 
 fn write_project_dot<W>(
@@ -39,14 +60,17 @@ where
     W: Write,
 {
     writeln!(writer, "digraph {{")?;
+    if let Some(ref title) = configuration.graph_title {
+        writeln!(writer, "    label=\"{}\"", dot_escape(title))?;
+    }
 
     for (node_idx, node_ref) in graph.node_references() {
         writeln!(
             writer,
             "    {} [label=\"{}\",{}]",
             node_idx.index(),
-            apply_abbreviations(node_ref.to_string(), configuration),
-            node_ref.dot_attributes()
+            dot_escape(&node_label(node_ref, configuration)),
+            node_ref.dot_attributes(configuration.palette)
         )?;
     }
 
@@ -56,20 +80,280 @@ where
         writeln!(writer, "    {} -> {}", source_node_idx, target_node_idx)?;
     }
 
-    for edge in removed_edges {
+    let redundant_edge_attributes = match configuration.redundant_edge_style {
+        RedundantEdgeStyle::Hidden => None,
+        RedundantEdgeStyle::Dashed => Some("style=dashed"),
+        RedundantEdgeStyle::Red => Some("color=red,style=dotted,penwidth=2"),
+    };
+
+    if let Some(attributes) = redundant_edge_attributes {
+        for edge in sorted_edges(removed_edges) {
+            writeln!(
+                writer,
+                "    {} -> {} [{}]",
+                edge.0,
+                edge.1,
+                attributes
+            )?;
+        }
+    }
+
+    writeln!(writer, "}}")?;
+
+    Ok(())
+}
+
+pub fn write_adjacency_list<P: AsRef<Path>>(
+    configuration: &Configuration,
+    filename: P,
+    graph: &DnGraph,
+) -> DnLibResult<()>
+{
+    let (path, mut writer) = create_output_file(configuration, filename, "txt")?;
+    write_adjacency(&mut writer, graph)?;
+    info!("Wrote {:?}", path);
+    Ok(())
+}
+
+/// Writes one "node -> child1,child2" line per node in the graph, using the nodes'
+/// display names. Nodes with no children are written with nothing after the arrow.
+/// The lines, and the children within each line, are sorted so that the output is
+/// the same on every run.
+fn write_adjacency<W>(writer: &mut W, graph: &DnGraph) -> DnLibResult<()>
+where
+    W: Write,
+{
+    let mut lines = graph.node_indices()
+        .map(|node_idx| {
+            let mut children = graph.neighbors_directed(node_idx, Direction::Outgoing)
+                .map(|child_idx| graph[child_idx].to_string())
+                .collect::<Vec<_>>();
+            children.sort();
+            format!("{} -> {}", graph[node_idx], children.join(","))
+        })
+        .collect::<Vec<_>>();
+    lines.sort();
+
+    for line in lines {
+        writeln!(writer, "{}", line)?;
+    }
+
+    Ok(())
+}
+
+pub fn write_component_dot_file<P: AsRef<Path>>(
+    configuration: &Configuration,
+    filename: P,
+    graph: &ComponentGraph,
+) -> DnLibResult<()>
+{
+    let (path, mut writer) = create_output_file(configuration, filename, "dot")?;
+    write_component_dot(&mut writer, configuration, graph)?;
+    info!("Wrote {:?}", path);
+    Ok(())
+}
+
+/// Writes the component graph in DOT format. Each edge is labelled with the
+/// number of project references it represents.
+fn write_component_dot<W>(
+    writer: &mut W,
+    configuration: &Configuration,
+    graph: &ComponentGraph,
+) -> DnLibResult<()>
+where
+    W: Write,
+{
+    writeln!(writer, "digraph {{")?;
+
+    for (node_idx, component) in graph.node_references() {
+        writeln!(
+            writer,
+            "    {} [label=\"{}\",shape=folder]",
+            node_idx.index(),
+            dot_escape(&apply_abbreviations(component.clone(), configuration))
+        )?;
+    }
+
+    for edge in graph.edge_references() {
+        writeln!(
+            writer,
+            "    {} -> {} [label=\"{}\"]",
+            edge.source().index(),
+            edge.target().index(),
+            edge.weight()
+        )?;
+    }
+
+    writeln!(writer, "}}")?;
+
+    Ok(())
+}
+
+pub fn write_package_class_dot_file<P: AsRef<Path>>(
+    configuration: &Configuration,
+    filename: P,
+    graph: &DnGraph,
+) -> DnLibResult<()>
+{
+    let (path, mut writer) = create_output_file(configuration, filename, "dot")?;
+    write_package_class_dot(&mut writer, configuration, graph)?;
+    info!("Wrote {:?}", path);
+    Ok(())
+}
+
+/// Writes the graph in DOT format with project nodes colored by the dominant class
+/// of their packages. Edges where a light project depends on a package-heavy one
+/// are emphasized.
+fn write_package_class_dot<W>(
+    writer: &mut W,
+    configuration: &Configuration,
+    graph: &DnGraph,
+) -> DnLibResult<()>
+where
+    W: Write,
+{
+    writeln!(writer, "digraph {{")?;
+    if let Some(ref title) = configuration.graph_title {
+        writeln!(writer, "    label=\"{}\"", dot_escape(title))?;
+    }
+
+    for (node_idx, node_ref) in graph.node_references() {
         writeln!(
             writer,
-            "    {} -> {} [color=red,style=dotted,penwidth=2]",
-            edge.0.index(),
-            edge.1.index()
+            "    {} [label=\"{}\",{}]",
+            node_idx.index(),
+            dot_escape(&node_label(node_ref, configuration)),
+            node_ref.package_class_dot_attributes(configuration)
         )?;
     }
 
+    for edge in graph.edge_references() {
+        let attributes = match (graph[edge.source()], graph[edge.target()]) {
+            (Node::Project(parent), Node::Project(child)) if is_heavy_dependency(parent, child) =>
+                " [color=red,penwidth=3]",
+            _ => "",
+        };
+
+        writeln!(writer, "    {} -> {}{}", edge.source().index(), edge.target().index(), attributes)?;
+    }
+
     writeln!(writer, "}}")?;
 
     Ok(())
 }
 
+pub fn write_project_dgml_file<P: AsRef<Path>>(
+    configuration: &Configuration,
+    filename: P,
+    graph: &DnGraph,
+    removed_edges: &HashSet<(NodeIndex, NodeIndex)>,
+) -> DnLibResult<()>
+{
+    let (path, mut writer) = create_output_file(configuration, filename, "dgml")?;
+    write_project_dgml(&mut writer, configuration, graph, removed_edges)?;
+    info!("Wrote {:?}", path);
+    Ok(())
+}
+
+/// Writes the graph in DGML format, which Visual Studio can open natively.
+/// Redundant edges are written as links with the `IsRedundant` property set.
+fn write_project_dgml<W>(
+    writer: &mut W,
+    configuration: &Configuration,
+    graph: &DnGraph,
+    removed_edges: &HashSet<(NodeIndex, NodeIndex)>,
+) -> DnLibResult<()>
+where
+    W: Write,
+{
+    writeln!(writer, r#"<?xml version="1.0" encoding="utf-8"?>"#)?;
+    writeln!(writer, r#"<DirectedGraph xmlns="http://schemas.microsoft.com/vs/2009/dgml">"#)?;
+
+    writeln!(writer, "  <Nodes>")?;
+    for (node_idx, node_ref) in graph.node_references() {
+        writeln!(
+            writer,
+            r#"    <Node Id="{}" Label="{}" Category="{}" />"#,
+            node_idx.index(),
+            xml_escape(&node_label(node_ref, configuration)),
+            node_ref.dgml_category()
+        )?;
+    }
+    writeln!(writer, "  </Nodes>")?;
+
+    writeln!(writer, "  <Links>")?;
+    for edge in graph.edge_references() {
+        writeln!(
+            writer,
+            r#"    <Link Source="{}" Target="{}" IsRedundant="False" />"#,
+            edge.source().index(),
+            edge.target().index()
+        )?;
+    }
+
+    for edge in sorted_edges(removed_edges) {
+        writeln!(
+            writer,
+            r#"    <Link Source="{}" Target="{}" Category="Redundant" IsRedundant="True" />"#,
+            edge.0,
+            edge.1
+        )?;
+    }
+    writeln!(writer, "  </Links>")?;
+
+    writeln!(writer, "  <Categories>")?;
+    writeln!(writer, r#"    <Category Id="Analysis" Background="Gold" />"#)?;
+    writeln!(writer, r#"    <Category Id="SolutionDirectory" Background="Turquoise" />"#)?;
+    writeln!(writer, r#"    <Category Id="Solution" Background="Gray" />"#)?;
+    writeln!(writer, r#"    <Category Id="Project" Background="White" />"#)?;
+    writeln!(writer, r#"    <Category Id="OrphanedProject" Background="Firebrick" />"#)?;
+    writeln!(writer, r#"    <Category Id="TransitivelyRequiredProject" Background="Orange" />"#)?;
+    writeln!(writer, r#"    <Category Id="Package" Background="LightYellow" />"#)?;
+    writeln!(writer, r#"    <Category Id="Redundant" Stroke="Red" StrokeDashArray="2,2" />"#)?;
+    writeln!(writer, "  </Categories>")?;
+
+    writeln!(writer, "  <Properties>")?;
+    writeln!(writer, r#"    <Property Id="IsRedundant" DataType="System.Boolean" />"#)?;
+    writeln!(writer, "  </Properties>")?;
+
+    writeln!(writer, "</DirectedGraph>")?;
+
+    Ok(())
+}
+
+/// The removed edges as (source, target) node indexes, in sorted order. They are held
+/// in a `HashSet`, so iterating it directly would vary from run to run.
+fn sorted_edges(removed_edges: &HashSet<(NodeIndex, NodeIndex)>) -> Vec<(usize, usize)> {
+    let mut edges = removed_edges.iter()
+        .map(|(source, target)| (source.index(), target.index()))
+        .collect::<Vec<_>>();
+    edges.sort();
+    edges
+}
+
+/// The label to show for a node. This is its label in the configured `label_mode`,
+/// except that the root node shows the `graph_title` if there is one.
+fn node_label(node: &Node, configuration: &Configuration) -> String {
+    match (node, &configuration.graph_title) {
+        (Node::Analysis(_), Some(title)) => title.clone(),
+        _ => apply_abbreviations(node.label(configuration.label_mode, configuration.scan_directory()), configuration),
+    }
+}
+
+/// Escapes a string for use inside a quoted DOT label, where a backslash starts an
+/// escape sequence, e.g. in a Windows path.
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 fn apply_abbreviations(mut s: String, configuration: &Configuration) -> String {
     if !configuration.abbreviate_on_graphs {
         return s;
@@ -82,4 +366,152 @@ fn apply_abbreviations(mut s: String, configuration: &Configuration) -> String {
     }
 
     s
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{FileInfo, Project, Solution};
+    use crate::enums::ProjectOwnership;
+    use crate::graph::Node;
+    use std::path::PathBuf;
+
+    fn make_project(path: &str, ownership: ProjectOwnership) -> Project {
+        let mut project = Project::default();
+        project.file_info = FileInfo { path: PathBuf::from(path), ..Default::default() };
+        project.ownership = ownership;
+        project
+    }
+
+    #[test]
+    pub fn write_project_dot_uses_graph_title() {
+        use crate::analysis::Analysis;
+
+        let analysis = Analysis { root_path: PathBuf::from("/src/repo"), ..Default::default() };
+        let mut graph = DnGraph::default();
+        graph.add_node(Node::Analysis(&analysis));
+
+        let mut output = vec![];
+        write_project_dot(&mut output, &Configuration::default(), &graph, &HashSet::new()).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(!output.contains("    label="));
+        assert!(output.contains("[label=\"/src/repo (root dir)\""));
+
+        let configuration = Configuration { graph_title: Some("Payments".to_owned()), ..Configuration::default() };
+        let mut output = vec![];
+        write_project_dot(&mut output, &configuration, &graph, &HashSet::new()).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("    label=\"Payments\"\n"));
+        assert!(output.contains("0 [label=\"Payments\","));
+        assert!(!output.contains("/src/repo"));
+
+        let configuration = Configuration { graph_title: Some(r#"The "Payments" repo in C:\src"#.to_owned()), ..Configuration::default() };
+        let mut output = vec![];
+        write_project_dot(&mut output, &configuration, &graph, &HashSet::new()).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains(r#"    label="The \"Payments\" repo in C:\\src""#), "{}", output);
+    }
+
+    #[test]
+    pub fn write_project_dot_draws_redundant_edges_in_the_configured_style() {
+        let sln = Solution::default();
+        let p1 = make_project("/temp/p1.csproj", ProjectOwnership::Linked);
+        let p2 = make_project("/temp/p2.csproj", ProjectOwnership::Linked);
+
+        let mut graph = DnGraph::default();
+        let sln_idx = graph.add_node(Node::Solution(&sln));
+        let p1_idx = graph.add_node(Node::Project(&p1));
+        let p2_idx = graph.add_node(Node::Project(&p2));
+        graph.add_edge(sln_idx, p1_idx, ());
+        graph.add_edge(p1_idx, p2_idx, ());
+        let mut removed_edges = HashSet::new();
+        removed_edges.insert((sln_idx, p2_idx));
+
+        let write = |redundant_edge_style| {
+            let configuration = Configuration { redundant_edge_style, ..Configuration::default() };
+            let mut output = vec![];
+            write_project_dot(&mut output, &configuration, &graph, &removed_edges).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        let output = write(RedundantEdgeStyle::Red);
+        assert!(output.contains("    0 -> 2 [color=red,style=dotted,penwidth=2]\n"));
+        assert!(output.contains("    1 -> 2\n"));
+
+        let output = write(RedundantEdgeStyle::Dashed);
+        assert!(output.contains("    0 -> 2 [style=dashed]\n"));
+        assert!(output.contains("    1 -> 2\n"));
+
+        let output = write(RedundantEdgeStyle::Hidden);
+        assert!(!output.contains("0 -> 2"));
+        assert!(output.contains("    1 -> 2\n"));
+
+        assert_eq!(Configuration::default().redundant_edge_style, RedundantEdgeStyle::Red);
+    }
+
+    #[test]
+    pub fn write_adjacency_works() {
+        let mut sln = Solution::default();
+        sln.file_info = FileInfo { path: PathBuf::from("/temp/car.sln"), ..Default::default() };
+        let app = make_project("/temp/app.csproj", ProjectOwnership::Linked);
+        let lib = make_project("/temp/lib.csproj", ProjectOwnership::Linked);
+        let core = make_project("/temp/core.csproj", ProjectOwnership::Linked);
+        let stray = make_project("/temp/stray.csproj", ProjectOwnership::Orphaned);
+
+        let mut graph = DnGraph::default();
+        let sln_idx = graph.add_node(Node::Solution(&sln));
+        let app_idx = graph.add_node(Node::Project(&app));
+        let lib_idx = graph.add_node(Node::Project(&lib));
+        let core_idx = graph.add_node(Node::Project(&core));
+        graph.add_node(Node::Project(&stray));
+        graph.add_edge(sln_idx, app_idx, ());
+        graph.add_edge(app_idx, lib_idx, ());
+        graph.add_edge(app_idx, core_idx, ());
+        graph.add_edge(lib_idx, core_idx, ());
+
+        let mut output = vec![];
+        write_adjacency(&mut output, &graph).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(),
+            "app -> core,lib\n\
+             car -> app\n\
+             core -> \n\
+             lib -> core\n\
+             stray -> \n");
+    }
+
+    #[test]
+    pub fn write_project_dgml_works() {
+        let mut sln = Solution::default();
+        sln.file_info = FileInfo { path: PathBuf::from("/temp/a&b.sln"), ..Default::default() };
+        let p1 = make_project("/temp/p1.csproj", ProjectOwnership::Linked);
+        let p2 = make_project("/temp/p2.csproj", ProjectOwnership::Orphaned);
+
+        let mut graph = DnGraph::default();
+        let sln_idx = graph.add_node(Node::Solution(&sln));
+        let p1_idx = graph.add_node(Node::Project(&p1));
+        let p2_idx = graph.add_node(Node::Project(&p2));
+        graph.add_edge(sln_idx, p1_idx, ());
+        graph.add_edge(p1_idx, p2_idx, ());
+
+        let mut removed_edges = HashSet::new();
+        removed_edges.insert((sln_idx, p2_idx));
+
+        let mut output = vec![];
+        write_project_dgml(&mut output, &Configuration::default(), &graph, &removed_edges).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let doc = roxmltree::Document::parse(&output).unwrap();
+        let nodes = doc.descendants().filter(|n| n.has_tag_name("Node")).collect::<Vec<_>>();
+        let links = doc.descendants().filter(|n| n.has_tag_name("Link")).collect::<Vec<_>>();
+
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(nodes[0].attribute("Label"), Some("a&b"));
+        assert_eq!(nodes[2].attribute("Category"), Some("OrphanedProject"));
+
+        assert_eq!(links.len(), 3);
+        let redundant = links.iter().filter(|l| l.attribute("IsRedundant") == Some("True")).collect::<Vec<_>>();
+        assert_eq!(redundant.len(), 1);
+        assert_eq!(redundant[0].attribute("Source"), Some("0"));
+        assert_eq!(redundant[0].attribute("Target"), Some("2"));
+    }
+}
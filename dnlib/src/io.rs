@@ -1,16 +1,31 @@
 use crate::enums::InterestingFile;
-use crate::errors::DnLibResult;
-use logging_timer::{timer, finish};
+use crate::errors::{DnLibError, DnLibResult};
+use logging_timer::{timer, executing, finish};
+use rayon::prelude::*;
+use regex::{Regex, RegexBuilder};
+use serde::{Serialize, Deserialize};
 
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 use std::{fs, io};
-use walkdir::{DirEntry, WalkDir};
+use walkdir::WalkDir;
 
 /// A trait for disk IO, to allow us to mock out the filesystem.
 pub trait FileLoader: Clone {
     fn read_to_string(&self, path: &Path) -> io::Result<String>;
+
+    /// The time the file was last modified, if the loader knows it.
+    fn modified(&self, _path: &Path) -> Option<SystemTime> {
+        None
+    }
+
+    /// The names of the files directly in `directory`, if the loader knows them.
+    fn file_names(&self, _directory: &Path) -> Vec<String> {
+        vec![]
+    }
 }
 
 /// A struct that passes FileLoader calls through to the
@@ -22,6 +37,19 @@ impl FileLoader for DiskFileLoader {
     fn read_to_string(&self, path: &Path) -> io::Result<String> {
         fs::read_to_string(path)
     }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|md| md.modified()).ok()
+    }
+
+    fn file_names(&self, directory: &Path) -> Vec<String> {
+        fs::read_dir(directory).map_or_else(|_| vec![], |entries| {
+            entries.filter_map(Result::ok)
+                .filter(|entry| entry.file_type().map_or(false, |ft| ft.is_file()))
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect()
+        })
+    }
 }
 
 /// A struct that implements FileLoader by resolving calls from
@@ -47,57 +75,384 @@ impl FileLoader for MemoryFileLoader {
             |contents| Ok(contents.to_owned()),
         )
     }
+
+    fn file_names(&self, directory: &Path) -> Vec<String> {
+        self.files.keys()
+            .filter(|path| path.parent() == Some(directory))
+            .map(|path| path.filename_as_str().to_owned())
+            .collect()
+    }
+}
+
+/// A file read through a `RecordingFileLoader`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FileRead {
+    pub path: PathBuf,
+    /// False if the file exists but could not be read as text, e.g. because it is
+    /// not valid UTF-8.
+    pub success: bool,
+}
+
+impl FileRead {
+    /// "sln", "csproj", the name of an interesting file such as "packages.config",
+    /// or "other" for anything else that was read, such as global.json.
+    pub fn kind(&self) -> String {
+        // Not `is_sln_file` and friends, since the file may not be on disk.
+        let ext = self.path.extension_as_str();
+        if unicase::eq_ascii(ext, "sln") {
+            "sln".to_owned()
+        } else if unicase::eq_ascii(ext, "csproj") {
+            "csproj".to_owned()
+        } else {
+            InterestingFile::from_str(self.path.filename_as_str())
+                .map_or_else(|_| "other".to_owned(), |file| file.to_string())
+        }
+    }
+}
+
+/// A FileLoader that passes calls through to another loader and records every file
+/// that is read, so that we can say exactly what the analysis looked at. Clones share
+/// the same record, so the reads made on all threads end up in one place. Files that
+/// do not exist are not recorded, since many reads just probe for optional files.
+#[derive(Debug, Default, Clone)]
+pub struct RecordingFileLoader<L> {
+    inner: L,
+    reads: Arc<Mutex<Vec<FileRead>>>,
+}
+
+impl<L: FileLoader> RecordingFileLoader<L> {
+    pub fn new(inner: L) -> Self {
+        RecordingFileLoader { inner, reads: Arc::default() }
+    }
+
+    /// The files read so far, ordered by path. A file that was read several times
+    /// appears once.
+    pub fn reads(&self) -> Vec<FileRead> {
+        let mut reads = self.reads.lock().unwrap().clone();
+        reads.sort();
+        reads.dedup();
+        reads
+    }
+}
+
+impl<L: FileLoader> FileLoader for RecordingFileLoader<L> {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        let result = self.inner.read_to_string(path);
+        match result {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {},
+            _ => self.reads.lock().unwrap().push(FileRead { path: path.to_owned(), success: result.is_ok() }),
+        }
+
+        result
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        self.inner.modified(path)
+    }
+
+    fn file_names(&self, directory: &Path) -> Vec<String> {
+        self.inner.file_names(directory)
+    }
 }
 
 /// This struct is used to collect the raw directory walking results prior to further
 /// analysis. It is basically just a list of paths of various types. No effort is made
 /// to relate the csproj files to their owning sln files, for example (that requires
 /// probing inside the file contents and is left to a later stage of analysis).
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct PathsToAnalyze {
     pub sln_files: Vec<PathBuf>,
     pub csproj_files: Vec<PathBuf>,
     pub other_files: Vec<PathBuf>,
 }
 
-pub fn find_files<P>(path: P) -> DnLibResult<PathsToAnalyze>
+impl PathsToAnalyze {
+    /// Adds `path` to the appropriate list, if it is a file we are interested in.
+    /// If `only_sln` is set then that is the only sln file that will be added.
+    fn classify(&mut self, path: &Path, only_sln: Option<&Path>, excluded_files: &[String]) {
+        if path.is_sln_file() {
+            if only_sln.is_none() || only_sln == Some(path) {
+                self.sln_files.push(path.to_owned());
+            }
+        } else if path.is_csproj_file() {
+            self.csproj_files.push(path.to_owned());
+        } else {
+            let filename = path.filename_as_str();
+            if is_file_of_interest(&filename, excluded_files) {
+                self.other_files.push(path.to_owned());
+            }
+        }
+    }
+
+    /// The number of files of each kind found so far, for logging.
+    fn counts(&self) -> String {
+        format!("NumSolutions={} NumCsproj={}, NumOtherFiles={}", self.sln_files.len(), self.csproj_files.len(), self.other_files.len())
+    }
+
+    /// Sorts each list, so that the result does not depend on the order in which
+    /// the file system returned the directory entries.
+    fn sort(&mut self) {
+        self.sln_files.sort();
+        self.csproj_files.sort();
+        self.other_files.sort();
+    }
+
+    /// The total number of files of all kinds.
+    pub fn num_files(&self) -> usize {
+        self.sln_files.len() + self.csproj_files.len() + self.other_files.len()
+    }
+}
+
+/// The name of the file, in the root of the scan, that lists further ignore patterns.
+pub const IGNORE_FILENAME: &str = ".dnscanignore";
+
+/// A set of glob patterns for files and directories that should not be scanned.
+/// The syntax is a subset of `.gitignore`: `*` and `?` match within a single path
+/// component and `**` matches across components. A pattern containing a `/` is
+/// matched against the whole path relative to the scan root, otherwise it is
+/// matched against each file or directory name. Matching ignores case.
+#[derive(Debug, Default, Clone)]
+pub struct IgnorePatterns {
+    patterns: Vec<Regex>,
+}
+
+impl IgnorePatterns {
+    /// Builds the patterns from lines such as those in a `.dnscanignore` file.
+    /// Blank lines and lines beginning with `#` are skipped.
+    pub fn new<S: AsRef<str>>(lines: &[S]) -> Self {
+        let patterns = lines.iter()
+            .map(|line| line.as_ref().trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(Self::glob_to_regex)
+            .collect();
+
+        IgnorePatterns { patterns }
+    }
+
+    /// Loads `.dnscanignore` from the `root` directory, if there is one, and adds its
+    /// patterns to `extra_patterns` (typically the configured `ignore_patterns`).
+    pub fn load<S: AsRef<str>>(root: &Path, extra_patterns: &[S]) -> DnLibResult<Self> {
+        let mut lines = extra_patterns.iter()
+            .map(|p| p.as_ref().to_owned())
+            .collect::<Vec<_>>();
+
+        let path = root.join(IGNORE_FILENAME);
+        match fs::read_to_string(&path) {
+            Ok(contents) => lines.extend(contents.lines().map(|l| l.to_owned())),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {},
+            Err(e) => return Err(DnLibError::file_read(&path, e)),
+        }
+
+        Ok(Self::new(&lines))
+    }
+
+    /// Returns true if `relative_path` (relative to the scan root) should be skipped.
+    pub fn is_ignored(&self, relative_path: &Path) -> bool {
+        if self.patterns.is_empty() || relative_path.as_os_str().is_empty() {
+            return false;
+        }
+
+        let path = relative_path.components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        self.patterns.iter().any(|re| re.is_match(&path))
+    }
+
+    fn glob_to_regex(glob: &str) -> Regex {
+        let glob = glob.trim_end_matches('/');
+        let anchored = glob.contains('/');
+        let glob = glob.trim_start_matches('/');
+
+        let mut pattern = String::from(if anchored { "^" } else { "(?:^|/)" });
+        let mut chars = glob.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        pattern.push_str("(?:.*/)?");
+                    } else {
+                        pattern.push_str(".*");
+                    }
+                },
+                '*' => pattern.push_str("[^/]*"),
+                '?' => pattern.push_str("[^/]"),
+                _ => pattern.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        pattern.push('$');
+
+        RegexBuilder::new(&pattern).case_insensitive(true).build().unwrap()
+    }
+}
+
+/// Walks the directory tree under `path` looking for files to analyze. If `path` is
+/// a sln file rather than a directory then its directory is walked instead, but that
+/// sln is the only one that will be returned. Files named in `excluded_files` are
+/// never treated as files of interest. Files and directories matching `ignore_patterns`,
+/// or the patterns in a `.dnscanignore` file in the root directory, are skipped.
+/// If `progress_interval` is set, the counts so far are logged that often during the walk.
+pub fn find_files<P>(
+    path: P,
+    excluded_files: &[String],
+    ignore_patterns: &[String],
+    progress_interval: Option<Duration>,
+) -> DnLibResult<PathsToAnalyze>
 where
     P: AsRef<Path>,
 {
-    let tmr = timer!("Find Files", "Dir={:?}", path.as_ref());
+    let path = path.as_ref();
+    let tmr = timer!("Find Files", "Dir={:?}", path);
 
+    let (root, only_sln) = walk_root(path);
+    let (root, only_sln) = (root.as_path(), only_sln.as_deref());
+
+    let ignore = IgnorePatterns::load(root, ignore_patterns)?;
     let mut pta = PathsToAnalyze::default();
-    let walker = WalkDir::new(path);
+    let walker = WalkDir::new(root);
+
+    let walk = walker.into_iter().filter_entry(|e| {
+        continue_walking(e.path()) && !ignore.is_ignored(e.path().strip_prefix(root).unwrap_or(e.path()))
+    });
 
-    for entry in walker.into_iter().filter_entry(|e| continue_walking(e)) {
+    let mut heartbeat = Heartbeat::new(progress_interval);
+    for (num_entries, entry) in walk.enumerate() {
         let entry = entry?;
-        let path = entry.path();
+        pta.classify(entry.path(), only_sln, excluded_files);
+        if heartbeat.is_due(num_entries + 1) {
+            executing!(tmr, "Entries={} {}", num_entries + 1, pta.counts());
+        }
+    }
 
-        if path.is_sln_file() {
-            pta.sln_files.push(path.to_owned());
-        } else if path.is_csproj_file() {
-            pta.csproj_files.push(path.to_owned());
-        } else {
-            let filename = path.filename_as_str();
-            if is_file_of_interest(&filename) {
-                pta.other_files.push(path.to_owned());
+    pta.sort();
+    finish!(tmr, "{}", pta.counts());
+
+    Ok(pta)
+}
+
+/// Splits the path to scan into the directory to walk and, if the path is a single
+/// sln file, that file as the walk will find it. The walk of "." yields "./Foo.sln",
+/// so a bare "Foo.sln" has to be joined onto its directory to be recognised.
+fn walk_root(path: &Path) -> (PathBuf, Option<PathBuf>) {
+    if path.is_sln_file() {
+        let root = path.parent_dir();
+        (root.to_owned(), Some(root.join(path.file_name().unwrap())))
+    } else {
+        (path.to_owned(), None)
+    }
+}
+
+/// Like `find_files`, but reads the directories at each level of the tree in parallel.
+/// This helps when the walk is dominated by disk latency, e.g. on spinning disks or
+/// network shares. The same pruning rules apply and the result is identical.
+pub fn find_files_parallel<P>(
+    path: P,
+    excluded_files: &[String],
+    ignore_patterns: &[String],
+    progress_interval: Option<Duration>,
+) -> DnLibResult<PathsToAnalyze>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let tmr = timer!("Find Files In Parallel", "Dir={:?}", path);
+
+    let (root, only_sln) = walk_root(path);
+    let (root, only_sln) = (root.as_path(), only_sln.as_deref());
+
+    let ignore = IgnorePatterns::load(root, ignore_patterns)?;
+    let wanted = |path: &Path| continue_walking(path) && !ignore.is_ignored(path.strip_prefix(root).unwrap_or(path));
+    let mut pta = PathsToAnalyze::default();
+
+    if !wanted(root) {
+        return Ok(pta);
+    }
+
+    let root_is_dir = fs::metadata(root).map_err(|e| walk_error(root, e))?.is_dir();
+    pta.classify(root, only_sln, excluded_files);
+    let mut dirs = if root_is_dir { vec![root.to_owned()] } else { vec![] };
+    let mut heartbeat = Heartbeat::new(progress_interval);
+    let mut num_entries = 1;
+
+    while !dirs.is_empty() {
+        let children = dirs.par_iter()
+            .map(|dir| read_dir_entries(dir))
+            .collect::<Vec<_>>();
+
+        dirs.clear();
+        for (child, is_dir) in children.into_iter().collect::<DnLibResult<Vec<_>>>()?.into_iter().flatten() {
+            if wanted(&child) {
+                num_entries += 1;
+                pta.classify(&child, only_sln, excluded_files);
+                if is_dir {
+                    dirs.push(child);
+                }
             }
         }
+
+        if heartbeat.is_due(num_entries) {
+            executing!(tmr, "Entries={} {}", num_entries, pta.counts());
+        }
     }
 
-    finish!(
-        tmr,
-        "NumSolutions={} NumCsproj={}, NumOtherFiles={}",
-        pta.sln_files.len(),
-        pta.csproj_files.len(),
-        pta.other_files.len()
-    );
+    pta.sort();
+    finish!(tmr, "{}", pta.counts());
 
     Ok(pta)
 }
 
-fn continue_walking(entry: &DirEntry) -> bool {
-    let path = entry.path();
+/// Decides when to log progress during a walk, so that a long scan of a large tree
+/// does not look as if it has hung.
+struct Heartbeat {
+    interval: Option<Duration>,
+    last: Instant,
+}
+
+impl Heartbeat {
+    fn new(interval: Option<Duration>) -> Self {
+        Heartbeat { interval, last: Instant::now() }
+    }
+
+    /// True if progress should be logged now, having walked `num_entries` entries.
+    /// The clock restarts each time this returns true.
+    fn is_due(&mut self, num_entries: usize) -> bool {
+        let due = heartbeat_due(num_entries, self.last.elapsed(), self.interval);
+        if due {
+            self.last = Instant::now();
+        }
+        due
+    }
+}
+
+/// True if a progress message is due, given the number of entries walked so far and
+/// the time since the last message (or since the walk started).
+fn heartbeat_due(num_entries: usize, since_last: Duration, interval: Option<Duration>) -> bool {
+    match interval {
+        Some(interval) => num_entries > 0 && interval > Duration::from_secs(0) && since_last >= interval,
+        None => false,
+    }
+}
+
+/// The entries in `dir`, and whether each is a directory. Symlinks are not followed,
+/// matching `WalkDir`.
+fn read_dir_entries(dir: &Path) -> DnLibResult<Vec<(PathBuf, bool)>> {
+    let mut entries = vec![];
+    for entry in fs::read_dir(dir).map_err(|e| walk_error(dir, e))? {
+        let entry = entry.map_err(|e| walk_error(dir, e))?;
+        let is_dir = entry.file_type().map_err(|e| walk_error(&entry.path(), e))?.is_dir();
+        entries.push((entry.path(), is_dir));
+    }
+    Ok(entries)
+}
+
+fn walk_error(path: &Path, err: io::Error) -> DnLibError {
+    DnLibError::WalkError(format!("IO error for operation on {}: {}", path.display(), err))
+}
+
+fn continue_walking(path: &Path) -> bool {
     if path.is_hidden_dir()
         || path.is_bin_or_obj_dir()
         || path.is_packages_dir()
@@ -111,8 +466,9 @@ fn continue_walking(entry: &DirEntry) -> bool {
     true
 }
 
-fn is_file_of_interest(filename: &str) -> bool {
-    InterestingFile::from_str(filename).is_ok()
+pub(crate) fn is_file_of_interest(filename: &str, excluded_files: &[String]) -> bool {
+    InterestingFile::from_str(filename).is_ok() &&
+        !excluded_files.iter().any(|ex| ex.eq_ignore_ascii_case(filename))
 }
 
 pub trait PathExtensions {
@@ -126,8 +482,11 @@ pub trait PathExtensions {
     fn directory_as_str(&self) -> &str;
     // Returns the extension as a str, or "" if it cannot be converted.
     fn extension_as_str(&self) -> &str;
+    // Returns the directory containing the file, which is "." for a bare file name such as "Foo.sln".
+    fn parent_dir(&self) -> &Path;
     fn eq_ignoring_case<P: AsRef<Path>>(&self, other: P) -> bool;
-    fn is_same_dir<P: AsRef<Path>>(&self, other: P) -> bool;
+    fn eq_path<P: AsRef<Path>>(&self, other: P, case_sensitive: bool) -> bool;
+    fn is_same_dir<P: AsRef<Path>>(&self, other: P, case_sensitive: bool) -> bool;
     fn is_hidden_dir(&self) -> bool;
     fn is_bin_or_obj_dir(&self) -> bool;
     fn is_packages_dir(&self) -> bool;
@@ -192,6 +551,13 @@ impl PathExtensions for Path {
         }
     }
 
+    fn parent_dir(&self) -> &Path {
+        match self.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        }
+    }
+
     /// Due to the awful situation on Windows, where paths embedded in project and solution files are
     /// often different in case to what is actually on disk, we perform most comparisons in a
     /// case-insensitive manner.
@@ -199,10 +565,22 @@ impl PathExtensions for Path {
         unicase::eq_ascii(self.as_str(), other.as_ref().as_str())
     }
 
-    fn is_same_dir<P: AsRef<Path>>(&self, other: P) -> bool {
-        let p1 = self.parent().unwrap();
-        let p2 = other.as_ref().parent().unwrap();
-        p1.is_dir() && p2.is_dir() && p1.eq_ignoring_case(p2)
+    /// Compares two paths, only ignoring case if the filesystem is case-insensitive.
+    /// On a case-sensitive filesystem two paths that differ only in case are genuinely
+    /// different files, so matching them would be wrong.
+    fn eq_path<P: AsRef<Path>>(&self, other: P, case_sensitive: bool) -> bool {
+        if case_sensitive {
+            self == other.as_ref()
+        } else {
+            self.eq_ignoring_case(other)
+        }
+    }
+
+    fn is_same_dir<P: AsRef<Path>>(&self, other: P, case_sensitive: bool) -> bool {
+        match (self.parent(), other.as_ref().parent()) {
+            (Some(p1), Some(p2)) => p1.is_dir() && p2.is_dir() && p1.eq_path(p2, case_sensitive),
+            _ => false,
+        }
     }
 
     fn is_hidden_dir(&self) -> bool {
@@ -293,11 +671,258 @@ pub fn make_path_under_home_dir(sub_path: &str) -> PathBuf {
     p
 }
 
+/// Makes `name` safe to use as a file name on any platform, by replacing path
+/// separators and the other characters that Windows does not allow with '_'.
+pub fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Converts a path into an absolute `file://` URI, such as editors accept as a link.
+/// Windows paths (`C:\foo` and `\\server\share`) are recognised whatever platform we
+/// are running on, since sln and csproj files may have been written on Windows.
+/// Relative paths are taken to be relative to the current directory.
+pub fn path_to_file_uri(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let is_drive = bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':';
+    let is_unc = path.starts_with(r"\\") || path.starts_with("//");
+
+    let path = if is_drive || is_unc || path.starts_with('/') || path.starts_with('\\') {
+        path.to_owned()
+    } else {
+        std::env::current_dir().unwrap_or_default().join(path).as_str().to_owned()
+    };
+
+    let path = path.replace('\\', "/");
+    let (prefix, rest) = if is_unc {
+        ("file://", path.trim_start_matches('/'))
+    } else if is_drive {
+        ("file:///", path.as_str())
+    } else {
+        ("file://", path.as_str())
+    };
+
+    let mut uri = String::from(prefix);
+    for (idx, b) in rest.bytes().enumerate() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => uri.push(b as char),
+            // The colon after a drive letter is kept, so that "C:" stays readable.
+            b':' if is_drive && idx == 1 => uri.push(':'),
+            _ => uri.push_str(&format!("%{:02X}", b)),
+        }
+    }
+
+    uri
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::path::PathBuf;
 
+    /// Creates a temporary directory for a test to walk. The walker skips hidden
+    /// directories, so this avoids tempfile's default ".tmp" prefix.
+    fn test_input_dir() -> tempfile::TempDir {
+        tempfile::Builder::new().prefix("dnlib-temp-").tempdir().unwrap()
+    }
+
+    #[test]
+    pub fn recording_file_loader_records_reads_from_all_clones() {
+        let mut inner = MemoryFileLoader::new();
+        inner.files.insert(PathBuf::from("/temp/car.sln"), "".to_owned());
+        inner.files.insert(PathBuf::from("/temp/ford.csproj"), "".to_owned());
+        inner.files.insert(PathBuf::from("/temp/packages.config"), "".to_owned());
+        inner.files.insert(PathBuf::from("/temp/global.json"), "".to_owned());
+
+        let loader = RecordingFileLoader::new(inner);
+        let clone = loader.clone();
+        assert!(loader.read_to_string(Path::new("/temp/ford.csproj")).is_ok());
+        assert!(clone.read_to_string(Path::new("/temp/car.sln")).is_ok());
+        assert!(clone.read_to_string(Path::new("/temp/packages.config")).is_ok());
+        assert!(loader.read_to_string(Path::new("/temp/global.json")).is_ok());
+        assert!(loader.read_to_string(Path::new("/temp/ford.csproj")).is_ok());
+        // Probing for a file that is not there is not a read.
+        assert!(loader.read_to_string(Path::new("/temp/.editorconfig")).is_err());
+
+        let reads = loader.reads().into_iter()
+            .map(|read| (read.path.as_str().to_owned(), read.kind(), read.success))
+            .collect::<Vec<_>>();
+        assert_eq!(reads, vec![
+            ("/temp/car.sln".to_owned(), "sln".to_owned(), true),
+            ("/temp/ford.csproj".to_owned(), "csproj".to_owned(), true),
+            ("/temp/global.json".to_owned(), "other".to_owned(), true),
+            ("/temp/packages.config".to_owned(), "packages.config".to_owned(), true),
+        ]);
+    }
+
+    #[test]
+    pub fn recording_file_loader_records_unreadable_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bad.csproj");
+        fs::write(&path, [0xff, 0xfe, 0x00]).unwrap();
+
+        let loader = RecordingFileLoader::new(DiskFileLoader);
+        assert!(loader.read_to_string(&path).is_err());
+        assert_eq!(loader.reads(), vec![FileRead { path, success: false }]);
+    }
+
+    #[test]
+    pub fn is_file_of_interest_honours_exclusions() {
+        assert!(is_file_of_interest("project.json", &[]));
+        assert!(!is_file_of_interest("readme.txt", &[]));
+
+        let excluded = vec!["Project.json".to_owned()];
+        assert!(!is_file_of_interest("project.json", &excluded));
+        assert!(is_file_of_interest("packages.config", &excluded));
+    }
+
+    #[test]
+    pub fn find_files_never_returns_excluded_files() {
+        let root = test_input_dir();
+        fs::create_dir_all(root.path().join("sub")).unwrap();
+        fs::write(root.path().join("project.json"), "").unwrap();
+        fs::write(root.path().join("sub").join("project.json"), "").unwrap();
+        fs::write(root.path().join("sub").join("packages.config"), "").unwrap();
+
+        let pta = find_files(root.path(), &[], &[], None).unwrap();
+        assert_eq!(pta.other_files.len(), 3);
+
+        let pta = find_files(root.path(), &["project.json".to_owned()], &[], None).unwrap();
+        assert_eq!(pta.other_files, vec![root.path().join("sub").join("packages.config")]);
+    }
+
+    #[test]
+    pub fn ignore_patterns_match_names_and_paths() {
+        let ignore = IgnorePatterns::new(&["# A comment", "", "  Legacy/  ", "*.Tests.csproj", "src/**/generated", "/tools"]);
+
+        assert!(ignore.is_ignored(Path::new("legacy")));
+        assert!(ignore.is_ignored(Path::new("src/Legacy")));
+        assert!(ignore.is_ignored(Path::new("src/Car.Tests.csproj")));
+        assert!(!ignore.is_ignored(Path::new("src/Car.csproj")));
+        assert!(ignore.is_ignored(Path::new("src/generated")));
+        assert!(ignore.is_ignored(Path::new("src/a/b/generated")));
+        assert!(!ignore.is_ignored(Path::new("generated")));
+        assert!(ignore.is_ignored(Path::new("tools")));
+        assert!(!ignore.is_ignored(Path::new("src/tools")));
+        assert!(!ignore.is_ignored(Path::new("# A comment")));
+        assert!(!ignore.is_ignored(Path::new("")));
+    }
+
+    #[test]
+    pub fn find_files_honours_dnscanignore_and_configured_patterns() {
+        let root = test_input_dir();
+        fs::create_dir_all(root.path().join("legacy")).unwrap();
+        fs::create_dir_all(root.path().join("samples")).unwrap();
+        fs::write(root.path().join("car.sln"), "").unwrap();
+        fs::write(root.path().join("ford.csproj"), "").unwrap();
+        fs::write(root.path().join("legacy").join("old.csproj"), "").unwrap();
+        fs::write(root.path().join("samples").join("sample.csproj"), "").unwrap();
+
+        let pta = find_files(root.path(), &[], &[], None).unwrap();
+        assert_eq!(pta.csproj_files.len(), 3);
+
+        fs::write(root.path().join(IGNORE_FILENAME), "# Old stuff\n\nlegacy/\n").unwrap();
+        let pta = find_files(root.path(), &[], &[], None).unwrap();
+        let mut csproj_files = pta.csproj_files.clone();
+        csproj_files.sort();
+        assert_eq!(csproj_files, vec![root.path().join("ford.csproj"), root.path().join("samples").join("sample.csproj")]);
+
+        let pta = find_files(root.path(), &[], &["samples".to_owned()], None).unwrap();
+        assert_eq!(pta.csproj_files, vec![root.path().join("ford.csproj")]);
+        assert_eq!(pta.sln_files, vec![root.path().join("car.sln")]);
+    }
+
+    #[test]
+    pub fn find_files_parallel_finds_the_same_files_as_find_files() {
+        let root = test_input_dir();
+        for dir in &["a/b/c", "a/bin", "a/.hidden", "legacy", "d"] {
+            fs::create_dir_all(root.path().join(dir)).unwrap();
+        }
+        for file in &["car.sln", "ford.csproj", "packages.config", "readme.txt", "a/a.csproj", "a/b/App.config",
+                      "a/b/c/deep.csproj", "a/b/c/web.config", "a/bin/built.csproj", "a/.hidden/secret.csproj",
+                      "legacy/old.sln", "legacy/old.csproj", "d/trucks.sln", "d/volvo.csproj"] {
+            fs::write(root.path().join(file), "").unwrap();
+        }
+        fs::write(root.path().join(IGNORE_FILENAME), "legacy/
+").unwrap();
+
+        let sequential = find_files(root.path(), &[], &[], None).unwrap();
+        let parallel = find_files_parallel(root.path(), &[], &[], None).unwrap();
+        assert_eq!(format!("{:?}", parallel), format!("{:?}", sequential));
+        assert_eq!(parallel.sln_files, vec![root.path().join("car.sln"), root.path().join("d").join("trucks.sln")]);
+        assert_eq!(parallel.csproj_files.len(), 4);
+        assert_eq!(parallel.other_files.len(), 3);
+
+        let sln = root.path().join("d").join("trucks.sln");
+        let sequential = find_files(&sln, &["web.config".to_owned()], &["a/b".to_owned()], None).unwrap();
+        let parallel = find_files_parallel(&sln, &["web.config".to_owned()], &["a/b".to_owned()], None).unwrap();
+        assert_eq!(format!("{:?}", parallel), format!("{:?}", sequential));
+        assert_eq!(parallel.sln_files, vec![sln]);
+
+        match find_files_parallel(root.path().join("no-such-dir"), &[], &[], None) {
+            Err(DnLibError::WalkError(msg)) => assert!(msg.contains("no-such-dir"), "{}", msg),
+            other => panic!("Expected a WalkError, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    pub fn find_files_accepts_a_bare_sln_file_name() {
+        // Tests run in the crate directory, so the sln has to be created there.
+        let sln = tempfile::Builder::new().prefix("dnlib-temp-").suffix(".sln").tempfile_in(".").unwrap();
+        let name = Path::new(sln.path().file_name().unwrap());
+        assert_eq!(name.parent_dir(), Path::new("."));
+
+        let pta = find_files(name, &[], &[], None).unwrap();
+        assert_eq!(pta.sln_files, vec![Path::new(".").join(name)]);
+        let pta = find_files_parallel(name, &[], &[], None).unwrap();
+        assert_eq!(pta.sln_files, vec![Path::new(".").join(name)]);
+    }
+
+    #[test]
+    pub fn parent_dir_works() {
+        assert_eq!(Path::new("Foo.sln").parent_dir(), Path::new("."));
+        assert_eq!(Path::new("src/Foo.sln").parent_dir(), Path::new("src"));
+        assert_eq!(Path::new("/src/Foo.sln").parent_dir(), Path::new("/src"));
+    }
+
+    #[test]
+    pub fn heartbeat_due_only_after_the_interval() {
+        let interval = Some(Duration::from_secs(5));
+        assert!(!heartbeat_due(100, Duration::from_secs(4), interval));
+        assert!(heartbeat_due(100, Duration::from_secs(5), interval));
+        assert!(heartbeat_due(1, Duration::from_secs(60), interval));
+        assert!(!heartbeat_due(0, Duration::from_secs(60), interval));
+        assert!(!heartbeat_due(100, Duration::from_secs(60), None));
+        assert!(!heartbeat_due(100, Duration::from_secs(60), Some(Duration::from_secs(0))));
+    }
+
+    #[test]
+    pub fn find_files_reports_a_missing_directory_as_a_walk_error() {
+        let root = test_input_dir();
+        let missing = root.path().join("no-such-dir");
+
+        match find_files(&missing, &[], &[], None) {
+            Err(DnLibError::WalkError(msg)) => assert!(msg.contains("no-such-dir"), "{}", msg),
+            other => panic!("Expected a WalkError, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    pub fn find_files_reports_an_unreadable_dnscanignore_as_a_file_read_error() {
+        let root = test_input_dir();
+        fs::create_dir_all(root.path().join(IGNORE_FILENAME)).unwrap();
+
+        match find_files(root.path(), &[], &[], None) {
+            Err(DnLibError::FileReadError { path, .. }) => assert_eq!(path, root.path().join(IGNORE_FILENAME)),
+            other => panic!("Expected a FileReadError, got {:?}", other.map(|_| ())),
+        }
+    }
+
     #[test]
     pub fn eq_ignoring_case() {
         let p1 = PathBuf::from("");
@@ -316,4 +941,61 @@ mod tests {
         let p2 = PathBuf::from(r"A\B\c");
         assert!(p1.eq_ignoring_case(p2));
     }
+
+    #[test]
+    pub fn eq_path() {
+        let p1 = PathBuf::from("a/b/c.csproj");
+        let p2 = PathBuf::from("A/b/C.csproj");
+        assert!(p1.eq_path(&p2, false));
+        assert!(!p1.eq_path(&p2, true));
+
+        let p2 = PathBuf::from("a/b/c.csproj");
+        assert!(p1.eq_path(&p2, false));
+        assert!(p1.eq_path(&p2, true));
+    }
+
+    #[test]
+    pub fn path_to_file_uri_for_windows_paths() {
+        assert_eq!(path_to_file_uri(r"C:\Source\My Project\Foo.csproj"), "file:///C:/Source/My%20Project/Foo.csproj");
+        assert_eq!(path_to_file_uri(r"d:\a#b\c.sln"), "file:///d:/a%23b/c.sln");
+        assert_eq!(path_to_file_uri(r"\\server\share\Foo.sln"), "file://server/share/Foo.sln");
+    }
+
+    #[test]
+    pub fn sanitize_file_name_works() {
+        assert_eq!(sanitize_file_name("Newtonsoft.Json"), "Newtonsoft.Json");
+        assert_eq!(sanitize_file_name("../Acme/Core"), ".._Acme_Core");
+        assert_eq!(sanitize_file_name("a\\b:c*d?e\"f<g>h|i\tj"), "a_b_c_d_e_f_g_h_i_j");
+    }
+
+    #[test]
+    pub fn path_to_file_uri_for_posix_paths() {
+        assert_eq!(path_to_file_uri("/home/me/my code/Foo.csproj"), "file:///home/me/my%20code/Foo.csproj");
+        assert_eq!(path_to_file_uri("/tmp/caf\u{e9}/x:y.sln"), "file:///tmp/caf%C3%A9/x%3Ay.sln");
+
+        let uri = path_to_file_uri("relative/Foo.csproj");
+        assert!(uri.starts_with("file:///"), "{}", uri);
+        assert!(uri.ends_with("/relative/Foo.csproj"), "{}", uri);
+    }
+
+    // Only a case-sensitive filesystem can hold two directories that differ only in case.
+    #[cfg(target_os = "linux")]
+    #[test]
+    pub fn is_same_dir() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("sub")).unwrap();
+        std::fs::create_dir_all(root.path().join("SUB")).unwrap();
+
+        let p1 = root.path().join("sub").join("a.csproj");
+        let p2 = root.path().join("SUB").join("b.csproj");
+        assert!(p1.is_same_dir(&p2, false));
+        assert!(!p1.is_same_dir(&p2, true));
+
+        let p2 = root.path().join("sub").join("b.csproj");
+        assert!(p1.is_same_dir(&p2, true));
+
+        // A path with no parent is never in the same directory as anything.
+        assert!(!p1.is_same_dir("", true));
+        assert!(!Path::new("").is_same_dir(&p1, false));
+    }
 }
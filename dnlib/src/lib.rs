@@ -8,6 +8,11 @@ pub mod analysis;
 pub mod git_info;
 pub mod graph;
 pub mod graph_output;
+pub mod property_groups;
+pub mod diagnostics;
+pub mod protobuf;
+pub mod feed_index;
+pub mod editorconfig;
 
 pub mod prelude {
     pub use crate::errors::*;
@@ -17,6 +22,11 @@ pub mod prelude {
     pub use crate::analysis::*;
     pub use crate::git_info::*;
     pub use crate::graph::*;
+    pub use crate::property_groups::*;
+    pub use crate::diagnostics::*;
+    pub use crate::protobuf::*;
+    pub use crate::feed_index::*;
+    pub use crate::editorconfig::*;
 }
 
 pub use prelude::*;
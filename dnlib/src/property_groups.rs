@@ -0,0 +1,140 @@
+use lazy_static::lazy_static;
+use regex::{Regex, RegexBuilder};
+
+/// A `<PropertyGroup>` from a project file, along with the simple properties that it
+/// sets. This is a lightweight view, not a full MSBuild evaluation: conditions are not
+/// evaluated, but the build configuration they test for can be recovered.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PropertyGroup {
+    /// The `Condition` attribute of the group, if any.
+    pub condition: Option<String>,
+
+    /// The name and value of each property set in the group, in document order.
+    pub properties: Vec<(String, String)>,
+}
+
+impl PropertyGroup {
+    /// Splits the project file into its property groups. Any properties that appear
+    /// outside a `<PropertyGroup>` are gathered into unconditional groups, so that the
+    /// result covers every property in the file.
+    pub fn parse_all(project_file_contents: &str) -> Vec<PropertyGroup> {
+        lazy_static! {
+            static ref GROUP_REGEX: Regex = RegexBuilder::new(r#"<PropertyGroup\b(?P<attrs>[^<>]*?)(?:/>|>(?P<body>.*?)</PropertyGroup\s*>)"#)
+                .dot_matches_new_line(true).build().unwrap();
+            static ref CONDITION_REGEX: Regex = Regex::new(r#"Condition\s*=\s*"(?P<cond>[^"]*)""#).unwrap();
+        }
+
+        let mut result = vec![];
+        let mut last_end = 0;
+        for cap in GROUP_REGEX.captures_iter(project_file_contents) {
+            let whole = cap.get(0).unwrap();
+            Self::add_loose_properties(&project_file_contents[last_end..whole.start()], &mut result);
+            last_end = whole.end();
+
+            result.push(PropertyGroup {
+                condition: CONDITION_REGEX.captures(&cap["attrs"]).map(|c| c["cond"].trim().to_owned()),
+                properties: cap.name("body").map(|b| Self::parse_properties(b.as_str())).unwrap_or_default(),
+            });
+        }
+
+        Self::add_loose_properties(&project_file_contents[last_end..], &mut result);
+        result
+    }
+
+    fn add_loose_properties(text: &str, result: &mut Vec<PropertyGroup>) {
+        let properties = Self::parse_properties(text);
+        if !properties.is_empty() {
+            result.push(PropertyGroup { condition: None, properties });
+        }
+    }
+
+    fn parse_properties(text: &str) -> Vec<(String, String)> {
+        lazy_static! {
+            static ref PROPERTY_REGEX: Regex = Regex::new(r#"<(?P<name>[A-Za-z_][\w.]*)(?:\s[^<>]*)?>(?P<value>[^<]*)</(?P<close>[A-Za-z_][\w.]*)\s*>"#).unwrap();
+        }
+
+        PROPERTY_REGEX.captures_iter(text)
+            .filter(|cap| cap["name"] == cap["close"])
+            .map(|cap| (cap["name"].to_owned(), cap["value"].to_owned()))
+            .collect()
+    }
+
+    /// The build configuration, e.g. "Debug", that the group's condition tests for.
+    /// Returns None if the group is unconditional or does not test the configuration.
+    pub fn configuration(&self) -> Option<&str> {
+        lazy_static! {
+            static ref CONFIGURATION_REGEX: Regex = Regex::new(r#"'\$\(Configuration\)[^']*'\s*==\s*'(?P<cfg>[^'|]*)"#).unwrap();
+        }
+
+        self.condition.as_ref()
+            .and_then(|c| CONFIGURATION_REGEX.captures(c))
+            .map(|cap| cap.name("cfg").unwrap().as_str().trim())
+    }
+
+    /// True if the group applies when building the given configuration. Groups that
+    /// do not test the configuration apply to all of them.
+    pub fn applies_to(&self, configuration: &str) -> bool {
+        match self.configuration() {
+            Some(c) => c.eq_ignore_ascii_case(configuration),
+            None => true,
+        }
+    }
+
+    /// The values of every occurrence of the named property in the group.
+    pub fn values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.properties.iter()
+            .filter(move |(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn parse_all_associates_properties_with_conditions() {
+        let groups = PropertyGroup::parse_all(r#"
+            <PropertyGroup>
+                <OutputType>Library</OutputType>
+            </PropertyGroup>
+            <PropertyGroup Condition=" '$(Configuration)|$(Platform)' == 'Debug|AnyCPU' ">
+                <DefineConstants>DEBUG;TRACE</DefineConstants>
+            </PropertyGroup>
+            <PropertyGroup Condition="'$(Configuration)'=='Release'">
+                <DefineConstants>TRACE</DefineConstants>
+            </PropertyGroup>
+            <PropertyGroup />
+            "#);
+
+        assert_eq!(groups.len(), 4);
+        assert_eq!(groups[0].condition, None);
+        assert_eq!(groups[0].values("OutputType").collect::<Vec<_>>(), vec!["Library"]);
+        assert_eq!(groups[1].configuration(), Some("Debug"));
+        assert_eq!(groups[1].values("DefineConstants").collect::<Vec<_>>(), vec!["DEBUG;TRACE"]);
+        assert_eq!(groups[2].configuration(), Some("Release"));
+        assert!(groups[3].properties.is_empty());
+
+        assert!(groups[0].applies_to("Release"));
+        assert!(groups[1].applies_to("debug"));
+        assert!(!groups[1].applies_to("Release"));
+    }
+
+    #[test]
+    pub fn parse_all_includes_loose_properties() {
+        let groups = PropertyGroup::parse_all("blah<PlatformTarget>x64</PlatformTarget>blah");
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].condition, None);
+        assert_eq!(groups[0].properties, vec![("PlatformTarget".to_owned(), "x64".to_owned())]);
+    }
+
+    #[test]
+    pub fn configuration_ignores_other_conditions() {
+        let group = PropertyGroup {
+            condition: Some("'$(Platform)' == 'x64'".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(group.configuration(), None);
+        assert!(group.applies_to("Debug"));
+    }
+}
@@ -0,0 +1,439 @@
+use crate::analysis::{Analysis, Package, Project, Solution, SolutionDirectory};
+use crate::enums::{OutputType, ProjectOwnership, ProjectVersion, TestFramework, VisualStudioVersion};
+use crate::errors::DnLibResult;
+use prost::Message;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The name of the file written by `write_analysis_protobuf`.
+pub const PROTOBUF_FILENAME: &str = "analysis.pb";
+
+/// Writes the analysis into `dir` as a Protocol Buffers message, following the schema
+/// in proto/analysis.proto. Returns the path of the file that was written.
+pub fn write_analysis_protobuf<P: AsRef<Path>>(dir: P, analysis: &Analysis) -> DnLibResult<PathBuf> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    let path = dir.join(PROTOBUF_FILENAME);
+    fs::write(&path, encode_analysis(analysis))?;
+    Ok(path)
+}
+
+/// Encodes the analysis as an `Analysis` message. Only the fields in the schema are
+/// written; everything else can be recalculated from them or is too detailed for
+/// the services that consume the file.
+pub fn encode_analysis(analysis: &Analysis) -> Vec<u8> {
+    pb::Analysis::from(analysis).encode_to_vec()
+}
+
+/// Decodes an `Analysis` message written by `encode_analysis`. Fields that are not in
+/// the schema are left at their defaults.
+pub fn decode_analysis(bytes: &[u8]) -> DnLibResult<Analysis> {
+    let msg = pb::Analysis::decode(bytes)?;
+    Ok(msg.into())
+}
+
+/// The messages and enums of proto/analysis.proto. They are declared by hand rather
+/// than generated by prost-build so that building dnlib does not need `protoc`; keep
+/// the tags in step with the schema.
+pub mod pb {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum VisualStudioVersion {
+        Unknown = 0,
+        Vs2015 = 1,
+        Vs2017 = 2,
+        Vs2019 = 3,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum ProjectOwnership {
+        Unknown = 0,
+        Linked = 1,
+        Orphaned = 2,
+        TransitivelyRequired = 3,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum ProjectVersion {
+        Unknown = 0,
+        MicrosoftNetSdk = 1,
+        MicrosoftNetSdkWeb = 2,
+        OldStyle = 3,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum OutputType {
+        Unknown = 0,
+        Library = 1,
+        WinExe = 2,
+        Exe = 3,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum TestFramework {
+        None = 0,
+        MsTest = 1,
+        XUnit = 2,
+        NUnit = 3,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Analysis {
+        #[prost(string, tag = "1")]
+        pub root_path: String,
+        #[prost(message, repeated, tag = "2")]
+        pub solution_directories: Vec<SolutionDirectory>,
+        #[prost(message, repeated, tag = "3")]
+        pub unassociated_projects: Vec<Project>,
+        #[prost(bool, tag = "4")]
+        pub case_sensitive_paths: bool,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SolutionDirectory {
+        #[prost(string, tag = "1")]
+        pub directory: String,
+        #[prost(message, repeated, tag = "2")]
+        pub solutions: Vec<Solution>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Solution {
+        #[prost(string, tag = "1")]
+        pub path: String,
+        #[prost(enumeration = "VisualStudioVersion", tag = "2")]
+        pub version: i32,
+        #[prost(string, optional, tag = "3")]
+        pub minimum_version: Option<String>,
+        #[prost(message, repeated, tag = "4")]
+        pub projects: Vec<Project>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Project {
+        #[prost(string, tag = "1")]
+        pub path: String,
+        #[prost(enumeration = "ProjectOwnership", tag = "2")]
+        pub ownership: i32,
+        #[prost(enumeration = "ProjectVersion", tag = "3")]
+        pub version: i32,
+        #[prost(enumeration = "OutputType", tag = "4")]
+        pub output_type: i32,
+        #[prost(string, optional, tag = "5")]
+        pub root_namespace: Option<String>,
+        #[prost(string, optional, tag = "6")]
+        pub assembly_name: Option<String>,
+        #[prost(string, optional, tag = "7")]
+        pub project_guid: Option<String>,
+        #[prost(string, repeated, tag = "8")]
+        pub target_frameworks: Vec<String>,
+        #[prost(enumeration = "TestFramework", tag = "9")]
+        pub test_framework: i32,
+        #[prost(message, repeated, tag = "10")]
+        pub packages: Vec<Package>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Package {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(string, tag = "2")]
+        pub version: String,
+        #[prost(bool, tag = "3")]
+        pub development: bool,
+        #[prost(string, tag = "4")]
+        pub class: String,
+        #[prost(bool, tag = "5")]
+        pub missing_version: bool,
+        #[prost(bool, tag = "6")]
+        pub framework_mismatch: bool,
+    }
+}
+
+fn path_to_string(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+impl From<&Analysis> for pb::Analysis {
+    fn from(analysis: &Analysis) -> Self {
+        pb::Analysis {
+            root_path: path_to_string(&analysis.root_path),
+            solution_directories: analysis.solution_directories.iter().map(Into::into).collect(),
+            unassociated_projects: analysis.unassociated_projects.iter().map(Into::into).collect(),
+            case_sensitive_paths: analysis.case_sensitive_paths,
+        }
+    }
+}
+
+impl From<pb::Analysis> for Analysis {
+    fn from(msg: pb::Analysis) -> Self {
+        Analysis {
+            root_path: PathBuf::from(msg.root_path),
+            solution_directories: msg.solution_directories.into_iter().map(Into::into).collect(),
+            unassociated_projects: msg.unassociated_projects.into_iter().map(Into::into).collect(),
+            case_sensitive_paths: msg.case_sensitive_paths,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&SolutionDirectory> for pb::SolutionDirectory {
+    fn from(sd: &SolutionDirectory) -> Self {
+        pb::SolutionDirectory {
+            directory: path_to_string(&sd.directory),
+            solutions: sd.solutions.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<pb::SolutionDirectory> for SolutionDirectory {
+    fn from(msg: pb::SolutionDirectory) -> Self {
+        SolutionDirectory {
+            directory: PathBuf::from(msg.directory),
+            solutions: msg.solutions.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&Solution> for pb::Solution {
+    fn from(sln: &Solution) -> Self {
+        pb::Solution {
+            path: path_to_string(&sln.file_info.path),
+            version: pb::VisualStudioVersion::from(sln.version) as i32,
+            minimum_version: sln.minimum_version.clone(),
+            projects: sln.projects.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<pb::Solution> for Solution {
+    fn from(msg: pb::Solution) -> Self {
+        let mut sln = Solution::default();
+        sln.file_info.path = PathBuf::from(msg.path);
+        sln.version = msg.version().into();
+        sln.minimum_version = msg.minimum_version;
+        sln.projects = msg.projects.into_iter().map(Into::into).collect();
+        sln
+    }
+}
+
+impl From<&Project> for pb::Project {
+    fn from(proj: &Project) -> Self {
+        pb::Project {
+            path: path_to_string(&proj.file_info.path),
+            ownership: pb::ProjectOwnership::from(proj.ownership) as i32,
+            version: pb::ProjectVersion::from(proj.version) as i32,
+            output_type: pb::OutputType::from(proj.output_type) as i32,
+            root_namespace: proj.root_namespace.clone(),
+            assembly_name: proj.assembly_name.clone(),
+            project_guid: proj.project_guid.clone(),
+            target_frameworks: proj.target_frameworks.clone(),
+            test_framework: pb::TestFramework::from(proj.test_framework) as i32,
+            packages: proj.packages.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<pb::Project> for Project {
+    fn from(msg: pb::Project) -> Self {
+        let mut proj = Project::default();
+        proj.file_info.path = PathBuf::from(&msg.path);
+        proj.ownership = msg.ownership().into();
+        proj.version = msg.version().into();
+        proj.output_type = msg.output_type().into();
+        proj.test_framework = msg.test_framework().into();
+        proj.root_namespace = msg.root_namespace;
+        proj.assembly_name = msg.assembly_name;
+        proj.project_guid = msg.project_guid;
+        proj.target_frameworks = msg.target_frameworks;
+        proj.packages = msg.packages.into_iter().map(Into::into).collect();
+        proj
+    }
+}
+
+impl From<&Package> for pb::Package {
+    fn from(pkg: &Package) -> Self {
+        pb::Package {
+            name: pkg.name.clone(),
+            version: pkg.version.clone(),
+            development: pkg.development,
+            class: pkg.class.clone(),
+            missing_version: pkg.missing_version,
+            framework_mismatch: pkg.framework_mismatch,
+        }
+    }
+}
+
+impl From<pb::Package> for Package {
+    fn from(msg: pb::Package) -> Self {
+        Package {
+            missing_version: msg.missing_version,
+            framework_mismatch: msg.framework_mismatch,
+            ..Package::new(msg.name, msg.version, msg.development, msg.class)
+        }
+    }
+}
+
+/// Implements the conversions between one of our enums and its protobuf equivalent.
+/// prost decodes numbers it does not know, e.g. from a newer schema, as the
+/// protobuf default, which maps to the default of our enum.
+macro_rules! proto_enum {
+    ($ours:ident, $theirs:ident, $($variant:ident => $pb_variant:ident),+) => {
+        impl From<$ours> for pb::$theirs {
+            fn from(value: $ours) -> Self {
+                match value {
+                    $($ours::$variant => pb::$theirs::$pb_variant),+
+                }
+            }
+        }
+
+        impl From<pb::$theirs> for $ours {
+            fn from(value: pb::$theirs) -> Self {
+                match value {
+                    $(pb::$theirs::$pb_variant => $ours::$variant),+
+                }
+            }
+        }
+    };
+}
+
+proto_enum!(VisualStudioVersion, VisualStudioVersion,
+    Unknown => Unknown, VS2015 => Vs2015, VS2017 => Vs2017, VS2019 => Vs2019);
+
+proto_enum!(ProjectOwnership, ProjectOwnership,
+    Unknown => Unknown, Linked => Linked, Orphaned => Orphaned, TransitivelyRequired => TransitivelyRequired);
+
+proto_enum!(ProjectVersion, ProjectVersion,
+    Unknown => Unknown, MicrosoftNetSdk => MicrosoftNetSdk, MicrosoftNetSdkWeb => MicrosoftNetSdkWeb, OldStyle => OldStyle);
+
+proto_enum!(OutputType, OutputType,
+    Unknown => Unknown, Library => Library, WinExe => WinExe, Exe => Exe);
+
+proto_enum!(TestFramework, TestFramework,
+    None => None, MSTest => MsTest, XUnit => XUnit, NUnit => NUnit);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::DnLibError;
+
+    fn make_project(path: &str) -> Project {
+        let mut proj = Project::default();
+        proj.file_info.path = PathBuf::from(path);
+        proj
+    }
+
+    fn make_analysis() -> Analysis {
+        let mut linked = make_project("/temp/car/ford.csproj");
+        linked.ownership = ProjectOwnership::Linked;
+        linked.version = ProjectVersion::MicrosoftNetSdkWeb;
+        linked.output_type = OutputType::Exe;
+        linked.root_namespace = Some("Ford".to_owned());
+        linked.assembly_name = Some(String::new());
+        linked.target_frameworks = vec!["net48".to_owned(), "net8.0".to_owned()];
+        linked.test_framework = TestFramework::XUnit;
+        linked.packages = vec![
+            Package::new("Unity", "4.0.1", false, "ThirdParty"),
+            Package { missing_version: true, framework_mismatch: true, ..Package::new("Serilog", "", true, "") },
+        ];
+
+        let mut sln = Solution::default();
+        sln.file_info.path = PathBuf::from("/temp/car/car.sln");
+        sln.version = VisualStudioVersion::VS2019;
+        sln.minimum_version = Some("10.0.40219.1".to_owned());
+        sln.projects = vec![linked, make_project("/temp/car/bmw.csproj")];
+
+        let mut stray = make_project("/temp/stray.csproj");
+        stray.version = ProjectVersion::OldStyle;
+        stray.project_guid = Some("5a7e8ccb-0f32-4e23-9a3c-0e8d65a4d76b".to_owned());
+
+        Analysis {
+            root_path: PathBuf::from("/temp"),
+            solution_directories: vec![SolutionDirectory {
+                directory: PathBuf::from("/temp/car"),
+                solutions: vec![sln],
+                ..Default::default()
+            }],
+            unassociated_projects: vec![stray],
+            case_sensitive_paths: true,
+            ..Default::default()
+        }
+    }
+
+    fn assert_projects_equal(actual: &Project, expected: &Project) {
+        assert_eq!(actual.file_info.path, expected.file_info.path);
+        assert_eq!(actual.ownership, expected.ownership);
+        assert_eq!(actual.version, expected.version);
+        assert_eq!(actual.output_type, expected.output_type);
+        assert_eq!(actual.root_namespace, expected.root_namespace);
+        assert_eq!(actual.assembly_name, expected.assembly_name);
+        assert_eq!(actual.project_guid, expected.project_guid);
+        assert_eq!(actual.target_frameworks, expected.target_frameworks);
+        assert_eq!(actual.test_framework, expected.test_framework);
+        assert_eq!(actual.packages, expected.packages);
+    }
+
+    #[test]
+    pub fn analysis_round_trips() {
+        let analysis = make_analysis();
+        let decoded = decode_analysis(&encode_analysis(&analysis)).unwrap();
+
+        assert_eq!(decoded.root_path, analysis.root_path);
+        assert_eq!(decoded.case_sensitive_paths, analysis.case_sensitive_paths);
+        assert_eq!(decoded.solution_directories.len(), 1);
+        assert_eq!(decoded.solution_directories[0].directory, analysis.solution_directories[0].directory);
+
+        let (sln, expected_sln) = (&decoded.solution_directories[0].solutions[0], &analysis.solution_directories[0].solutions[0]);
+        assert_eq!(decoded.solution_directories[0].solutions.len(), 1);
+        assert_eq!(sln.file_info.path, expected_sln.file_info.path);
+        assert_eq!(sln.version, expected_sln.version);
+        assert_eq!(sln.minimum_version, expected_sln.minimum_version);
+        assert_eq!(sln.projects.len(), 2);
+        for (actual, expected) in sln.projects.iter().zip(&expected_sln.projects) {
+            assert_projects_equal(actual, expected);
+        }
+
+        assert_eq!(decoded.unassociated_projects.len(), 1);
+        assert_projects_equal(&decoded.unassociated_projects[0], &analysis.unassociated_projects[0]);
+    }
+
+    #[test]
+    pub fn empty_analysis_round_trips() {
+        let bytes = encode_analysis(&Analysis::default());
+        assert!(bytes.is_empty());
+        let decoded = decode_analysis(&bytes).unwrap();
+        assert!(decoded.solution_directories.is_empty());
+        assert!(!decoded.case_sensitive_paths);
+    }
+
+    #[test]
+    pub fn unknown_enum_values_decode_as_the_default() {
+        let msg = pb::Project { version: 42, ..Default::default() };
+        let decoded = decode_analysis(&pb::Analysis { unassociated_projects: vec![msg], ..Default::default() }.encode_to_vec()).unwrap();
+        assert_eq!(decoded.unassociated_projects[0].version, ProjectVersion::Unknown);
+    }
+
+    #[test]
+    pub fn decode_rejects_truncated_messages() {
+        let bytes = encode_analysis(&make_analysis());
+        match decode_analysis(&bytes[..bytes.len() - 1]) {
+            Err(DnLibError::ProtobufError(_)) => {}
+            _ => panic!("Expected a ProtobufError"),
+        }
+    }
+
+    #[test]
+    pub fn write_analysis_protobuf_writes_a_decodable_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_analysis_protobuf(dir.path().join("out"), &make_analysis()).unwrap();
+        assert_eq!(path, dir.path().join("out").join(PROTOBUF_FILENAME));
+
+        let decoded = decode_analysis(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(decoded.unassociated_projects.len(), 1);
+    }
+}
@@ -0,0 +1,29 @@
+use std::time::Duration;
+
+/// Calculates the number of files processed per second. If no measurable time
+/// has elapsed there is no meaningful rate, and 0 is returned.
+pub fn throughput(num_files: usize, elapsed: Duration) -> f64 {
+    let secs = elapsed.as_secs_f64();
+    if secs > 0.0 {
+        num_files as f64 / secs
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn throughput_is_files_per_second() {
+        assert_eq!(throughput(100, Duration::from_secs(4)), 25.0);
+        assert_eq!(throughput(3, Duration::from_millis(500)), 6.0);
+        assert_eq!(throughput(0, Duration::from_secs(1)), 0.0);
+    }
+
+    #[test]
+    pub fn throughput_with_no_elapsed_time_is_zero() {
+        assert_eq!(throughput(100, Duration::from_secs(0)), 0.0);
+    }
+}
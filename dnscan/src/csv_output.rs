@@ -1,8 +1,13 @@
-use crate::errors::AnalysisResult;
+use crate::errors::{AnalysisError, AnalysisResult};
 use csv;
 use dnlib::prelude::*;
-use log::info;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::{info, warn};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 
 fn bool_to_str(b: bool) -> &'static str {
@@ -13,6 +18,25 @@ fn bool_to_str(b: bool) -> &'static str {
     }
 }
 
+/// The columns that hold the path of a file or directory on disk, and which are
+/// converted to `file://` URIs if `file_uris` is set. HintPaths are relative to
+/// their project, so they are left alone.
+const PATH_COLUMNS: &[&str] = &[
+    "Path", "SlnDirectory", "SlnPath", "ProjPath", "ChildProjPath", "SourceProjPath", "MentionedPath", "OnDiskPath", "OutputPath",
+];
+
+/// The columns that hold package classes, which are renamed by the `class_aliases`.
+const CLASS_COLUMNS: &[&str] = &["PkgClass", "GroupName"];
+
+/// Compares two cell values. If both look like numbers they are compared
+/// numerically (so that "9" sorts before "10"), otherwise as strings.
+fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => a.cmp(b),
+    }
+}
+
 fn ensure_dir<P: AsRef<Path>>(dir: P, filename: &str) -> AnalysisResult<PathBuf> {
     let mut path = dir.as_ref().to_path_buf();
     fs::create_dir_all(&path)?;
@@ -20,11 +44,205 @@ fn ensure_dir<P: AsRef<Path>>(dir: P, filename: &str) -> AnalysisResult<PathBuf>
     Ok(path)
 }
 
-pub fn write_solutions<P: AsRef<Path>>(dir: P, analysis: &Analysis) -> AnalysisResult<()> {
-    let path = ensure_dir(dir, "solutions.csv")?;
-    let mut wtr = csv::Writer::from_path(&path)?;
+/// The contents of a CSV file, built up in memory before being written. This
+/// allows us to apply limits (and other transformations) to the rows in one
+/// place rather than in every writer.
+#[derive(Debug, Default)]
+struct CsvTable {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl CsvTable {
+    fn new(header: &[&str]) -> Self {
+        CsvTable {
+            header: header.iter().map(|&h| h.to_owned()).collect(),
+            rows: vec![],
+        }
+    }
+
+    fn push(&mut self, row: &[&str]) {
+        self.rows.push(row.iter().map(|&c| c.to_owned()).collect());
+    }
+
+    /// Sorts the rows by the named column. The sort is stable, so rows with equal
+    /// values stay in the order they were pushed. Returns false if there is no
+    /// such column, in which case the rows are left untouched.
+    fn sort_by(&mut self, column: &str, descending: bool) -> bool {
+        let idx = match self.header.iter().position(|h| h == column) {
+            Some(idx) => idx,
+            None => return false,
+        };
+
+        self.rows.sort_by(|a, b| {
+            let ord = compare_cells(&a[idx], &b[idx]);
+            if descending { ord.reverse() } else { ord }
+        });
+
+        true
+    }
+
+    /// Removes the named column from the header and every row. Writers that have
+    /// optional columns push them unconditionally and remove them here.
+    fn remove_column(&mut self, column: &str) {
+        if let Some(idx) = self.header.iter().position(|h| h == column) {
+            self.header.remove(idx);
+            for row in &mut self.rows {
+                row.remove(idx);
+            }
+        }
+    }
+
+    /// The indexes of the columns whose names are in `columns`.
+    fn column_indexes(&self, columns: &[&str]) -> Vec<usize> {
+        self.header.iter().enumerate()
+            .filter(|(_, h)| columns.contains(&h.as_str()))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// A row that is written in place of the rows that were dropped due to the
+    /// `max_csv_rows` limit. It has the same number of columns as the header,
+    /// otherwise the CSV writer would complain.
+    fn truncation_marker(&self, rows_written: usize) -> Vec<String> {
+        let mut marker = vec![String::new(); self.header.len()];
+        marker[0] = format!("TRUNCATED: showing {} of {} rows", rows_written, self.rows.len());
+        marker
+    }
+}
+
+/// Writes the table to `filename` in the output directory. If the table has more
+/// rows than the configured `max_csv_rows` then only the first rows are written,
+/// followed by a marker row. Rows are written in the order they were pushed, so
+/// truncation is as deterministic as the writer that built the table.
+///
+/// The CSV is built in memory first so that, if `only_write_changed` is set, an
+/// existing file with identical contents can be left alone. If `gzip_output` is
+/// set the file is compressed and ".gz" is appended to its name. If `file_uris` is
+/// set the `PATH_COLUMNS` are written as `file://` URIs. The `CLASS_COLUMNS` are
+/// renamed according to the `class_aliases`, so every file uses the same names.
+///
+/// If `append_csv` is set and the file already exists, the rows are appended to it
+/// without a header, provided the existing header matches the table's. The marker
+/// row is not written when appending, since later rows would follow it.
+fn write_table(configuration: &Configuration, filename: &str, table: &CsvTable) -> AnalysisResult<()> {
+    let path = if configuration.gzip_output {
+        ensure_dir(&configuration.output_directory, &format!("{}.gz", filename))?
+    } else {
+        ensure_dir(&configuration.output_directory, filename)?
+    };
+    let mut wtr = csv::Writer::from_writer(vec![]);
+
+    let append = configuration.append_csv && fs::metadata(&path).map_or(false, |md| md.len() > 0);
+    if append {
+        check_existing_header(&path, &table.header)?;
+    } else {
+        wtr.write_record(&table.header)?;
+    }
+
+    let limit = configuration.max_csv_rows.unwrap_or(usize::MAX);
+    let uri_columns = if configuration.file_uris { table.column_indexes(PATH_COLUMNS) } else { vec![] };
+    let class_columns = if configuration.class_aliases.is_empty() { vec![] } else { table.column_indexes(CLASS_COLUMNS) };
+    for row in table.rows.iter().take(limit) {
+        if uri_columns.is_empty() && class_columns.is_empty() {
+            wtr.write_record(row)?;
+        } else {
+            wtr.write_record(row.iter().enumerate().map(|(idx, cell)| {
+                if cell.is_empty() {
+                    cell.to_owned()
+                } else if uri_columns.contains(&idx) {
+                    path_to_file_uri(cell)
+                } else if class_columns.contains(&idx) {
+                    configuration.package_class_alias(cell).to_owned()
+                } else {
+                    cell.to_owned()
+                }
+            }))?;
+        }
+    }
+
+    if table.rows.len() > limit {
+        if !configuration.append_csv {
+            wtr.write_record(table.truncation_marker(limit))?;
+        }
+        warn!("Truncated {:?} to {} of {} rows", path, limit, table.rows.len());
+    }
+
+    let mut data = wtr.into_inner().map_err(|e| e.into_error())?;
+    if configuration.gzip_output {
+        data = gzip(&data)?;
+    }
+
+    if append {
+        fs::OpenOptions::new().append(true).open(&path)?.write_all(&data)?;
+        info!("Appended to {:?}", path);
+        return Ok(());
+    }
+
+    if configuration.only_write_changed && is_unchanged(&path, &data) {
+        info!("Unchanged {:?}", path);
+        return Ok(());
+    }
+
+    fs::write(&path, &data)?;
+    info!("Wrote {:?}", path);
+    Ok(())
+}
+
+fn gzip(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+/// Checks that the existing CSV file at `path` has the given header, so that rows
+/// appended to it line up with its columns.
+fn check_existing_header(path: &Path, header: &[String]) -> AnalysisResult<()> {
+    let mut rdr = csv::ReaderBuilder::new().has_headers(false).from_path(path)?;
+    let mut existing = csv::StringRecord::new();
+    if !rdr.read_record(&mut existing)? || existing.iter().eq(header.iter().map(String::as_str)) {
+        return Ok(());
+    }
+
+    Err(AnalysisError::CsvHeaderMismatch(path.to_owned()))
+}
+
+/// Returns true if the file at `path` exists and has exactly the contents `data`.
+fn is_unchanged(path: &Path, data: &[u8]) -> bool {
+    match fs::read(path) {
+        Ok(existing) => existing == data,
+        Err(_) => false,
+    }
+}
+
+pub fn write_paths(configuration: &Configuration, paths: &PathsToAnalyze) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["Path", "Kind"]);
+
+    for (kind, files) in &[
+        ("sln", &paths.sln_files),
+        ("csproj", &paths.csproj_files),
+        ("other", &paths.other_files),
+    ] {
+        for path in files.iter() {
+            table.push(&[path.as_str(), kind]);
+        }
+    }
+
+    write_table(configuration, "paths.csv", &table)
+}
 
-    wtr.write_record(&[
+pub fn write_manifest(configuration: &Configuration, reads: &[FileRead]) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["Path", "Kind", "ReadSuccessfully"]);
+
+    for read in reads {
+        table.push(&[read.path.as_str(), &read.kind(), bool_to_str(read.success)]);
+    }
+
+    write_table(configuration, "manifest.csv", &table)
+}
+
+pub fn write_solutions(configuration: &Configuration, analysis: &Analysis) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&[
         "SlnDirectory",
         "GitBranch",
         "GitSha",
@@ -34,17 +252,29 @@ pub fn write_solutions<P: AsRef<Path>>(dir: P, analysis: &Analysis) -> AnalysisR
         "GitAuthorEmail",
         "GitRemoteName",
         "GitRemoteUrl",
+        "GitIsSubmodule",
+        "SlnDirectoryHasCIConfig",
+        "SlnDirectoryPinnedSdkVersion",
+        "SlnDirectoryHasEditorConfig",
+        "SlnDirectoryHasReadme",
         "SlnPath",
         "SlnFile",
+        "SlnDomain",
         "SlnIsValidUTF8",
+        "SlnLineEnding",
+        "SlnLastModified",
         "SlnVersion",
+        "SlnMinimumVersion",
+        "SlnVersionProjectMismatch",
+        "MigrationProgress",
+        "PartiallyMigrated",
         "LinkedProjectsCount",
         "OrphanedProjectsCount",
-    ])?;
+    ]);
 
     for sd in &analysis.solution_directories {
         for sln in &sd.solutions {
-            wtr.write_record(&[
+            table.push(&[
                 // sln columns
                 sd.directory.as_str(),
                 sd.git_info.as_ref().map_or("", |git_info| &git_info.branch),
@@ -55,64 +285,112 @@ pub fn write_solutions<P: AsRef<Path>>(dir: P, analysis: &Analysis) -> AnalysisR
                 sd.git_info.as_ref().map_or("", |git_info| &git_info.author_email),
                 sd.git_info.as_ref().map_or("", |git_info| &git_info.remote_name),
                 sd.git_info.as_ref().map_or("", |git_info| &git_info.remote_url),
+                sd.git_info.as_ref().map_or("", |git_info| bool_to_str(git_info.is_submodule)),
+                bool_to_str(sd.has_ci_config),
+                sd.pinned_sdk_version.as_deref().unwrap_or_default(),
+                bool_to_str(sd.has_editorconfig),
+                bool_to_str(sd.has_readme),
                 sln.file_info.path_as_str(),
                 sln.file_info.filename_as_str(),
+                sln.domain(),
                 bool_to_str(sln.file_info.is_valid_utf8),
+                sln.file_info.line_ending.as_ref(),
+                &sln.file_info.modified_as_string(),
                 sln.version.as_ref(),
+                sln.minimum_version.as_deref().unwrap_or_default(),
+                bool_to_str(sln.version_project_mismatch()),
+                &format!("{:.2}", sln.migration_progress()),
+                bool_to_str(sln.partially_migrated()),
                 // project columns
                 &sln.linked_projects().count().to_string(),
                 &sln.orphaned_projects().count().to_string(),
-            ])?;
+            ]);
         }
     }
 
-    wtr.flush()?;
-    info!("Wrote {:?}", path);
-    Ok(())
+    if !configuration.include_modified_times {
+        table.remove_column("SlnLastModified");
+    }
+
+    write_table(configuration, "solutions.csv", &table)
 }
 
-pub fn write_solutions_to_projects<P: AsRef<Path>>(
-    dir: P,
+pub fn write_solutions_to_projects(
+    configuration: &Configuration,
     analysis: &Analysis,
 ) -> AnalysisResult<()> {
-    let path = ensure_dir(dir, "solutions_to_projects.csv")?;
-    let mut wtr = csv::Writer::from_path(&path)?;
-
-    wtr.write_record(&[
+    let mut table = CsvTable::new(&[
         "SlnDirectory",
         "SlnPath",
         "SlnFile",
         "SlnIsValidUTF8",
         "SlnVersion",
         "ProjOwnership",
+        "ProjIsExternal",
         "ProjPath",
         "ProjFile",
         "ProjIsValidUTF8",
+        "ProjLineEnding",
+        "ProjLastModified",
         "ProjVersion",
         "ProjOutputType",
+        "ProjRootNamespace",
+        "ProjAssemblyName",
+        "ProjFolderNameMatchesAssembly",
+        "ProjStableId",
+        "ProjCompany",
+        "ProjAuthors",
+        "ProjCopyright",
+        "ProjUserSecretsId",
         "ProjXmlDoc",
         "ProjTTFile",
         "ProjEmbeddedDebugging",
+        "ProjDebugType",
+        "ProjProducesPortablePdb",
         "ProjLinkedSolutionInfo",
         "ProjAutoGenerateBindingRedirects",
+        "ProjPlatformTarget",
+        "ProjPrefer32Bit",
+        "ProjDeterministic",
+        "ProjAllowsUnsafeCode",
+        "ProjCoveredByEditorConfig",
+        "ProjLangVersion",
+        "ProjDefineConstants",
+        "ProjSuppressedAnalyzerRules",
         "ProjTargetFrameworks",
+        "ProjFrameworkIsVariable",
+        "ProjResolvedTargetFrameworks",
+        "ProjImportedTargets",
+        "ProjHasPackagesConfigImports",
+        "ProjCentralPackageManagement",
+        "ProjCentralPackageManagementOptOut",
+        "ProjHasWildcardIncludes",
         "ProjTestFramework",
+        "ProjTestProjectMisconfigured",
         "ProjUsesSpecflow",
+        "ProjUsesSourceLink",
         "ProjPackagesCount",
         "ProjAssembliesCount",
         "ProjChildCount",
+        "ProjParentCount",
+        "ProjTransitiveChildCount",
+        "ProjTransitiveParentCount",
+        "ProjGraphRole",
         "ProjWebConfig",
         "ProjAppConfig",
+        "ProjBindingRedirectCount",
         "ProjAppSettingsJson",
         "ProjPackageJson",
         "ProjPackagesConfig",
         "ProjProjectJson",
-    ])?;
+        "ProjUsesObsoleteProjectJson",
+        "ProjHasGlobalUsings",
+    ]);
 
     for sd in &analysis.solution_directories {
         for sln in &sd.solutions {
             for proj in &sln.projects {
-                wtr.write_record(&[
+                table.push(&[
                     // sln columns
                     sd.directory.as_str(),
                     sln.file_info.path_as_str(),
@@ -121,46 +399,88 @@ pub fn write_solutions_to_projects<P: AsRef<Path>>(
                     sln.version.as_ref(),
                     // project columns
                     proj.ownership.as_ref(),
+                    bool_to_str(proj.is_external),
                     proj.file_info.path_as_str(),
                     proj.file_info.filename_as_str(),
                     bool_to_str(proj.file_info.is_valid_utf8),
+                    proj.file_info.line_ending.as_ref(),
+                    &proj.file_info.modified_as_string(),
                     proj.version.as_ref(),
                     proj.output_type.as_ref(),
+                    proj.effective_root_namespace(),
+                    proj.effective_assembly_name(),
+                    bool_to_str(proj.folder_name_matches_assembly()),
+                    &proj.stable_id(),
+                    proj.company.as_deref().unwrap_or_default(),
+                    proj.authors.as_deref().unwrap_or_default(),
+                    proj.copyright.as_deref().unwrap_or_default(),
+                    proj.user_secrets_id.as_deref().unwrap_or_default(),
                     proj.xml_doc.as_ref(),
                     bool_to_str(proj.tt_file),
                     bool_to_str(proj.embedded_debugging),
+                    proj.debug_type.as_deref().unwrap_or_default(),
+                    bool_to_str(proj.produces_portable_pdb),
                     bool_to_str(proj.linked_solution_info),
                     bool_to_str(proj.auto_generate_binding_redirects),
+                    proj.platform_target.as_deref().unwrap_or_default(),
+                    bool_to_str(proj.prefer_32bit),
+                    proj.deterministic.map(bool_to_str).unwrap_or_default(),
+                    bool_to_str(proj.allows_unsafe_code),
+                    bool_to_str(proj.covered_by_editorconfig),
+                    proj.lang_version.as_deref().unwrap_or_default(),
+                    &proj.define_constants.join(","),
+                    &proj.suppressed_analyzer_rules.join(","),
                     &proj.target_frameworks.join(","),
+                    bool_to_str(proj.framework_is_variable),
+                    &proj.resolved_target_frameworks.join(","),
+                    &proj.imported_targets.join(","),
+                    bool_to_str(proj.has_packages_config_imports),
+                    bool_to_str(proj.central_package_management),
+                    bool_to_str(proj.central_package_management_optout),
+                    bool_to_str(proj.has_wildcard_includes),
                     proj.test_framework.as_ref(),
+                    bool_to_str(proj.test_project_misconfigured),
                     bool_to_str(proj.uses_specflow),
+                    bool_to_str(proj.uses_source_link),
                     &proj.packages.len().to_string(),
                     &proj.referenced_assemblies.len().to_string(),
                     &proj.get_child_projects(sln).len().to_string(),
+                    &proj.get_parent_projects(sln).len().to_string(),
+                    &proj.get_transitive_child_projects(sln).len().to_string(),
+                    &proj.get_transitive_parent_projects(sln).len().to_string(),
+                    proj.graph_role(sln).as_ref(),
                     proj.web_config.as_ref(),
                     proj.app_config.as_ref(),
+                    &proj.binding_redirect_count.to_string(),
                     proj.app_settings_json.as_ref(),
                     proj.package_json.as_ref(),
                     proj.packages_config.as_ref(),
                     proj.project_json.as_ref(),
-                ])?;
+                    bool_to_str(proj.uses_obsolete_project_json),
+                    bool_to_str(proj.has_global_usings),
+                ]);
             }
         }
     }
 
-    wtr.flush()?;
-    info!("Wrote {:?}", path);
-    Ok(())
+    if !configuration.include_modified_times {
+        table.remove_column("ProjLastModified");
+    }
+
+    if let Some(column) = &configuration.sort_by {
+        if !table.sort_by(column, configuration.sort_descending) {
+            warn!("Cannot sort solutions_to_projects.csv by unknown column {:?}, leaving unsorted", column);
+        }
+    }
+
+    write_table(configuration, "solutions_to_projects.csv", &table)
 }
 
-pub fn write_projects_to_packages<P: AsRef<Path>>(
-    dir: P,
+pub fn write_projects_to_packages(
+    configuration: &Configuration,
     analysis: &Analysis,
 ) -> AnalysisResult<()> {
-    let path = ensure_dir(dir, "projects_to_packages.csv")?;
-    let mut wtr = csv::Writer::from_path(&path)?;
-
-    wtr.write_record(&[
+    let mut table = CsvTable::new(&[
         "SlnDirectory",
         "SlnPath",
         "SlnFile",
@@ -178,13 +498,14 @@ pub fn write_projects_to_packages<P: AsRef<Path>>(
         "PkgVersion",
         "PkgIsDevelopment",
         "PkgIsPreview",
-    ])?;
+        "PkgFrameworkMismatch",
+    ]);
 
     for sd in &analysis.solution_directories {
         for sln in &sd.solutions {
             for proj in &sln.projects {
                 for pkg in &proj.packages {
-                    wtr.write_record(&[
+                    table.push(&[
                         // sln columns
                         sd.directory.as_str(),
                         sln.file_info.path_as_str(),
@@ -205,28 +526,666 @@ pub fn write_projects_to_packages<P: AsRef<Path>>(
                         &pkg.version,
                         bool_to_str(pkg.development),
                         bool_to_str(pkg.is_preview()),
-                    ])?;
+                        bool_to_str(pkg.framework_mismatch),
+                    ]);
                 }
             }
         }
     }
 
-    wtr.flush()?;
-    info!("Wrote {:?}", path);
-    Ok(())
+    write_table(configuration, "projects_to_packages.csv", &table)
+}
+
+/// Writes the packages that apply to each target framework of the multi-targeted
+/// projects. Single-targeted projects are not included, see projects_to_packages.csv.
+pub fn write_packages_by_framework(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["ProjPath", "ProjFile", "TargetFramework", "PkgName", "PkgClass", "PkgVersion"]);
+
+    for proj in analysis.projects() {
+        for (tf, pkg) in proj.packages_by_framework() {
+            table.push(&[
+                proj.file_info.path_as_str(),
+                proj.file_info.filename_as_str(),
+                tf,
+                &pkg.name,
+                &pkg.class,
+                &pkg.version,
+            ]);
+        }
+    }
+
+    write_table(configuration, "packages_by_framework.csv", &table)
+}
+
+/// Writes a project-by-package matrix, suitable for pivot tables. There is one row
+/// per project and one column per distinct package name, in sorted order. Each cell
+/// holds the version(s) of the package that the project references, or is blank.
+/// Large trees can reference hundreds of packages, so the number of package columns
+/// can be capped with `max_csv_columns`.
+pub fn write_package_matrix(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    // A project can be in several solutions, but should only appear once.
+    let projects = analysis.solution_directories.iter()
+        .flat_map(|sd| sd.solutions.iter())
+        .flat_map(|sln| sln.projects.iter())
+        .chain(analysis.unassociated_projects.iter())
+        .map(|proj| (proj.file_info.path_as_str(), proj))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut package_names = projects.values()
+        .flat_map(|proj| proj.packages.iter().map(|pkg| pkg.name.as_str()))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    if let Some(limit) = configuration.max_csv_columns {
+        if package_names.len() > limit {
+            warn!("Truncated package_matrix.csv to {} of {} package columns", limit, package_names.len());
+            package_names.truncate(limit);
+        }
+    }
+
+    let mut header = vec!["ProjPath", "ProjFile"];
+    header.extend(package_names.iter());
+    let mut table = CsvTable::new(&header);
+
+    for (path, proj) in projects {
+        let mut row = vec![path.to_owned(), proj.file_info.filename_as_str().to_owned()];
+        for name in &package_names {
+            let versions = proj.packages.iter()
+                .filter(|pkg| pkg.name == *name)
+                .map(|pkg| pkg.version.as_str())
+                .collect::<Vec<_>>();
+            row.push(versions.join(","));
+        }
+        table.rows.push(row);
+    }
+
+    write_table(configuration, "package_matrix.csv", &table)
+}
+
+pub fn write_transitively_required_projects(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&[
+        "SlnPath",
+        "SlnFile",
+        "ProjOwnership",
+        "ProjPath",
+        "ProjFile",
+    ]);
+
+    for (sln, proj) in analysis.transitively_required_projects() {
+        table.push(&[
+            sln.file_info.path_as_str(),
+            sln.file_info.filename_as_str(),
+            proj.ownership.as_ref(),
+            proj.file_info.path_as_str(),
+            proj.file_info.filename_as_str(),
+        ]);
+    }
+
+    write_table(configuration, "transitively_required_projects.csv", &table)
+}
+
+pub fn write_package_downgrades(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&[
+        "ProjPath",
+        "ProjFile",
+        "ChildProjPath",
+        "ChildProjFile",
+        "PkgName",
+        "PkgVersion",
+        "ChildPkgVersion",
+    ]);
+
+    for downgrade in analysis.potential_package_downgrades() {
+        table.push(&[
+            downgrade.parent.file_info.path_as_str(),
+            downgrade.parent.file_info.filename_as_str(),
+            downgrade.child.file_info.path_as_str(),
+            downgrade.child.file_info.filename_as_str(),
+            downgrade.package_name,
+            downgrade.parent_version,
+            downgrade.child_version,
+        ]);
+    }
+
+    write_table(configuration, "package_downgrades.csv", &table)
+}
+
+pub fn write_package_consolidation(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["PkgName", "PkgMaxVersion", "ProjectsCount", "LaggingProjectsCount"]);
+
+    for consolidation in analysis.package_consolidation_report() {
+        table.push(&[
+            consolidation.package_name,
+            consolidation.max_version,
+            &consolidation.num_projects.to_string(),
+            &consolidation.num_laggards.to_string(),
+        ]);
+    }
+
+    write_table(configuration, "package_consolidation.csv", &table)
+}
+
+pub fn write_test_frameworks(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["ProjTestFramework", "ProjectsCount"]);
+
+    for (test_framework, count) in analysis.test_framework_histogram() {
+        table.push(&[test_framework.as_ref(), &count.to_string()]);
+    }
+
+    write_table(configuration, "test_frameworks.csv", &table)
+}
+
+pub fn write_namespace_collisions(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["RootNamespace", "ProjPath", "ProjFile"]);
+
+    for (namespace, projects) in analysis.root_namespace_collisions() {
+        for proj in projects {
+            table.push(&[
+                &namespace,
+                proj.file_info.path_as_str(),
+                proj.file_info.filename_as_str(),
+            ]);
+        }
+    }
+
+    write_table(configuration, "namespace_collisions.csv", &table)
+}
+
+pub fn write_folder_name_mismatches(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["ProjPath", "ProjFile", "ProjFolder", "ProjAssemblyName"]);
+
+    for proj in analysis.projects().filter(|proj| !proj.folder_name_matches_assembly()) {
+        table.push(&[
+            proj.file_info.path_as_str(),
+            proj.file_info.filename_as_str(),
+            proj.file_info.path.parent().map_or("", |dir| dir.filename_as_str()),
+            proj.effective_assembly_name(),
+        ]);
+    }
+
+    write_table(configuration, "folder_name_mismatches.csv", &table)
+}
+
+pub fn write_lang_version_issues(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["SlnPath", "SlnFile", "SlnLangVersions"]);
+
+    for sln in analysis.solution_directories.iter().flat_map(|sd| sd.solutions.iter()) {
+        if sln.lang_version_consistent() {
+            continue;
+        }
+
+        let versions = sln.distinct_lang_versions().iter()
+            .map(|lv| lv.unwrap_or("unspecified"))
+            .collect::<Vec<_>>();
+
+        table.push(&[
+            sln.file_info.path_as_str(),
+            sln.file_info.filename_as_str(),
+            &versions.join(","),
+        ]);
+    }
+
+    write_table(configuration, "lang_version_issues.csv", &table)
+}
+
+pub fn write_solution_package_classes(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["SlnPath", "SlnFile", "PackageClass", "Percentage"]);
+
+    for sln in analysis.solution_directories.iter().flat_map(|sd| sd.solutions.iter()) {
+        let mut breakdown = sln.package_class_breakdown().into_iter().collect::<Vec<_>>();
+        breakdown.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (class, percentage) in breakdown {
+            table.push(&[
+                sln.file_info.path_as_str(),
+                sln.file_info.filename_as_str(),
+                &class,
+                &format!("{:.2}", percentage),
+            ]);
+        }
+    }
+
+    write_table(configuration, "solution_package_classes.csv", &table)
+}
+
+/// Writes the findings of all the individual checks to one file, with a consistent
+/// set of columns, so that everything that needs attention can be seen in one place.
+pub fn write_diagnostics(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["Category", "Severity", "SlnPath", "ProjPath", "Line", "Detail"]);
+
+    for diagnostic in filter_by_severity(collect_diagnostics(analysis), configuration.min_severity) {
+        table.push(&[
+            diagnostic.category.as_ref(),
+            diagnostic.severity.as_ref(),
+            diagnostic.solution.as_deref().unwrap_or_default(),
+            diagnostic.project.as_deref().unwrap_or_default(),
+            &diagnostic.line.map(|l| l.to_string()).unwrap_or_default(),
+            &diagnostic.detail,
+        ]);
+    }
+
+    write_table(configuration, "diagnostics.csv", &table)
+}
+
+pub fn write_directory_packages(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["SlnDirectory", "PkgName", "PkgVersion", "PkgClass", "ProjectCount"]);
+
+    for sd in &analysis.solution_directories {
+        for (pkg, count) in sd.aggregate_packages() {
+            table.push(&[
+                sd.directory.as_str(),
+                &pkg.name,
+                &pkg.version,
+                &pkg.class,
+                &count.to_string(),
+            ]);
+        }
+    }
+
+    write_table(configuration, "directory_packages.csv", &table)
+}
+
+pub fn write_solution_directories(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&[
+        "SlnDirectory",
+        "GitBranch",
+        "GitSha",
+        "SolutionsCount",
+        "LinkedProjectsCount",
+        "OrphanedProjectsCount",
+        "DistinctPackagesCount",
+    ]);
+
+    for sd in &analysis.solution_directories {
+        table.push(&[
+            sd.directory.as_str(),
+            sd.git_info.as_ref().map_or("", |git_info| &git_info.branch),
+            sd.git_info.as_ref().map_or("", |git_info| &git_info.sha),
+            &sd.num_solutions().to_string(),
+            &sd.num_linked_projects().to_string(),
+            &sd.num_orphaned_projects().to_string(),
+            &sd.aggregate_packages().len().to_string(),
+        ]);
+    }
+
+    write_table(configuration, "solution_directories.csv", &table)
+}
+
+pub fn write_group_usage(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["GroupName", "GroupRegex", "PackageCount"]);
+
+    for (pkg_group, count) in analysis.package_group_usage(configuration) {
+        table.push(&[
+            &pkg_group.name,
+            &pkg_group.pattern,
+            &count.to_string(),
+        ]);
+    }
+
+    // The packages that fell through to the default class, so the totals add up.
+    table.push(&[
+        &configuration.default_package_class,
+        "",
+        &analysis.unclassified_packages(configuration).len().to_string(),
+    ]);
+
+    write_table(configuration, "group_usage.csv", &table)
+}
+
+pub fn write_package_shadows(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["ProjPath", "ProjFile", "PkgName", "PkgVersion", "SourceProjPath"]);
+
+    for (proj, pkg, source) in analysis.package_shadowing_projects() {
+        table.push(&[
+            proj.file_info.path_as_str(),
+            proj.file_info.filename_as_str(),
+            &pkg.name,
+            &pkg.version,
+            source.file_info.path_as_str(),
+        ]);
+    }
+
+    write_table(configuration, "package_shadows.csv", &table)
+}
+
+pub fn write_available_updates(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["ProjPath", "ProjFile", "PkgName", "PkgVersion", "PkgLatestVersion"]);
+
+    for (proj, pkg) in available_updates(analysis) {
+        table.push(&[
+            proj.file_info.path_as_str(),
+            proj.file_info.filename_as_str(),
+            &pkg.name,
+            &pkg.version,
+            pkg.update_available.as_deref().unwrap_or_default(),
+        ]);
+    }
+
+    write_table(configuration, "available_updates.csv", &table)
+}
+
+pub fn write_analyzer_rules(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["ProjPath", "ProjFile", "Rule", "Severity"]);
+
+    for proj in analysis.projects() {
+        for (rule, severity) in &proj.analyzer_rule_severities {
+            table.push(&[
+                proj.file_info.path_as_str(),
+                proj.file_info.filename_as_str(),
+                rule,
+                severity,
+            ]);
+        }
+    }
+
+    write_table(configuration, "analyzer_rules.csv", &table)
+}
+
+pub fn write_fragile_references(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["ProjPath", "ProjFile", "HintPath"]);
+
+    for proj in analysis.projects() {
+        for hint_path in &proj.fragile_reference_hint_paths {
+            table.push(&[
+                proj.file_info.path_as_str(),
+                proj.file_info.filename_as_str(),
+                hint_path,
+            ]);
+        }
+    }
+
+    write_table(configuration, "fragile_references.csv", &table)
+}
+
+pub fn write_missing_projects(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["SlnPath", "SlnFile", "ProjPath"]);
+
+    for (sln, path) in analysis.missing_projects() {
+        table.push(&[
+            sln.file_info.path_as_str(),
+            sln.file_info.filename_as_str(),
+            path.as_str(),
+        ]);
+    }
+
+    write_table(configuration, "missing_projects.csv", &table)
+}
+
+pub fn write_project_json_projects(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["ProjPath", "ProjFile", "ProjectJson"]);
+
+    for proj in analysis.obsolete_project_json_projects() {
+        table.push(&[
+            proj.file_info.path_as_str(),
+            proj.file_info.filename_as_str(),
+            proj.project_json.as_ref(),
+        ]);
+    }
+
+    write_table(configuration, "project_json_projects.csv", &table)
+}
+
+pub fn write_case_mismatches(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["SlnPath", "SlnFile", "MentionedPath", "OnDiskPath"]);
+
+    for (sln, mentioned, on_disk) in analysis.case_mismatches() {
+        table.push(&[
+            sln.file_info.path_as_str(),
+            sln.file_info.filename_as_str(),
+            mentioned.as_str(),
+            on_disk.as_str(),
+        ]);
+    }
+
+    write_table(configuration, "case_mismatches.csv", &table)
+}
+
+pub fn write_shared_output_paths(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["OutputPath", "ProjPath", "ProjFile"]);
+
+    for (output_path, projects) in analysis.shared_output_paths() {
+        for proj in projects {
+            table.push(&[
+                &output_path,
+                proj.file_info.path_as_str(),
+                proj.file_info.filename_as_str(),
+            ]);
+        }
+    }
+
+    write_table(configuration, "shared_output_paths.csv", &table)
+}
+
+pub fn write_low_coverage_solutions(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&[
+        "SlnPath",
+        "SlnFile",
+        "SlnLinkedProjectsCount",
+        "SlnOrphanedProjectsCount",
+        "SlnCoverage",
+    ]);
+
+    for sln in analysis.low_coverage_solutions(configuration.min_solution_coverage) {
+        table.push(&[
+            sln.file_info.path_as_str(),
+            sln.file_info.filename_as_str(),
+            &sln.linked_projects().count().to_string(),
+            &sln.orphaned_projects().count().to_string(),
+            &format!("{:.2}", sln.coverage_ratio()),
+        ]);
+    }
+
+    write_table(configuration, "low_coverage_solutions.csv", &table)
+}
+
+pub fn write_output_types(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["ProjOutputType", "ProjectsCount"]);
+
+    for (output_type, count) in analysis.output_type_histogram() {
+        table.push(&[output_type.as_ref(), &count.to_string()]);
+    }
+
+    write_table(configuration, "output_types.csv", &table)
+}
+
+pub fn write_orphaned_projects(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&["SlnDirectory", "SlnFile", "ProjPath", "OrphanReason"]);
+
+    for (sln_dir, sln, proj) in analysis.orphaned_projects() {
+        table.push(&[
+            sln_dir.directory.as_str(),
+            sln.file_info.filename_as_str(),
+            proj.file_info.path_as_str(),
+            proj.orphan_reason.as_ref().map(|r| r.as_ref()).unwrap_or_default(),
+        ]);
+    }
+
+    write_table(configuration, "orphaned_projects.csv", &table)
+}
+
+pub fn write_unreferenced_projects(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&[
+        "ProjOwnership",
+        "ProjPath",
+        "ProjFile",
+        "ProjIsValidUTF8",
+        "ProjVersion",
+        "ProjOutputType",
+    ]);
+
+    for proj in analysis.unreferenced_projects() {
+        table.push(&[
+            proj.ownership.as_ref(),
+            proj.file_info.path_as_str(),
+            proj.file_info.filename_as_str(),
+            bool_to_str(proj.file_info.is_valid_utf8),
+            proj.version.as_ref(),
+            proj.output_type.as_ref(),
+        ]);
+    }
+
+    write_table(configuration, "unreferenced_projects.csv", &table)
+}
+
+pub fn write_isolated_projects(
+    configuration: &Configuration,
+    analysis: &Analysis,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&[
+        "ProjOwnership",
+        "ProjPath",
+        "ProjFile",
+        "ProjVersion",
+        "ProjOutputType",
+        "ProjParentCount",
+    ]);
+
+    for proj in analysis.isolated_projects() {
+        table.push(&[
+            proj.ownership.as_ref(),
+            proj.file_info.path_as_str(),
+            proj.file_info.filename_as_str(),
+            proj.version.as_ref(),
+            proj.output_type.as_ref(),
+            &analysis.solution_directories.iter()
+                .flat_map(|sd| sd.solutions.iter())
+                .map(|sln| proj.get_parent_projects(sln).len())
+                .sum::<usize>()
+                .to_string(),
+        ]);
+    }
+
+    write_table(configuration, "isolated_projects.csv", &table)
 }
 
 use std::collections::HashSet;
 
-pub fn write_projects_to_child_projects<P: AsRef<Path>>(
-    dir: P,
+/// Writes just the project references that the transitive reduction found to be
+/// redundant, i.e. the rows of projects_to_child_projects.csv that are flagged
+/// "Redundant". Each one can be removed without changing what gets built.
+pub fn write_redundant_references(
+    configuration: &Configuration,
     analysis: &Analysis,
     redundant_project_relationships: &HashSet<(&Project, &Project)>,
 ) -> AnalysisResult<()> {
-    let path = ensure_dir(dir, "projects_to_child_projects.csv")?;
-    let mut wtr = csv::Writer::from_path(&path)?;
+    let mut table = CsvTable::new(&[
+        "SlnDirectory",
+        "SlnPath",
+        "SlnFile",
+        "ProjPath",
+        "ProjFile",
+        "ChildProjPath",
+        "ChildProjFile",
+    ]);
+
+    for sd in &analysis.solution_directories {
+        for sln in &sd.solutions {
+            for owning_proj in &sln.projects {
+                for child_proj in owning_proj.get_child_projects(sln) {
+                    if redundant_project_relationships.contains(&(owning_proj, child_proj)) {
+                        table.push(&[
+                            sd.directory.as_str(),
+                            sln.file_info.path_as_str(),
+                            sln.file_info.filename_as_str(),
+                            owning_proj.file_info.path_as_str(),
+                            owning_proj.file_info.filename_as_str(),
+                            child_proj.file_info.path_as_str(),
+                            child_proj.file_info.filename_as_str(),
+                        ]);
+                    }
+                }
+            }
+        }
+    }
+
+    write_table(configuration, "redundant_references.csv", &table)
+}
 
-    wtr.write_record(&[
+pub fn write_projects_to_child_projects(
+    configuration: &Configuration,
+    analysis: &Analysis,
+    redundant_project_relationships: &HashSet<(&Project, &Project)>,
+) -> AnalysisResult<()> {
+    let mut table = CsvTable::new(&[
         "SlnDirectory",
         "SlnPath",
         "SlnFile",
@@ -241,13 +1200,13 @@ pub fn write_projects_to_child_projects<P: AsRef<Path>>(
         "ChildProjVersion",
         "ChildProjOutputType",
         "IsRedundant",
-    ])?;
+    ]);
 
     for sd in &analysis.solution_directories {
         for sln in &sd.solutions {
             for owning_proj in &sln.projects {
                 for child_proj in &owning_proj.get_child_projects(sln) {
-                    wtr.write_record(&[
+                    table.push(&[
                         // sln columns
                         sd.directory.as_str(),
                         sln.file_info.path_as_str(),
@@ -269,13 +1228,628 @@ pub fn write_projects_to_child_projects<P: AsRef<Path>>(
                         } else {
                             ""
                         },
-                    ])?;
+                    ]);
                 }
             }
         }
     }
 
-    wtr.flush()?;
-    info!("Wrote {:?}", path);
-    Ok(())
+    write_table(configuration, "projects_to_child_projects.csv", &table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_configuration(output_directory: &Path, max_csv_rows: Option<usize>) -> Configuration {
+        Configuration {
+            output_directory: output_directory.to_owned(),
+            max_csv_rows,
+            ..Configuration::default()
+        }
+    }
+
+    fn make_table(num_rows: usize) -> CsvTable {
+        let mut table = CsvTable::new(&["Name", "Count"]);
+        for i in 0..num_rows {
+            table.push(&[&format!("row{}", i), &i.to_string()]);
+        }
+        table
+    }
+
+    fn read_records(path: &Path) -> Vec<csv::StringRecord> {
+        let mut rdr = csv::Reader::from_path(path).unwrap();
+        rdr.records().map(|r| r.unwrap()).collect()
+    }
+
+    fn column(table: &CsvTable, idx: usize) -> Vec<&str> {
+        table.rows.iter().map(|r| r[idx].as_str()).collect()
+    }
+
+    #[test]
+    pub fn sort_by_numeric_column() {
+        let mut table = CsvTable::new(&["Name", "Count"]);
+        table.push(&["a", "9"]);
+        table.push(&["b", "10"]);
+        table.push(&["c", "2"]);
+
+        assert!(table.sort_by("Count", false));
+        assert_eq!(column(&table, 1), vec!["2", "9", "10"]);
+
+        assert!(table.sort_by("Count", true));
+        assert_eq!(column(&table, 1), vec!["10", "9", "2"]);
+    }
+
+    #[test]
+    pub fn sort_by_string_column() {
+        let mut table = CsvTable::new(&["Name", "Count"]);
+        table.push(&["pear", "1"]);
+        table.push(&["apple", "2"]);
+        table.push(&["fig", "3"]);
+
+        assert!(table.sort_by("Name", false));
+        assert_eq!(column(&table, 0), vec!["apple", "fig", "pear"]);
+
+        assert!(table.sort_by("Name", true));
+        assert_eq!(column(&table, 0), vec!["pear", "fig", "apple"]);
+    }
+
+    #[test]
+    pub fn sort_by_unknown_column_leaves_rows_untouched() {
+        let mut table = make_table(3);
+        table.rows.reverse();
+
+        assert!(!table.sort_by("NoSuchColumn", false));
+        assert_eq!(column(&table, 0), vec!["row2", "row1", "row0"]);
+    }
+
+    #[test]
+    pub fn write_table_converts_path_columns_to_uris() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut configuration = make_configuration(dir.path(), None);
+        configuration.file_uris = true;
+
+        let mut table = CsvTable::new(&["SlnPath", "HintPath", "ProjPath"]);
+        table.push(&["/src/My App.sln", r"..\lib\a.dll", ""]);
+        write_table(&configuration, "test.csv", &table).unwrap();
+
+        let records = read_records(&dir.path().join("test.csv"));
+        assert_eq!(records[0].iter().collect::<Vec<_>>(), vec!["file:///src/My%20App.sln", r"..\lib\a.dll", ""]);
+    }
+
+    #[test]
+    pub fn is_unchanged_compares_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.csv");
+        assert!(!is_unchanged(&path, b"a,b\n"));
+
+        fs::write(&path, b"a,b\n").unwrap();
+        assert!(is_unchanged(&path, b"a,b\n"));
+        assert!(!is_unchanged(&path, b"a,c\n"));
+        assert!(!is_unchanged(&path, b"a,b\n1,2\n"));
+    }
+
+    #[test]
+    pub fn write_table_only_changed_rewrites_a_changed_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut configuration = make_configuration(dir.path(), None);
+        configuration.only_write_changed = true;
+        let path = dir.path().join("test.csv");
+
+        write_table(&configuration, "test.csv", &make_table(5)).unwrap();
+        let original = fs::read(&path).unwrap();
+        assert!(is_unchanged(&path, &original));
+
+        write_table(&configuration, "test.csv", &make_table(5)).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), original);
+
+        write_table(&configuration, "test.csv", &make_table(6)).unwrap();
+        assert_eq!(read_records(&path).len(), 6);
+    }
+
+    #[test]
+    pub fn write_table_append_adds_rows_to_an_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut configuration = make_configuration(dir.path(), None);
+        configuration.append_csv = true;
+        let path = dir.path().join("test.csv");
+
+        write_table(&configuration, "test.csv", &make_table(3)).unwrap();
+        write_table(&configuration, "test.csv", &make_table(2)).unwrap();
+
+        let records = read_records(&path);
+        assert_eq!(records.len(), 5);
+        assert_eq!(&records[3][0], "row0");
+        assert_eq!(fs::read_to_string(&path).unwrap().matches("Name,Count").count(), 1);
+    }
+
+    #[test]
+    pub fn write_table_append_aggregates_two_analyses() {
+        let make_analysis = |path: &str, packages: &[&str]| {
+            let mut proj = Project::default();
+            proj.file_info.path = PathBuf::from(path);
+            proj.packages = packages.iter().map(|&name| {
+                let mut pkg = Package::new(name, "1.0.0", false, "");
+                pkg.update_available = Some("2.0.0".to_owned());
+                pkg
+            }).collect();
+            Analysis { unassociated_projects: vec![proj], ..Default::default() }
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut configuration = make_configuration(dir.path(), None);
+        configuration.append_csv = true;
+
+        write_available_updates(&configuration, &make_analysis("/first/a.csproj", &["Serilog", "Unity"])).unwrap();
+        write_available_updates(&configuration, &make_analysis("/second/b.csproj", &["Autofac"])).unwrap();
+
+        let records = read_records(&dir.path().join("available_updates.csv"));
+        assert_eq!(records.len(), 3);
+        assert_eq!(records.iter().map(|r| &r[2]).collect::<Vec<_>>(), vec!["Serilog", "Unity", "Autofac"]);
+    }
+
+    #[test]
+    pub fn write_table_append_rejects_a_different_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut configuration = make_configuration(dir.path(), None);
+        configuration.append_csv = true;
+        let path = dir.path().join("test.csv");
+
+        write_table(&configuration, "test.csv", &make_table(3)).unwrap();
+
+        let mut table = CsvTable::new(&["Name", "Total"]);
+        table.push(&["row9", "9"]);
+        let result = write_table(&configuration, "test.csv", &table);
+        assert!(matches!(result, Err(AnalysisError::CsvHeaderMismatch(ref p)) if *p == path));
+        assert_eq!(read_records(&path).len(), 3);
+    }
+
+    #[test]
+    pub fn write_table_append_rejects_a_file_from_another_writer() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut configuration = make_configuration(dir.path(), None);
+        configuration.append_csv = true;
+        let path = dir.path().join("available_updates.csv");
+        fs::write(&path, "ProjPath,ProjFile,PkgName,PkgVersion\n/temp/a.csproj,a.csproj,Unity,4.0.1\n").unwrap();
+
+        let result = write_available_updates(&configuration, &make_matrix_analysis());
+        assert!(matches!(result, Err(AnalysisError::CsvHeaderMismatch(ref p)) if *p == path));
+        assert_eq!(read_records(&path).len(), 1);
+    }
+
+    #[test]
+    pub fn write_table_append_does_not_write_the_truncation_marker() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut configuration = make_configuration(dir.path(), Some(2));
+        configuration.append_csv = true;
+
+        write_table(&configuration, "test.csv", &make_table(3)).unwrap();
+        write_table(&configuration, "test.csv", &make_table(3)).unwrap();
+
+        let records = read_records(&dir.path().join("test.csv"));
+        assert_eq!(records.iter().map(|r| &r[0]).collect::<Vec<_>>(), vec!["row0", "row1", "row0", "row1"]);
+    }
+
+    #[test]
+    pub fn write_paths_classifies_files() {
+        let input = crate::test_input_dir();
+        let root = input.path();
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("car.sln"), "").unwrap();
+        fs::write(root.join("ford.csproj"), "").unwrap();
+        fs::write(root.join("sub").join("toyota.csproj"), "").unwrap();
+        fs::write(root.join("sub").join("packages.config"), "").unwrap();
+        fs::write(root.join("readme.txt"), "").unwrap();
+
+        let paths = find_files(root, &[], &[], None).unwrap();
+        let output = tempfile::tempdir().unwrap();
+        let configuration = make_configuration(output.path(), None);
+        write_paths(&configuration, &paths).unwrap();
+
+        let mut records = read_records(&output.path().join("paths.csv"))
+            .iter()
+            .map(|r| {
+                let filename = Path::new(&r[0]).file_name().unwrap().to_string_lossy().into_owned();
+                (filename, r[1].to_owned())
+            })
+            .collect::<Vec<_>>();
+        records.sort();
+
+        let expected = vec![
+            ("car.sln", "sln"),
+            ("ford.csproj", "csproj"),
+            ("packages.config", "other"),
+            ("toyota.csproj", "csproj"),
+        ];
+        let expected = expected
+            .into_iter()
+            .map(|(f, k)| (f.to_owned(), k.to_owned()))
+            .collect::<Vec<_>>();
+        assert_eq!(records, expected);
+    }
+
+    #[test]
+    pub fn write_manifest_lists_every_file_read() {
+        let input = make_input_directory();
+        let output = tempfile::tempdir().unwrap();
+        let mut configuration = make_configuration(output.path(), None);
+        configuration.input_directory = input.path().to_owned();
+
+        let file_loader = RecordingFileLoader::new(DiskFileLoader);
+        let analysis = Analysis::new_with_file_loader(&configuration, file_loader.clone()).unwrap();
+        write_manifest(&configuration, &file_loader.reads()).unwrap();
+
+        let records = read_records(&output.path().join("manifest.csv"));
+        let expected = analysis.paths_analyzed.sln_files.iter()
+            .chain(analysis.paths_analyzed.csproj_files.iter())
+            .collect::<Vec<_>>();
+        assert!(!expected.is_empty());
+        for path in expected {
+            assert!(records.iter().any(|r| &r[0] == path.as_str() && &r[2] == "true"), "{:?} was not recorded", path);
+        }
+    }
+
+    #[test]
+    pub fn write_table_gzip_round_trips() {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut configuration = make_configuration(dir.path(), None);
+        configuration.gzip_output = true;
+        write_table(&configuration, "test.csv", &make_table(3)).unwrap();
+
+        assert!(!dir.path().join("test.csv").exists());
+        let compressed = fs::read(dir.path().join("test.csv.gz")).unwrap();
+        let mut contents = String::new();
+        GzDecoder::new(&compressed[..]).read_to_string(&mut contents).unwrap();
+
+        assert_eq!(contents, "Name,Count\nrow0,0\nrow1,1\nrow2,2\n");
+    }
+
+    #[test]
+    pub fn write_table_without_limit_writes_all_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let configuration = make_configuration(dir.path(), None);
+        write_table(&configuration, "test.csv", &make_table(5)).unwrap();
+
+        let records = read_records(&dir.path().join("test.csv"));
+        assert_eq!(records.len(), 5);
+        assert_eq!(&records[4][0], "row4");
+    }
+
+    #[test]
+    pub fn write_table_under_limit_has_no_marker() {
+        let dir = tempfile::tempdir().unwrap();
+        let configuration = make_configuration(dir.path(), Some(5));
+        write_table(&configuration, "test.csv", &make_table(5)).unwrap();
+
+        let records = read_records(&dir.path().join("test.csv"));
+        assert_eq!(records.len(), 5);
+        assert_eq!(&records[4][0], "row4");
+    }
+
+    #[test]
+    pub fn write_table_over_limit_truncates_and_appends_marker() {
+        let dir = tempfile::tempdir().unwrap();
+        let configuration = make_configuration(dir.path(), Some(3));
+        write_table(&configuration, "test.csv", &make_table(10)).unwrap();
+
+        let records = read_records(&dir.path().join("test.csv"));
+        assert_eq!(records.len(), 4);
+        assert_eq!(&records[0][0], "row0");
+        assert_eq!(&records[2][0], "row2");
+        assert_eq!(&records[3][0], "TRUNCATED: showing 3 of 10 rows");
+        assert_eq!(&records[3][1], "");
+    }
+
+    fn make_matrix_analysis() -> Analysis {
+        let make_project = |path: &str, packages: &[(&str, &str)]| {
+            let mut proj = Project::default();
+            proj.file_info.path = PathBuf::from(path);
+            proj.packages = packages.iter().map(|&(n, v)| Package::new(n, v, false, "")).collect();
+            proj
+        };
+
+        let mut sln = Solution::default();
+        sln.projects = vec![
+            make_project("/temp/app.csproj", &[("Unity", "4.0.1"), ("Newtonsoft.Json", "12.0.1")]),
+            make_project("/temp/lib.csproj", &[("Newtonsoft.Json", "11.0.2")]),
+            make_project("/temp/core.csproj", &[]),
+        ];
+
+        Analysis {
+            solution_directories: vec![SolutionDirectory { solutions: vec![sln], ..Default::default() }],
+            unassociated_projects: vec![make_project("/temp/orphan.csproj", &[("Autofac", "4.9.0")])],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    pub fn write_package_matrix_has_one_row_per_project_and_column_per_package() {
+        let dir = tempfile::tempdir().unwrap();
+        let configuration = make_configuration(dir.path(), None);
+        write_package_matrix(&configuration, &make_matrix_analysis()).unwrap();
+
+        let mut rdr = csv::Reader::from_path(dir.path().join("package_matrix.csv")).unwrap();
+        let header = rdr.headers().unwrap().clone();
+        assert_eq!(header.iter().collect::<Vec<_>>(),
+            vec!["ProjPath", "ProjFile", "Autofac", "Newtonsoft.Json", "Unity"]);
+
+        let records = rdr.records().map(|r| r.unwrap()).collect::<Vec<_>>();
+        assert_eq!(records.len(), 4);
+        assert!(records.iter().all(|r| r.len() == 5));
+
+        let row = |file: &str| records.iter().find(|r| &r[1] == file).unwrap();
+        assert_eq!(&row("app.csproj")[3], "12.0.1");
+        assert_eq!(&row("app.csproj")[4], "4.0.1");
+        assert_eq!(&row("lib.csproj")[3], "11.0.2");
+        assert_eq!(&row("lib.csproj")[4], "");
+        assert_eq!(&row("core.csproj")[2], "");
+        assert_eq!(&row("orphan.csproj")[2], "4.9.0");
+    }
+
+    #[test]
+    pub fn write_package_matrix_caps_package_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut configuration = make_configuration(dir.path(), None);
+        configuration.max_csv_columns = Some(2);
+        write_package_matrix(&configuration, &make_matrix_analysis()).unwrap();
+
+        let mut rdr = csv::Reader::from_path(dir.path().join("package_matrix.csv")).unwrap();
+        assert_eq!(rdr.headers().unwrap().iter().collect::<Vec<_>>(),
+            vec!["ProjPath", "ProjFile", "Autofac", "Newtonsoft.Json"]);
+        assert_eq!(rdr.records().count(), 4);
+    }
+
+    /// Runs every writer that only needs the analysis and returns how many files were written.
+    fn write_all_csv_files(configuration: &Configuration, analysis: &Analysis) -> usize {
+        type Writer = fn(&Configuration, &Analysis) -> AnalysisResult<()>;
+        let writers: &[Writer] = &[
+            write_solutions,
+            write_solutions_to_projects,
+            write_projects_to_packages,
+            write_unreferenced_projects,
+            write_isolated_projects,
+            write_transitively_required_projects,
+            write_output_types,
+            write_test_frameworks,
+            write_namespace_collisions,
+            write_folder_name_mismatches,
+            write_lang_version_issues,
+            write_solution_package_classes,
+            write_package_downgrades,
+            write_package_consolidation,
+            write_package_matrix,
+            write_packages_by_framework,
+            write_low_coverage_solutions,
+            write_missing_projects,
+            write_project_json_projects,
+            write_orphaned_projects,
+            write_case_mismatches,
+            write_shared_output_paths,
+            write_fragile_references,
+            write_analyzer_rules,
+            write_available_updates,
+            write_directory_packages,
+            write_solution_directories,
+            write_group_usage,
+            write_package_shadows,
+            write_diagnostics,
+        ];
+
+        for writer in writers {
+            writer(configuration, analysis).unwrap();
+        }
+
+        let redundant = HashSet::new();
+        write_projects_to_child_projects(configuration, analysis, &redundant).unwrap();
+        write_redundant_references(configuration, analysis, &redundant).unwrap();
+
+        writers.len() + 2
+    }
+
+    fn make_input_directory() -> tempfile::TempDir {
+        let input = crate::test_input_dir();
+        let root = input.path();
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("car.sln"), r#""ford.csproj" "sub\toyota.csproj""#).unwrap();
+        fs::write(root.join("ford.csproj"), r#"<Project Sdk="Microsoft.NET.Sdk">
+            <PackageReference Include="Newtonsoft.Json" Version="12.0.1" />
+            <ProjectReference Include="sub\toyota.csproj" />
+            </Project>"#).unwrap();
+        fs::write(root.join("sub").join("toyota.csproj"), r#"<Project Sdk="Microsoft.NET.Sdk">
+            <LangVersion>8.0</LangVersion>
+            </Project>"#).unwrap();
+        fs::write(root.join("sub").join("packages.config"), "").unwrap();
+        input
+    }
+
+    #[test]
+    pub fn write_solution_directories_aggregates_each_directory() {
+        let input = make_input_directory();
+        let root = input.path();
+        fs::create_dir_all(root.join("other")).unwrap();
+        fs::write(root.join("other").join("bike.sln"), r#""a.csproj""#).unwrap();
+        fs::write(root.join("other").join("bus.sln"), r#""a.csproj""#).unwrap();
+        fs::write(root.join("other").join("a.csproj"), r#"<Project Sdk="Microsoft.NET.Sdk">
+            <PackageReference Include="Newtonsoft.Json" Version="12.0.1" />
+            <PackageReference Include="Serilog" Version="2.10.0" />
+            </Project>"#).unwrap();
+        fs::write(root.join("other").join("stray.csproj"), r#"<Project Sdk="Microsoft.NET.Sdk"></Project>"#).unwrap();
+
+        let output = tempfile::tempdir().unwrap();
+        let mut configuration = make_configuration(output.path(), None);
+        configuration.input_directory = root.to_owned();
+        let analysis = Analysis::new(&configuration).unwrap();
+        write_solution_directories(&configuration, &analysis).unwrap();
+
+        let mut rdr = csv::Reader::from_path(output.path().join("solution_directories.csv")).unwrap();
+        let records = rdr.records().map(|r| r.unwrap()).collect::<Vec<_>>();
+        let counts = records.iter()
+            .map(|r| (Path::new(&r[0]).file_name().unwrap().to_str().unwrap().to_owned(), r[3].to_owned(), r[4].to_owned(), r[5].to_owned(), r[6].to_owned()))
+            .collect::<Vec<_>>();
+        let root_name = root.file_name().unwrap().to_str().unwrap().to_owned();
+        let owned = |s: &str| s.to_owned();
+        assert_eq!(counts, vec![
+            (root_name, owned("1"), owned("2"), owned("0"), owned("1")),
+            (owned("other"), owned("2"), owned("1"), owned("1"), owned("2")),
+        ]);
+    }
+
+    #[test]
+    pub fn excluded_solution_directories_are_absent_from_outputs() {
+        let input = make_input_directory();
+        let root = input.path();
+        fs::create_dir_all(root.join("other")).unwrap();
+        fs::write(root.join("other").join("bike.sln"), r#""a.csproj""#).unwrap();
+        fs::write(root.join("other").join("a.csproj"), r#"<Project Sdk="Microsoft.NET.Sdk"></Project>"#).unwrap();
+
+        let output = tempfile::tempdir().unwrap();
+        let mut configuration = make_configuration(output.path(), None);
+        configuration.input_directory = root.to_owned();
+        let mut analysis = Analysis::new(&configuration).unwrap();
+        assert_eq!(analysis.solution_directories.len(), 2);
+        analysis.exclude_solution_directories(&["other"]);
+        write_solutions(&configuration, &analysis).unwrap();
+        write_solutions_to_projects(&configuration, &analysis).unwrap();
+
+        let solutions = read_records(&output.path().join("solutions.csv"));
+        assert_eq!(solutions.len(), 1);
+        assert!(solutions.iter().all(|r| !r[0].contains("other")));
+
+        let projects = read_records(&output.path().join("solutions_to_projects.csv"));
+        assert!(!projects.is_empty());
+        assert!(projects.iter().all(|r| !r[0].contains("other") && &r[8] != "a.csproj"));
+    }
+
+    #[test]
+    pub fn class_aliases_are_applied_to_package_classes() {
+        let input = make_input_directory();
+        let output = tempfile::tempdir().unwrap();
+        let mut configuration = make_configuration(output.path(), None);
+        configuration.input_directory = input.path().to_owned();
+        configuration.class_aliases.insert("Third Party".to_owned(), "External".to_owned());
+        let analysis = Analysis::new(&configuration).unwrap();
+        write_projects_to_packages(&configuration, &analysis).unwrap();
+        write_group_usage(&configuration, &analysis).unwrap();
+
+        let mut rdr = csv::Reader::from_path(output.path().join("projects_to_packages.csv")).unwrap();
+        let class_idx = rdr.headers().unwrap().iter().position(|h| h == "PkgClass").unwrap();
+        let records = rdr.records().map(|r| r.unwrap()).collect::<Vec<_>>();
+        assert_eq!(records.len(), 1);
+        assert_eq!(&records[0][class_idx], "External");
+
+        let mut rdr = csv::Reader::from_path(output.path().join("group_usage.csv")).unwrap();
+        let group_names = rdr.records().map(|r| r.unwrap()[0].to_owned()).collect::<Vec<_>>();
+        assert!(group_names.contains(&"External".to_owned()));
+        assert!(group_names.contains(&"Microsoft".to_owned()));
+        assert!(!group_names.contains(&"Third Party".to_owned()));
+    }
+
+    /// Writes bike.sln to `dir`, with projects a, b and c where a references b and c
+    /// and b references c. So a -> c is redundant.
+    fn write_bike_solution(dir: &Path) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("bike.sln"), r#""a.csproj" "b.csproj" "c.csproj""#).unwrap();
+        for (name, children) in &[("a", vec!["b", "c"]), ("b", vec!["c"]), ("c", vec![])] {
+            let refs = children.iter()
+                .map(|c| format!(r#"<ProjectReference Include="{}.csproj" />"#, c))
+                .collect::<String>();
+            fs::write(dir.join(format!("{}.csproj", name)),
+                format!(r#"<Project Sdk="Microsoft.NET.Sdk">{}</Project>"#, refs)).unwrap();
+        }
+    }
+
+    #[test]
+    pub fn write_redundant_references_only_writes_reduced_edges() {
+        let input = crate::test_input_dir();
+        let root = input.path();
+        write_bike_solution(root);
+
+        let output = tempfile::tempdir().unwrap();
+        let mut configuration = make_configuration(output.path(), None);
+        configuration.input_directory = root.to_owned();
+        let analysis = Analysis::new(&configuration).unwrap();
+        let mut graph = make_project_graph(&analysis, GraphFlags::PROJECTS, &[]);
+        let removed_edges = graph.transitive_reduction();
+        let redundant = convert_nodes_to_projects(&graph, &removed_edges);
+        write_redundant_references(&configuration, &analysis, &redundant).unwrap();
+
+        let mut rdr = csv::Reader::from_path(output.path().join("redundant_references.csv")).unwrap();
+        let pairs = rdr.records()
+            .map(|r| { let r = r.unwrap(); (r[4].to_owned(), r[6].to_owned()) })
+            .collect::<Vec<_>>();
+        assert_eq!(pairs, vec![("a.csproj".to_owned(), "c.csproj".to_owned())]);
+    }
+
+    fn assert_identical_files(expected_dir: &Path, actual_dir: &Path) -> usize {
+        let mut filenames = fs::read_dir(actual_dir).unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        filenames.sort();
+        for filename in &filenames {
+            let expected = fs::read(expected_dir.join(filename)).unwrap();
+            let actual = fs::read(actual_dir.join(filename)).unwrap();
+            assert_eq!(expected, actual, "{:?} differs", filename);
+        }
+        filenames.len()
+    }
+
+    #[test]
+    pub fn loaded_analysis_writes_identical_csv_files() {
+        let input = make_input_directory();
+        let root = input.path();
+
+        let direct_dir = tempfile::tempdir().unwrap();
+        let mut configuration = make_configuration(direct_dir.path(), None);
+        configuration.input_directory = root.to_owned();
+        let analysis = Analysis::new(&configuration).unwrap();
+        let num_files = write_all_csv_files(&configuration, &analysis);
+
+        let saved = direct_dir.path().join("analysis.json");
+        analysis.save(&saved).unwrap();
+        let loaded = Analysis::load(&saved).unwrap();
+        let loaded_dir = tempfile::tempdir().unwrap();
+        configuration.output_directory = loaded_dir.path().to_owned();
+        write_all_csv_files(&configuration, &loaded);
+
+        assert_eq!(assert_identical_files(direct_dir.path(), loaded_dir.path()), num_files);
+    }
+
+    #[test]
+    pub fn repeated_runs_write_identical_files() {
+        let input = make_input_directory();
+        let root = input.path();
+        write_bike_solution(&root.join("other"));
+
+        let first_dir = tempfile::tempdir().unwrap();
+        let second_dir = tempfile::tempdir().unwrap();
+        let mut configuration = make_configuration(first_dir.path(), None);
+        configuration.input_directory = root.to_owned();
+        configuration.write_dgml = true;
+        crate::run_analysis(&configuration).unwrap();
+        configuration.output_directory = second_dir.path().to_owned();
+        crate::run_analysis(&configuration).unwrap();
+
+        assert!(assert_identical_files(first_dir.path(), second_dir.path()) > 24);
+    }
+
+    #[test]
+    pub fn component_graphs_keep_the_whole_sln_name() {
+        let input = make_input_directory();
+        let root = input.path();
+        fs::write(root.join("Acme.Web.sln"), r#""ford.csproj""#).unwrap();
+        fs::write(root.join("Acme.Api.sln"), r#""sub\toyota.csproj""#).unwrap();
+
+        let output = tempfile::tempdir().unwrap();
+        let mut configuration = make_configuration(output.path(), None);
+        configuration.input_directory = root.to_owned();
+        configuration.write_component_graphs = true;
+        crate::run_analysis(&configuration).unwrap();
+
+        for expected in &["Acme.Web-components.dot", "Acme.Api-components.dot", "car-components.dot"] {
+            assert!(output.path().join(expected).is_file(), "{}", expected);
+        }
+    }
 }
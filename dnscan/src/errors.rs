@@ -2,6 +2,8 @@ use csv;
 use std::error::Error;
 use std::fmt;
 use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Debug)]
 pub enum AnalysisError {
@@ -9,7 +11,10 @@ pub enum AnalysisError {
     DnLib(dnlib::DnLibError),
     Io(io::Error),
     Csv(csv::Error),
+    Zip(zip::result::ZipError),
     // Errors raised by us...
+    Timeout(Duration),
+    CsvHeaderMismatch(PathBuf),
     //Regular(ErrorKind),
     //Custom(String)
 }
@@ -20,6 +25,9 @@ impl Error for AnalysisError {
             AnalysisError::DnLib(ref err) => err.description(),
             AnalysisError::Io(ref err) => err.description(),
             AnalysisError::Csv(ref err) => err.description(),
+            AnalysisError::Zip(ref err) => err.description(),
+            AnalysisError::Timeout(_) => "the analysis timed out",
+            AnalysisError::CsvHeaderMismatch(_) => "the header of an existing CSV file does not match",
         }
     }
 }
@@ -30,6 +38,13 @@ impl fmt::Display for AnalysisError {
             AnalysisError::DnLib(ref err) => err.fmt(f),
             AnalysisError::Io(ref err) => err.fmt(f),
             AnalysisError::Csv(ref err) => err.fmt(f),
+            AnalysisError::Zip(ref err) => err.fmt(f),
+            AnalysisError::Timeout(ref duration) => {
+                write!(f, "The analysis did not complete within {} seconds", duration.as_secs())
+            }
+            AnalysisError::CsvHeaderMismatch(ref path) => {
+                write!(f, "Cannot append to {:?} because its header does not match the new rows", path)
+            }
         }
     }
 }
@@ -52,4 +67,10 @@ impl From<csv::Error> for AnalysisError {
     }
 }
 
+impl From<zip::result::ZipError> for AnalysisError {
+    fn from(err: zip::result::ZipError) -> AnalysisError {
+        AnalysisError::Zip(err)
+    }
+}
+
 pub type AnalysisResult<T> = std::result::Result<T, AnalysisError>;
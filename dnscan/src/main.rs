@@ -1,14 +1,21 @@
+mod benchmark;
 mod csv_output;
 mod errors;
 mod options;
+mod package_query;
+mod stats;
+mod timeout;
+mod tree_output;
+mod validation;
+mod zip_output;
 
 use chrono::{DateTime, Utc};
 use dnlib::prelude::*;
 use logging_timer::{finish, timer, stimer, executing};
 use env_logger::Builder;
 use errors::AnalysisResult;
-use log::{warn, Level};
-use options::Options;
+use log::{info, warn, Level, LevelFilter};
+use options::{Command, Options, OutputFormat};
 use std::io::Write;
 
 fn configure_logging() {
@@ -47,36 +54,176 @@ fn main() {
         std::process::exit(0);
     }
 
-    match options.input_directory.as_ref() {
-        Some(d) => {
-            if !d.exists() || !d.is_dir() {
-                eprintln!("The directory {:?} does not exist or is a file.", d);
+    if options.command == Command::Graph && options.load_analysis.is_none() {
+        eprintln!("The graph command regenerates the graphs from a saved analysis, please specify one with --load");
+        std::process::exit(1);
+    }
+
+    match (options.input_directory.as_ref(), options.load_analysis.as_ref()) {
+        (Some(d), _) => {
+            if !d.exists() || !(d.is_dir() || d.is_sln_file()) {
+                eprintln!("The path {:?} does not exist or is neither a directory nor a sln file.", d);
                 std::process::exit(1);
             }
         }
-        None => {
-            eprintln!("Please specify a DIR to scan");
+        (None, Some(f)) => {
+            if !f.is_file() {
+                eprintln!("The saved analysis {:?} does not exist.", f);
+                std::process::exit(1);
+            }
+        }
+        (None, None) => {
+            eprintln!("Please specify a DIR (or sln file) to scan");
             std::process::exit(1);
         }
     }
 
     let _tmr = stimer!(Level::Info; "Directory Analysis");
-    let dir = options.input_directory.as_ref().unwrap();
+    // When regenerating from a saved analysis without a DIR, the configuration
+    // file is looked for alongside the saved analysis.
+    let dir = match options.input_directory.as_ref() {
+        Some(dir) if dir.is_sln_file() => dir.parent_dir(),
+        Some(dir) => dir.as_path(),
+        None => options.load_analysis.as_ref().unwrap().parent_dir(),
+    };
+    let command = options.command;
     let configuration = Configuration::new(dir);
     let configuration = merge_configuration_and_options(configuration, options);
 
+    // The JSON on stdout is the only output wanted in porcelain mode, which may
+    // have been turned on by the configuration file rather than the command line.
+    if configuration.porcelain {
+        log::set_max_level(LevelFilter::Off);
+    }
+
+    if let Err(e) = configuration.validate() {
+        eprintln!("Invalid configuration: {}", e);
+        std::process::exit(1);
+    }
+
+    if configuration.porcelain {
+        run_porcelain_and_print_result(&configuration);
+    }
+
+    if configuration.validate_only {
+        run_validation_and_print_result(&configuration);
+    }
+
+    if let Some(ref package_name) = configuration.package_query {
+        run_package_query_and_print_result(&configuration, package_name);
+    }
+
+    if command == Command::Stats {
+        run_stats_and_print_result(&configuration);
+    }
+
     run_analysis_and_print_result(&configuration);
 }
 
+/// Prints the lines of a successful result, unless `quiet`, and exits the process
+/// with the result's exit code. An error is printed and exits with 1.
+fn print_lines_and_exit(result: AnalysisResult<(Vec<String>, i32)>, quiet: bool) -> ! {
+    match result {
+        Ok((lines, exit_code)) => {
+            if !quiet {
+                for line in lines {
+                    println!("{}", line);
+                }
+            }
+            std::process::exit(exit_code);
+        }
+        Err(e) => {
+            eprintln!("Error occurred {:#?}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Runs the validation and exits the process with a code that says whether any
+/// problems were found.
+pub fn run_validation_and_print_result(configuration: &Configuration) {
+    let result = validation::validate(configuration)
+        .map(|diagnostics| (
+            validation::summary_lines(&diagnostics),
+            validation::exit_code(&diagnostics, &configuration.fail_on),
+        ));
+    print_lines_and_exit(result, configuration.quiet);
+}
+
+/// Runs the validation, prints its JSON report and exits the process with a code
+/// that says whether any problems were found.
+pub fn run_porcelain_and_print_result(configuration: &Configuration) {
+    let result = validation::porcelain(configuration).map(|(json, exit_code)| (vec![json], exit_code));
+    print_lines_and_exit(result, false);
+}
+
+/// Runs the analysis, prints the projects that use the package and exits the process.
+/// The result is what was asked for, so it is printed even when quiet.
+pub fn run_package_query_and_print_result(configuration: &Configuration, package_name: &str) {
+    let result = package_query::query(configuration, package_name).map(|lines| (lines, 0));
+    print_lines_and_exit(result, false);
+}
+
+/// Runs the analysis, prints its summary and exits the process.
+pub fn run_stats_and_print_result(configuration: &Configuration) {
+    let result = stats::stats(configuration).map(|lines| (lines, 0));
+    print_lines_and_exit(result, configuration.quiet);
+}
+
 pub fn run_analysis_and_print_result(configuration: &Configuration) {
-    if let Err(e) = run_analysis(configuration) {
+    let result = match configuration.timeout_seconds {
+        Some(secs) => {
+            let configuration = configuration.clone();
+            timeout::run_with_timeout(std::time::Duration::from_secs(secs), move || {
+                run_analysis(&configuration)
+            })
+        }
+        None => run_analysis(configuration),
+    };
+
+    if let Err(e) = result {
         eprintln!("Error occurred {:#?}", e);
         std::process::exit(1);
     }
 }
 
 pub fn run_analysis(configuration: &Configuration) -> AnalysisResult<()> {
-    let analysis = Analysis::new(&configuration)?;
+    match configuration.zip_output {
+        Some(ref zip_path) => zip_output::write_outputs_to_zip(configuration, zip_path, analyze_and_write_outputs),
+        None => analyze_and_write_outputs(configuration),
+    }
+}
+
+fn analyze_and_write_outputs(configuration: &Configuration) -> AnalysisResult<()> {
+    let started = std::time::Instant::now();
+
+    // A saved analysis has no reads to record, so there is no manifest for it.
+    let mut manifest = None;
+    let mut analysis = match configuration.load_analysis {
+        Some(ref path) => {
+            info!("Loading analysis from {:?}", path);
+            Analysis::load(path)?
+        }
+        None if configuration.write_manifest => {
+            let file_loader = RecordingFileLoader::new(DiskFileLoader);
+            let analysis = Analysis::new_with_file_loader(configuration, file_loader.clone())?;
+            manifest = Some(file_loader.reads());
+            analysis
+        }
+        None => Analysis::new(configuration)?,
+    };
+
+    if let Some(ref name) = configuration.solution_filter {
+        analysis.retain_solution(name)?;
+    }
+
+    analysis.exclude_solution_directories(&configuration.excluded_solution_directories);
+
+    if let Some(ref path) = configuration.feed_index {
+        let feed_index = FeedIndex::load(path)?;
+        flag_available_updates(&mut analysis, &feed_index);
+    }
+
     if analysis.is_empty() {
         warn!(
             "Did not find any .sln or .csproj files under {}",
@@ -84,6 +231,33 @@ pub fn run_analysis(configuration: &Configuration) -> AnalysisResult<()> {
         );
     }
 
+    if configuration.print_tree {
+        if !configuration.quiet {
+            tree_output::print_tree(&analysis)?;
+        }
+        return Ok(());
+    }
+
+    if configuration.save_analysis {
+        std::fs::create_dir_all(&configuration.output_directory)?;
+        let path = configuration.output_directory.join("analysis.json");
+        analysis.save(&path)?;
+        info!("Wrote {:?}", path);
+    }
+
+    if configuration.write_protobuf {
+        let path = write_analysis_protobuf(&configuration.output_directory, &analysis)?;
+        info!("Wrote {:?}", path);
+    }
+
+    if configuration.dump_paths {
+        csv_output::write_paths(configuration, &analysis.paths_analyzed)?;
+    }
+
+    if let Some(ref reads) = manifest {
+        csv_output::write_manifest(configuration, reads)?;
+    }
+
     let tmr = stimer!("Calculate project graphs and redundant projects");
     let mut individual_graphs = make_project_graphs(&analysis);
     let individual_graphs = individual_graphs
@@ -96,7 +270,7 @@ pub fn run_analysis(configuration: &Configuration) -> AnalysisResult<()> {
 
     executing!(tmr, "Individual graphs done");
 
-    let mut overall_graph = make_project_graph(&analysis, GraphFlags::PROJECTS);
+    let mut overall_graph = make_project_graph(&analysis, GraphFlags::PROJECTS, &[]);
     let removed_edges = overall_graph.transitive_reduction();
     let redundant_projects = convert_nodes_to_projects(&overall_graph, &removed_edges);
     finish!(
@@ -106,33 +280,163 @@ pub fn run_analysis(configuration: &Configuration) -> AnalysisResult<()> {
     );
 
     let _tmr = timer!("Write output files");
-    csv_output::write_solutions(&configuration.output_directory, &analysis)?;
-    csv_output::write_solutions_to_projects(&configuration.output_directory, &analysis)?;
-    csv_output::write_projects_to_packages(&configuration.output_directory, &analysis)?;
-    // We could probably figure out the overall set of redundant projects from the individual graphs,
-    // but this is the way I did it originally, and for now it's good enough.
-    csv_output::write_projects_to_child_projects(
-        &configuration.output_directory,
-        &analysis,
-        &redundant_projects,
-    )?;
-
-    dnlib::graph_output::write_project_dot_file(
-        &configuration,
-        &std::path::PathBuf::from("dnscan.dot"),
-        &overall_graph,
-        &removed_edges,
-    )?;
+    if configuration.write_csv {
+        csv_output::write_solutions(configuration, &analysis)?;
+        csv_output::write_solutions_to_projects(configuration, &analysis)?;
+        csv_output::write_projects_to_packages(configuration, &analysis)?;
+        csv_output::write_package_matrix(configuration, &analysis)?;
+        csv_output::write_packages_by_framework(configuration, &analysis)?;
+        csv_output::write_orphaned_projects(configuration, &analysis)?;
+        csv_output::write_unreferenced_projects(configuration, &analysis)?;
+        csv_output::write_isolated_projects(configuration, &analysis)?;
+        csv_output::write_transitively_required_projects(configuration, &analysis)?;
+        csv_output::write_package_downgrades(configuration, &analysis)?;
+        csv_output::write_package_consolidation(configuration, &analysis)?;
+        csv_output::write_output_types(configuration, &analysis)?;
+        csv_output::write_test_frameworks(configuration, &analysis)?;
+        csv_output::write_namespace_collisions(configuration, &analysis)?;
+        csv_output::write_folder_name_mismatches(configuration, &analysis)?;
+        csv_output::write_lang_version_issues(configuration, &analysis)?;
+        csv_output::write_solution_package_classes(configuration, &analysis)?;
+        csv_output::write_low_coverage_solutions(configuration, &analysis)?;
+        csv_output::write_missing_projects(configuration, &analysis)?;
+        csv_output::write_project_json_projects(configuration, &analysis)?;
+        csv_output::write_case_mismatches(configuration, &analysis)?;
+        csv_output::write_shared_output_paths(configuration, &analysis)?;
+        csv_output::write_fragile_references(configuration, &analysis)?;
+        csv_output::write_analyzer_rules(configuration, &analysis)?;
+        csv_output::write_diagnostics(configuration, &analysis)?;
+        csv_output::write_group_usage(configuration, &analysis)?;
+        csv_output::write_package_shadows(configuration, &analysis)?;
+        if configuration.feed_index.is_some() {
+            csv_output::write_available_updates(configuration, &analysis)?;
+        }
+        csv_output::write_solution_directories(configuration, &analysis)?;
+        if configuration.write_directory_packages {
+            csv_output::write_directory_packages(configuration, &analysis)?;
+        }
+        // We could probably figure out the overall set of redundant projects from the individual graphs,
+        // but this is the way I did it originally, and for now it's good enough.
+        csv_output::write_projects_to_child_projects(
+            configuration,
+            &analysis,
+            &redundant_projects,
+        )?;
+        csv_output::write_redundant_references(configuration, &analysis, &redundant_projects)?;
+    }
 
-    for (sln, graph, removed_edges) in individual_graphs {
+    if configuration.write_dot {
         dnlib::graph_output::write_project_dot_file(
-            &configuration,
-            &std::path::PathBuf::from(sln.file_info.path.file_name().unwrap()),
-            &graph,
+            configuration,
+            &std::path::PathBuf::from("dnscan.dot"),
+            &overall_graph,
+            &removed_edges,
+        )?;
+    }
+
+    if configuration.write_dgml {
+        dnlib::graph_output::write_project_dgml_file(
+            configuration,
+            "dnscan.dgml",
+            &overall_graph,
             &removed_edges,
         )?;
     }
 
+    if configuration.write_package_class_graph {
+        dnlib::graph_output::write_package_class_dot_file(
+            configuration,
+            "dnscan-package-classes",
+            &overall_graph,
+        )?;
+    }
+
+    if configuration.write_package_graph {
+        let package_graph = make_project_graph(
+            &analysis,
+            GraphFlags::PROJECTS | GraphFlags::PACKAGES,
+            &configuration.hidden_package_classes,
+        );
+        dnlib::graph_output::write_project_dot_file(
+            configuration,
+            "dnscan-packages",
+            &package_graph,
+            &std::collections::HashSet::new(),
+        )?;
+    }
+
+    if let Some(ref package_name) = configuration.package_consumers_graph {
+        // The filename is given with its extension, which would otherwise eat
+        // the end of package names such as "Newtonsoft.Json".
+        dnlib::graph_output::write_project_dot_file(
+            configuration,
+            format!("dnscan-consumers-{}.dot", sanitize_file_name(package_name)),
+            &make_package_consumers_graph(&analysis, package_name),
+            &std::collections::HashSet::new(),
+        )?;
+    }
+
+    if configuration.write_adjacency_list {
+        dnlib::graph_output::write_adjacency_list(
+            configuration,
+            "dnscan-adjacency",
+            &overall_graph,
+        )?;
+    }
+
+    for (sln, graph, removed_edges) in individual_graphs {
+        if configuration.write_dot {
+            dnlib::graph_output::write_project_dot_file(
+                &configuration,
+                &std::path::PathBuf::from(sln.file_info.path.file_name().unwrap()),
+                &graph,
+                &removed_edges,
+            )?;
+        }
+
+        if configuration.write_dgml {
+            dnlib::graph_output::write_project_dgml_file(
+                configuration,
+                sln.file_info.path.file_name().unwrap(),
+                graph,
+                &removed_edges,
+            )?;
+        }
+
+        if configuration.write_component_graphs {
+            dnlib::graph_output::write_component_dot_file(
+                configuration,
+                format!("{}-components.dot", sln.file_info.path.file_stem_as_str()),
+                &make_component_graph(sln),
+            )?;
+        }
+    }
+
+    for line in stats::summary_lines(&analysis) {
+        info!("{}", line);
+    }
+
+    if configuration.warn_unclassified {
+        let unclassified = analysis.unclassified_packages(configuration);
+        if !unclassified.is_empty() {
+            warn!(
+                "{} packages are not in any package group and were classed as {:?}: {}",
+                unclassified.len(),
+                configuration.default_package_class,
+                unclassified.join(", ")
+            );
+        }
+    }
+
+    if configuration.benchmark {
+        let num_files = analysis.paths_analyzed.num_files();
+        let elapsed = started.elapsed();
+        info!("Processed {} files in {:?}", num_files, elapsed);
+        if !configuration.quiet {
+            println!("throughput={:.1}", benchmark::throughput(num_files, elapsed));
+        }
+    }
+
     Ok(())
 }
 
@@ -145,11 +449,224 @@ fn merge_configuration_and_options(mut config: Configuration, options: Options)
         config.input_directory = dir;
     }
 
+    if options.max_csv_rows.is_some() {
+        config.max_csv_rows = options.max_csv_rows;
+    }
+
+    if options.max_csv_columns.is_some() {
+        config.max_csv_columns = options.max_csv_columns;
+    }
+
+    if options.timeout_seconds.is_some() {
+        config.timeout_seconds = options.timeout_seconds;
+    }
+
+    if options.solution_threads.is_some() {
+        config.solution_threads = options.solution_threads;
+    }
+
+    if options.project_threads.is_some() {
+        config.project_threads = options.project_threads;
+    }
+
+    if let Some(max_git_operations) = options.max_git_operations {
+        config.max_git_operations = max_git_operations;
+    }
+
+    if options.only_write_changed {
+        config.only_write_changed = true;
+    }
+
+    if options.append_csv {
+        config.append_csv = true;
+    }
+
+    if options.dump_paths {
+        config.dump_paths = true;
+    }
+
+    if options.write_manifest {
+        config.write_manifest = true;
+    }
+
+    if options.benchmark {
+        config.benchmark = true;
+    }
+
+    if options.print_tree {
+        config.print_tree = true;
+    }
+
+    if options.quiet {
+        config.quiet = true;
+    }
+
+    if options.validate_only {
+        config.validate_only = true;
+    }
+
+    if options.porcelain {
+        config.porcelain = true;
+    }
+
+    if let Some(min_severity) = options.min_severity {
+        config.min_severity = min_severity;
+    }
+
+    config.fail_on.extend(options.fail_on);
+
+    if options.solution_filter.is_some() {
+        config.solution_filter = options.solution_filter;
+    }
+
+    if options.package_query.is_some() {
+        config.package_query = options.package_query;
+    }
+
+    if options.graph_title.is_some() {
+        config.graph_title = options.graph_title;
+    }
+
+    if options.warn_unclassified {
+        config.warn_unclassified = true;
+    }
+
+    if let Some(secs) = options.walk_progress_seconds {
+        config.walk_progress_seconds = secs;
+    }
+
+    if options.parallel_walk {
+        config.parallel_walk = true;
+    }
+
+    if options.follow_external_refs {
+        config.follow_external_refs = true;
+    }
+
+    if options.external_refs_ceiling.is_some() {
+        config.external_refs_ceiling = options.external_refs_ceiling;
+    }
+
+    // An explicit list of formats replaces the configured ones, the individual
+    // flags below can still add to it.
+    if let Some(formats) = options.formats {
+        config.write_csv = formats.contains(&OutputFormat::Csv);
+        config.write_dot = formats.contains(&OutputFormat::Dot);
+        config.write_dgml = formats.contains(&OutputFormat::Dgml);
+        config.save_analysis = formats.contains(&OutputFormat::Json);
+    }
+
+    if let Some(min_solution_coverage) = options.min_solution_coverage {
+        config.min_solution_coverage = min_solution_coverage;
+    }
+
+    if let Some(palette) = options.palette {
+        config.palette = palette;
+    }
+
+    if let Some(label_mode) = options.label_mode {
+        config.label_mode = label_mode;
+    }
+
+    if let Some(redundant_edge_style) = options.redundant_edge_style {
+        config.redundant_edge_style = redundant_edge_style;
+    }
+
+    if options.write_dgml {
+        config.write_dgml = true;
+    }
+
+    if options.write_directory_packages {
+        config.write_directory_packages = true;
+    }
+
+    if options.include_modified_times {
+        config.include_modified_times = true;
+    }
+
+    if options.file_uris {
+        config.file_uris = true;
+    }
+
+    if options.suppress_orphan_warnings {
+        config.suppress_orphan_warnings = true;
+    }
+
+    if options.zip_output.is_some() {
+        config.zip_output = options.zip_output;
+    }
+
+    if options.write_component_graphs {
+        config.write_component_graphs = true;
+    }
+
+    if options.write_package_class_graph {
+        config.write_package_class_graph = true;
+    }
+
+    if options.write_package_graph {
+        config.write_package_graph = true;
+    }
+
+    config.hidden_package_classes.extend(options.hidden_package_classes);
+
+    if options.package_consumers_graph.is_some() {
+        config.package_consumers_graph = options.package_consumers_graph;
+    }
+
+    if options.write_adjacency_list {
+        config.write_adjacency_list = true;
+    }
+
+    if options.save_analysis {
+        config.save_analysis = true;
+    }
+
+    if options.write_protobuf {
+        config.write_protobuf = true;
+    }
+
+    if options.load_analysis.is_some() {
+        config.load_analysis = options.load_analysis;
+    }
+
+    if options.feed_index.is_some() {
+        config.feed_index = options.feed_index;
+    }
+
+    config.exclude_files.extend(options.exclude_files);
+    config.excluded_solution_directories.extend(options.excluded_solution_directories);
+
+    if options.gzip_output {
+        config.gzip_output = true;
+    }
+
+    if options.sort_by.is_some() {
+        config.sort_by = options.sort_by;
+        config.sort_descending = options.sort_descending;
+    }
+
+    // Only the graphs are wanted, everything else was written when the analysis was saved.
+    if options.command == Command::Graph {
+        config.write_csv = false;
+        config.save_analysis = false;
+        config.write_protobuf = false;
+        config.dump_paths = false;
+        config.write_manifest = false;
+    }
+
     if config.output_directory.is_relative() {
-        let tmp = config.output_directory;
-        config.output_directory = config.input_directory.clone();
-        config.output_directory.push(tmp);
+        let mut output_directory = config.scan_directory().to_owned();
+        output_directory.push(&config.output_directory);
+        config.output_directory = output_directory;
     }
 
     config
 }
+
+/// Creates a temporary directory for a test to scan. The walker skips hidden
+/// directories, so this avoids tempfile's default ".tmp" prefix.
+#[cfg(test)]
+fn test_input_dir() -> tempfile::TempDir {
+    tempfile::Builder::new().prefix("dnscan-temp-").tempdir().unwrap()
+}
@@ -1,19 +1,154 @@
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use dnlib::prelude::{DiagnosticCategory, LabelMode, Palette, RedundantEdgeStyle, Severity};
+use std::collections::BTreeSet;
+use std::ffi::OsString;
 use std::path::PathBuf;
+use std::str::FromStr;
+
+/// The kinds of output file that can be selected with `--format`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OutputFormat {
+    Csv,
+    Dot,
+    Dgml,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<OutputFormat, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "csv" => Ok(OutputFormat::Csv),
+            "dot" => Ok(OutputFormat::Dot),
+            "dgml" => Ok(OutputFormat::Dgml),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!("unknown format '{}', expected one of csv, dot, dgml, json", s.trim())),
+        }
+    }
+}
+
+/// Parses a comma-separated list of formats, such as "csv,dgml".
+pub fn parse_formats(s: &str) -> Result<BTreeSet<OutputFormat>, String> {
+    s.split(',')
+        .filter(|f| !f.trim().is_empty())
+        .map(OutputFormat::from_str)
+        .collect()
+}
+
+/// The verb given on the command line. `Scan` is assumed when there is none.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Analyzes the directory and writes all the configured outputs.
+    Scan,
+    /// Regenerates the graphs from a saved analysis, without writing anything else.
+    Graph,
+    /// Prints a summary of the analysis without writing any files.
+    Stats,
+}
+
+impl Default for Command {
+    fn default() -> Self {
+        Command::Scan
+    }
+}
 
 #[derive(Debug, Default)]
 /// The command line options.
 pub struct Options {
+    pub command: Command,
     pub dump_example_config: bool,
     pub input_directory: Option<PathBuf>,
     pub output_directory: Option<PathBuf>,
+    pub max_csv_rows: Option<usize>,
+    pub max_csv_columns: Option<usize>,
+    pub sort_by: Option<String>,
+    pub sort_descending: bool,
+    pub timeout_seconds: Option<u64>,
+    pub solution_threads: Option<usize>,
+    pub project_threads: Option<usize>,
+    pub max_git_operations: Option<usize>,
+    pub only_write_changed: bool,
+    pub append_csv: bool,
+    pub dump_paths: bool,
+    pub write_manifest: bool,
+    pub benchmark: bool,
+    pub print_tree: bool,
+    pub quiet: bool,
+    pub validate_only: bool,
+    pub porcelain: bool,
+    pub min_severity: Option<Severity>,
+    pub fail_on: Vec<DiagnosticCategory>,
+    pub solution_filter: Option<String>,
+    pub package_query: Option<String>,
+    pub graph_title: Option<String>,
+    pub warn_unclassified: bool,
+    pub parallel_walk: bool,
+    pub walk_progress_seconds: Option<u64>,
+    pub follow_external_refs: bool,
+    pub external_refs_ceiling: Option<PathBuf>,
+    pub write_dgml: bool,
+    pub gzip_output: bool,
+    pub write_component_graphs: bool,
+    pub write_directory_packages: bool,
+    pub include_modified_times: bool,
+    pub file_uris: bool,
+    pub suppress_orphan_warnings: bool,
+    pub zip_output: Option<PathBuf>,
+    pub exclude_files: Vec<String>,
+    pub excluded_solution_directories: Vec<String>,
+    pub write_package_class_graph: bool,
+    pub write_package_graph: bool,
+    pub hidden_package_classes: Vec<String>,
+    pub package_consumers_graph: Option<String>,
+    pub write_adjacency_list: bool,
+    pub save_analysis: bool,
+    pub write_protobuf: bool,
+    pub load_analysis: Option<PathBuf>,
+    pub feed_index: Option<PathBuf>,
+    pub formats: Option<BTreeSet<OutputFormat>>,
+    pub palette: Option<Palette>,
+    pub label_mode: Option<LabelMode>,
+    pub redundant_edge_style: Option<RedundantEdgeStyle>,
+    pub min_solution_coverage: Option<f64>,
 }
 
 pub fn get_options() -> Options {
-    let matches = App::new("dnscan")
+    parse_options(std::env::args_os())
+}
+
+/// Parses a command line, the first item being the program name. Every verb takes
+/// the same options, and the options can also be given without a verb, which means
+/// `scan`, so command lines from before the verbs existed still work.
+pub fn parse_options<I, T>(args: I) -> Options
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let app = App::new("dnscan")
         .version("0.1")
         .author("Philip Daniels <philip.daniels1971@gmail.com")
         .about("Scans .Net projects and analyzes them")
+        .subcommand(add_args(SubCommand::with_name("scan")
+            .about("Scans the directory and writes all the outputs (the default)")))
+        .subcommand(add_args(SubCommand::with_name("graph")
+            .about("Regenerates the graphs from a saved analysis given with --load, without writing any CSV files")))
+        .subcommand(add_args(SubCommand::with_name("stats")
+            .about("Prints a summary of the analysis without writing any files")));
+
+    let matches = add_args(app).get_matches_from(args);
+
+    match matches.subcommand() {
+        ("scan", Some(sub_matches)) => options_from_matches(Command::Scan, sub_matches),
+        ("graph", Some(sub_matches)) => options_from_matches(Command::Graph, sub_matches),
+        ("stats", Some(sub_matches)) => options_from_matches(Command::Stats, sub_matches),
+        _ => options_from_matches(Command::Scan, &matches),
+    }
+}
+
+/// Adds the options shared by all the verbs.
+fn add_args<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app
         .arg(
             Arg::with_name("dump-example-config")
                 .short("x")
@@ -27,13 +162,344 @@ pub fn get_options() -> Options {
                 .takes_value(true)
                 .help("Specifies the output directory where CSV and graphs will be written. Can be relative or absolute.")
         )
+        .arg(
+            Arg::with_name("max-rows")
+                .long("max-rows")
+                .takes_value(true)
+                .validator(|v| v.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+                .help("Limits each CSV file to this many rows. A final marker row indicates when truncation occurred.")
+        )
+        .arg(
+            Arg::with_name("max-columns")
+                .long("max-columns")
+                .takes_value(true)
+                .validator(|v| v.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+                .help("Limits the package matrix CSV to this many package columns.")
+        )
+        .arg(
+            Arg::with_name("sort-by")
+                .long("sort-by")
+                .takes_value(true)
+                .value_name("column")
+                .help("Sorts the rows of the solutions-to-projects CSV by the named column, e.g. ProjPackagesCount")
+        )
+        .arg(
+            Arg::with_name("sort-desc")
+                .long("sort-desc")
+                .requires("sort-by")
+                .help("Sorts in descending order (use with --sort-by)")
+        )
+        .arg(
+            Arg::with_name("timeout")
+                .long("timeout")
+                .takes_value(true)
+                .value_name("seconds")
+                .validator(|v| v.parse::<u64>().map(|_| ()).map_err(|e| e.to_string()))
+                .help("Abandons the scan, without writing any further output, if it takes longer than this")
+        )
+        .arg(
+            Arg::with_name("solution-threads")
+                .long("solution-threads")
+                .takes_value(true)
+                .value_name("count")
+                .validator(validate_thread_count)
+                .help("The number of threads used to load solution files")
+        )
+        .arg(
+            Arg::with_name("project-threads")
+                .long("project-threads")
+                .takes_value(true)
+                .value_name("count")
+                .validator(validate_thread_count)
+                .help("The number of threads used to load project files")
+        )
+        .arg(
+            Arg::with_name("max-git-operations")
+                .long("max-git-operations")
+                .takes_value(true)
+                .value_name("count")
+                .validator(validate_thread_count)
+                .help("The maximum number of git repositories that are read at the same time")
+        )
+        .arg(
+            Arg::with_name("only-changed")
+                .long("only-changed")
+                .help("Only overwrites a CSV file if its contents have changed since the previous run")
+        )
+        .arg(
+            Arg::with_name("append")
+                .long("append")
+                .help("Appends rows to existing CSV files instead of overwriting them, to aggregate several runs")
+        )
+        .arg(
+            Arg::with_name("dump-paths")
+                .long("dump-paths")
+                .help("Writes the files found by the directory walk, and how they were classified, to paths.csv")
+        )
+        .arg(
+            Arg::with_name("manifest")
+                .long("manifest")
+                .help("Writes every file read during the analysis, and whether it could be read, to manifest.csv")
+        )
+        .arg(
+            Arg::with_name("tree")
+                .long("tree")
+                .help("Prints the solutions, projects and packages found as a tree instead of writing output files")
+        )
+        .arg(
+            Arg::with_name("quiet")
+                .short("q")
+                .long("quiet")
+                .help("Prints nothing on stdout")
+        )
+        .arg(
+            Arg::with_name("solution")
+                .long("solution")
+                .value_name("NAME")
+                .takes_value(true)
+                .help("Restricts the outputs to the solution with this name, with or without the .sln extension")
+        )
+        .arg(
+            Arg::with_name("package")
+                .long("package")
+                .value_name("NAME")
+                .takes_value(true)
+                .conflicts_with("validate-only")
+                .help("Prints every project that uses this package and the version it uses, without writing any output files")
+        )
+        .arg(
+            Arg::with_name("validate-only")
+                .long("validate-only")
+                .help("Runs the analysis and summarises any problems found, without writing any output files. Exits with code 2 if there are problems")
+        )
+        .arg(
+            Arg::with_name("porcelain")
+                .long("porcelain")
+                .conflicts_with_all(&["validate-only", "package", "tree"])
+                .help("Like --validate-only, but prints the counts and diagnostics as a single JSON object and logs nothing. For scripts")
+        )
+        .arg(
+            Arg::with_name("fail-on")
+                .long("fail-on")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("category")
+                .validator(|v| v.parse::<DiagnosticCategory>().map(|_| ()))
+                .help("With --validate-only or --porcelain, only exits with code 2 if there are problems in this category, e.g. dangling-refs, version-conflicts or eol-frameworks (may be repeated)")
+        )
+        .arg(
+            Arg::with_name("benchmark")
+                .long("benchmark")
+                .help("Prints the number of files processed per second at the end of the run")
+        )
+        .arg(
+            Arg::with_name("walk-progress")
+                .long("walk-progress")
+                .takes_value(true)
+                .value_name("seconds")
+                .validator(|v| v.parse::<u64>().map(|_| ()).map_err(|e| e.to_string()))
+                .help("How often to log progress while searching DIR for files, 0 to turn it off (default 10)")
+        )
+        .arg(
+            Arg::with_name("parallel-walk")
+                .long("parallel-walk")
+                .help("Reads the directories under DIR in parallel, which can be faster on slow disks")
+        )
+        .arg(
+            Arg::with_name("follow-external-refs")
+                .long("follow-external-refs")
+                .help("Also analyzes projects outside DIR that are referenced by the solutions and projects inside it")
+        )
+        .arg(
+            Arg::with_name("external-refs-ceiling")
+                .long("external-refs-ceiling")
+                .takes_value(true)
+                .value_name("dir")
+                .requires("follow-external-refs")
+                .help("Limits --follow-external-refs to projects under this directory (relative to the scanned directory)")
+        )
+        .arg(
+            Arg::with_name("warn-unclassified")
+                .long("warn-unclassified")
+                .help("Lists the packages that do not match any package group, so the groups can be extended")
+        )
+        .arg(
+            Arg::with_name("dgml")
+                .long("dgml")
+                .help("Also writes the project graphs as DGML files, which Visual Studio can open")
+        )
+        .arg(
+            Arg::with_name("directory-packages")
+                .long("directory-packages")
+                .help("Also writes the packages used by each solution directory, with usage counts")
+        )
+        .arg(
+            Arg::with_name("modified-times")
+                .long("modified-times")
+                .help("Includes the last modified time of each solution and project file in the CSVs")
+        )
+        .arg(
+            Arg::with_name("file-uris")
+                .long("file-uris")
+                .help("Writes the paths in the CSVs as file:// URIs, for clickable links in editors")
+        )
+        .arg(
+            Arg::with_name("no-orphan-warnings")
+                .long("no-orphan-warnings")
+                .help("Does not warn about projects that are not in any solution")
+        )
+        .arg(
+            Arg::with_name("zip-output")
+                .long("zip-output")
+                .value_name("FILE")
+                .takes_value(true)
+                .help("Writes all the output files into a single zip archive instead of the output directory")
+        )
+        .arg(
+            Arg::with_name("components")
+                .long("components")
+                .help("Also writes a graph of the components (top-level directories) of each solution")
+        )
+        .arg(
+            Arg::with_name("package-classes")
+                .long("package-classes")
+                .help("Also writes a graph coloring projects by the dominant class of their packages")
+        )
+        .arg(
+            Arg::with_name("package-graph")
+                .long("package-graph")
+                .help("Also writes a graph of the projects and the packages they use")
+        )
+        .arg(
+            Arg::with_name("package-consumers-graph")
+                .long("package-consumers-graph")
+                .value_name("NAME")
+                .takes_value(true)
+                .help("Also writes a graph of just the projects that reference this package, for upgrade impact analysis")
+        )
+        .arg(
+            Arg::with_name("hide-package-class")
+                .long("hide-package-class")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("class")
+                .help("Leaves packages of this class, e.g. Microsoft, out of the package graph (may be repeated)")
+        )
+        .arg(
+            Arg::with_name("title")
+                .long("title")
+                .value_name("TITLE")
+                .takes_value(true)
+                .help("The title to show on the graphs instead of the path that was scanned")
+        )
+        .arg(
+            Arg::with_name("adjacency-list")
+                .long("adjacency-list")
+                .help("Also writes the project graph as a text adjacency list, one 'project -> children' line per node")
+        )
+        .arg(
+            Arg::with_name("gzip")
+                .long("gzip")
+                .help("Gzip-compresses all output files, appending .gz to their names")
+        )
+        .arg(
+            Arg::with_name("exclude-file")
+                .long("exclude-file")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("name")
+                .help("Ignores files with this name, e.g. project.json, when looking for files of interest (may be repeated)")
+        )
+        .arg(
+            Arg::with_name("exclude-solution-dir")
+                .long("exclude-solution-dir")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("path-or-glob")
+                .help("Leaves solution directories matching this path or glob out of all the outputs (may be repeated)")
+        )
+        .arg(
+            Arg::with_name("save-analysis")
+                .long("save-analysis")
+                .help("Writes the analysis to analysis.json, for later use with --load")
+        )
+        .arg(
+            Arg::with_name("protobuf")
+                .long("protobuf")
+                .help("Writes the analysis to analysis.pb as a Protocol Buffers message")
+        )
+        .arg(
+            Arg::with_name("load")
+                .long("load")
+                .takes_value(true)
+                .value_name("analysis.json")
+                .conflicts_with("save-analysis")
+                .help("Regenerates the outputs from an analysis saved by --save-analysis instead of scanning")
+        )
+        .arg(
+            Arg::with_name("feed-index")
+                .long("feed-index")
+                .takes_value(true)
+                .value_name("file")
+                .help("A JSON file mapping package names to their latest versions; packages behind them are written to available_updates.csv")
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .value_name("formats")
+                .validator(|v| parse_formats(&v).map(|_| ()))
+                .help("A comma-separated list of the output formats to write: csv, dot, dgml, json (default csv,dot)")
+        )
+        .arg(
+            Arg::with_name("palette")
+                .long("palette")
+                .takes_value(true)
+                .possible_values(&["default", "cud"])
+                .help("The colors used in the DOT files; 'cud' is a color-blind-friendly palette")
+        )
+        .arg(
+            Arg::with_name("label-mode")
+                .long("label-mode")
+                .takes_value(true)
+                .possible_values(&["stem", "relative", "full"])
+                .help("Labels solutions and projects on the graphs with their file stem (the default), path relative to the input directory, or full path")
+        )
+        .arg(
+            Arg::with_name("redundant-edge-style")
+                .long("redundant-edge-style")
+                .takes_value(true)
+                .possible_values(&["hidden", "dashed", "red"])
+                .help("How redundant project references are drawn in the DOT files: left out, dashed, or red (the default)")
+        )
+        .arg(
+            Arg::with_name("min-severity")
+                .long("min-severity")
+                .takes_value(true)
+                .possible_values(&["info", "warning", "error"])
+                .help("Leaves diagnostics less severe than this out of diagnostics.csv and the --validate-only exit code")
+        )
+        .arg(
+            Arg::with_name("min-coverage")
+                .long("min-coverage")
+                .takes_value(true)
+                .value_name("ratio")
+                .validator(validate_ratio)
+                .help("Reports solutions that mention less than this fraction (0 to 1) of the projects in their directory tree")
+        )
         .arg(
             Arg::with_name("input-directory")
-                .help("Specifies the directory to start scanning from")
+                .help("Specifies the directory to start scanning from, or a single sln file to analyze")
         )
-        .get_matches();
+}
 
+fn options_from_matches(command: Command, matches: &ArgMatches) -> Options {
     Options {
+        command,
         dump_example_config: matches.is_present("dump-example-config"),
         input_directory: matches
             .value_of("input-directory")
@@ -43,5 +509,186 @@ pub fn get_options() -> Options {
             .value_of("output-directory")
             .map(|d| Some(PathBuf::from(d)))
             .unwrap_or_default(),
+        max_csv_rows: matches
+            .value_of("max-rows")
+            .map(|n| n.parse().unwrap()),
+        max_csv_columns: matches
+            .value_of("max-columns")
+            .map(|n| n.parse().unwrap()),
+        sort_by: matches.value_of("sort-by").map(|c| c.to_owned()),
+        sort_descending: matches.is_present("sort-desc"),
+        timeout_seconds: matches
+            .value_of("timeout")
+            .map(|n| n.parse().unwrap()),
+        solution_threads: matches
+            .value_of("solution-threads")
+            .map(|n| n.parse().unwrap()),
+        project_threads: matches
+            .value_of("project-threads")
+            .map(|n| n.parse().unwrap()),
+        max_git_operations: matches
+            .value_of("max-git-operations")
+            .map(|n| n.parse().unwrap()),
+        only_write_changed: matches.is_present("only-changed"),
+        append_csv: matches.is_present("append"),
+        dump_paths: matches.is_present("dump-paths"),
+        write_manifest: matches.is_present("manifest"),
+        benchmark: matches.is_present("benchmark"),
+        print_tree: matches.is_present("tree"),
+        quiet: matches.is_present("quiet"),
+        validate_only: matches.is_present("validate-only"),
+        porcelain: matches.is_present("porcelain"),
+        min_severity: matches.value_of("min-severity").map(|s| match s {
+            "warning" => Severity::Warning,
+            "error" => Severity::Error,
+            _ => Severity::Info,
+        }),
+        fail_on: matches
+            .values_of("fail-on")
+            .map(|categories| categories.map(|c| c.parse().unwrap()).collect())
+            .unwrap_or_default(),
+        solution_filter: matches.value_of("solution").map(|s| s.to_owned()),
+        package_query: matches.value_of("package").map(|s| s.to_owned()),
+        graph_title: matches.value_of("title").map(|s| s.to_owned()),
+        warn_unclassified: matches.is_present("warn-unclassified"),
+        parallel_walk: matches.is_present("parallel-walk"),
+        walk_progress_seconds: matches
+            .value_of("walk-progress")
+            .map(|n| n.parse().unwrap()),
+        follow_external_refs: matches.is_present("follow-external-refs"),
+        external_refs_ceiling: matches.value_of("external-refs-ceiling").map(PathBuf::from),
+        write_dgml: matches.is_present("dgml"),
+        gzip_output: matches.is_present("gzip"),
+        write_component_graphs: matches.is_present("components"),
+        write_directory_packages: matches.is_present("directory-packages"),
+        include_modified_times: matches.is_present("modified-times"),
+        file_uris: matches.is_present("file-uris"),
+        suppress_orphan_warnings: matches.is_present("no-orphan-warnings"),
+        zip_output: matches.value_of("zip-output").map(PathBuf::from),
+        write_package_class_graph: matches.is_present("package-classes"),
+        write_package_graph: matches.is_present("package-graph"),
+        hidden_package_classes: matches
+            .values_of("hide-package-class")
+            .map(|classes| classes.map(|c| c.to_owned()).collect())
+            .unwrap_or_default(),
+        package_consumers_graph: matches.value_of("package-consumers-graph").map(|s| s.to_owned()),
+        write_adjacency_list: matches.is_present("adjacency-list"),
+        save_analysis: matches.is_present("save-analysis"),
+        write_protobuf: matches.is_present("protobuf"),
+        load_analysis: matches.value_of("load").map(PathBuf::from),
+        feed_index: matches.value_of("feed-index").map(PathBuf::from),
+        formats: matches.value_of("format").map(|f| parse_formats(f).unwrap()),
+        palette: matches.value_of("palette").map(|p| match p {
+            "cud" => Palette::Cud,
+            _ => Palette::Default,
+        }),
+        label_mode: matches.value_of("label-mode").map(|m| match m {
+            "relative" => LabelMode::Relative,
+            "full" => LabelMode::Full,
+            _ => LabelMode::Stem,
+        }),
+        redundant_edge_style: matches.value_of("redundant-edge-style").map(|s| match s {
+            "hidden" => RedundantEdgeStyle::Hidden,
+            "dashed" => RedundantEdgeStyle::Dashed,
+            _ => RedundantEdgeStyle::Red,
+        }),
+        min_solution_coverage: matches
+            .value_of("min-coverage")
+            .map(|n| n.parse().unwrap()),
+        exclude_files: matches
+            .values_of("exclude-file")
+            .map(|names| names.map(|n| n.to_owned()).collect())
+            .unwrap_or_default(),
+        excluded_solution_directories: matches
+            .values_of("exclude-solution-dir")
+            .map(|dirs| dirs.map(|d| d.to_owned()).collect())
+            .unwrap_or_default(),
+    }
+}
+
+fn validate_thread_count(v: String) -> Result<(), String> {
+    match v.parse::<usize>() {
+        Ok(0) => Err("must be at least 1".to_owned()),
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn validate_ratio(v: String) -> Result<(), String> {
+    match v.parse::<f64>() {
+        Ok(r) if (0.0..=1.0).contains(&r) => Ok(()),
+        Ok(_) => Err("must be between 0 and 1".to_owned()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn parse_formats_works() {
+        let formats = parse_formats("csv,dgml").unwrap();
+        assert_eq!(formats, [OutputFormat::Csv, OutputFormat::Dgml].iter().cloned().collect());
+
+        let formats = parse_formats(" JSON , dot,").unwrap();
+        assert_eq!(formats, [OutputFormat::Dot, OutputFormat::Json].iter().cloned().collect());
+    }
+
+    #[test]
+    pub fn parse_formats_rejects_unknown_formats() {
+        let err = parse_formats("csv,pdf").unwrap_err();
+        assert!(err.contains("pdf"));
+    }
+
+    #[test]
+    pub fn default_formats_are_csv_and_dot() {
+        let options = Options::default();
+        assert!(options.formats.is_none());
+
+        let configuration = dnlib::prelude::Configuration::default();
+        assert!(configuration.write_csv);
+        assert!(configuration.write_dot);
+        assert!(!configuration.write_dgml);
+        assert!(!configuration.save_analysis);
+    }
+
+    #[test]
+    pub fn no_command_means_scan() {
+        let options = parse_options(vec!["dnscan", "/src", "--dgml"]);
+        assert_eq!(options.command, Command::Scan);
+        assert_eq!(options.input_directory, Some(PathBuf::from("/src")));
+        assert!(options.write_dgml);
+    }
+
+    #[test]
+    pub fn scan_command_works() {
+        let options = parse_options(vec!["dnscan", "scan", "/src", "--dgml"]);
+        assert_eq!(options.command, Command::Scan);
+        assert_eq!(options.input_directory, Some(PathBuf::from("/src")));
+        assert!(options.write_dgml);
+    }
+
+    #[test]
+    pub fn graph_command_works() {
+        let options = parse_options(vec!["dnscan", "graph", "--load", "/out/analysis.json"]);
+        assert_eq!(options.command, Command::Graph);
+        assert_eq!(options.load_analysis, Some(PathBuf::from("/out/analysis.json")));
+        assert!(options.input_directory.is_none());
+    }
+
+    #[test]
+    pub fn stats_command_works() {
+        let options = parse_options(vec!["dnscan", "stats", "/src", "--solution", "Core.sln"]);
+        assert_eq!(options.command, Command::Stats);
+        assert_eq!(options.input_directory, Some(PathBuf::from("/src")));
+        assert_eq!(options.solution_filter.as_deref(), Some("Core.sln"));
+    }
+
+    #[test]
+    pub fn a_directory_named_like_a_command_can_be_scanned_with_an_explicit_scan() {
+        let options = parse_options(vec!["dnscan", "scan", "graph"]);
+        assert_eq!(options.command, Command::Scan);
+        assert_eq!(options.input_directory, Some(PathBuf::from("graph")));
     }
 }
@@ -0,0 +1,137 @@
+use crate::errors::AnalysisResult;
+use dnlib::prelude::*;
+
+/// Runs the analysis and reports where the package called `package_name` is used,
+/// but does not write any output files. Used by `--package`, e.g. when planning
+/// an upgrade.
+pub fn query(configuration: &Configuration, package_name: &str) -> AnalysisResult<Vec<String>> {
+    let mut analysis = match configuration.load_analysis {
+        Some(ref path) => Analysis::load(path)?,
+        None => Analysis::new(configuration)?,
+    };
+
+    if let Some(ref name) = configuration.solution_filter {
+        analysis.retain_solution(name)?;
+    }
+
+    analysis.exclude_solution_directories(&configuration.excluded_solution_directories);
+
+    Ok(report_lines(&analysis, package_name))
+}
+
+/// Counts the projects using each version of the package, in version order.
+/// `usages` must already be ordered by version, as `Analysis::package_usages` does.
+pub fn version_histogram<'a>(usages: &[(&Project, &'a Package)]) -> Vec<(&'a str, usize)> {
+    let mut histogram: Vec<(&str, usize)> = vec![];
+    for &(_, pkg) in usages {
+        match histogram.last_mut() {
+            Some((version, count)) if *version == pkg.version => *count += 1,
+            _ => histogram.push((pkg.version.as_str(), 1)),
+        }
+    }
+
+    histogram
+}
+
+/// One line per (version, project) pair, followed by the version histogram and a total.
+pub fn report_lines(analysis: &Analysis, package_name: &str) -> Vec<String> {
+    let usages = analysis.package_usages(package_name);
+    if usages.is_empty() {
+        return vec![format!("No projects use {}", package_name)];
+    }
+
+    let mut lines = usages.iter()
+        .map(|(proj, pkg)| format!("{} {}", pkg.version, proj.file_info.path_as_str()))
+        .collect::<Vec<_>>();
+
+    lines.push(String::new());
+    for (version, count) in version_histogram(&usages) {
+        lines.push(format!("{}: {} projects", version, count));
+    }
+
+    lines.push(format!("{} projects use {}", usages.len(), package_name));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn make_project(path: &str, packages: &[(&str, &str)]) -> Project {
+        let mut proj = Project::default();
+        proj.file_info.path = PathBuf::from(path);
+        proj.packages = packages.iter()
+            .map(|(name, version)| Package::new(*name, *version, false, "Third Party"))
+            .collect();
+        proj
+    }
+
+    fn make_analysis() -> Analysis {
+        let mut sln = Solution::default();
+        sln.projects = vec![
+            make_project("/src/web.csproj", &[("Newtonsoft.Json", "12.0.1"), ("Serilog", "2.10.0")]),
+            make_project("/src/core.csproj", &[("newtonsoft.json", "9.0.1")]),
+            make_project("/src/api.csproj", &[("Newtonsoft.Json", "12.0.1")]),
+        ];
+
+        Analysis {
+            solution_directories: vec![SolutionDirectory { solutions: vec![sln], ..Default::default() }],
+            unassociated_projects: vec![make_project("/src/stray.csproj", &[("Newtonsoft.Json", "10.0.3")])],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    pub fn package_usages_are_sorted_by_version_then_project() {
+        let analysis = make_analysis();
+        let usages = analysis.package_usages("Newtonsoft.Json").into_iter()
+            .map(|(proj, pkg)| (pkg.version.as_str(), proj.file_info.path_as_str()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(usages, vec![
+            ("9.0.1", "/src/core.csproj"),
+            ("10.0.3", "/src/stray.csproj"),
+            ("12.0.1", "/src/api.csproj"),
+            ("12.0.1", "/src/web.csproj"),
+        ]);
+    }
+
+    #[test]
+    pub fn version_histogram_counts_projects_per_version() {
+        let analysis = make_analysis();
+        let usages = analysis.package_usages("Newtonsoft.Json");
+        assert_eq!(version_histogram(&usages), vec![("9.0.1", 1), ("10.0.3", 1), ("12.0.1", 2)]);
+    }
+
+    #[test]
+    pub fn version_histogram_keeps_equal_versions_written_differently_apart() {
+        let mut sln = Solution::default();
+        sln.projects = vec![
+            make_project("/src/a.csproj", &[("Serilog", "1.0")]),
+            make_project("/src/b.csproj", &[("Serilog", "1.0.0")]),
+            make_project("/src/c.csproj", &[("Serilog", "1.0")]),
+            make_project("/src/d.csproj", &[("Serilog", "1.0.0")]),
+        ];
+        let analysis = Analysis {
+            solution_directories: vec![SolutionDirectory { solutions: vec![sln], ..Default::default() }],
+            ..Default::default()
+        };
+
+        let usages = analysis.package_usages("Serilog");
+        assert_eq!(version_histogram(&usages), vec![("1.0", 2), ("1.0.0", 2)]);
+    }
+
+    #[test]
+    pub fn report_lines_works() {
+        let analysis = make_analysis();
+        assert_eq!(report_lines(&analysis, "Serilog"), vec![
+            "2.10.0 /src/web.csproj",
+            "",
+            "2.10.0: 1 projects",
+            "1 projects use Serilog",
+        ]);
+
+        assert_eq!(report_lines(&analysis, "Unity"), vec!["No projects use Unity"]);
+    }
+}
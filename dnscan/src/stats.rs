@@ -0,0 +1,84 @@
+use crate::errors::AnalysisResult;
+use dnlib::prelude::*;
+
+/// Runs the analysis and returns its summary, but does not write any output
+/// files. Used by `dnscan stats`.
+pub fn stats(configuration: &Configuration) -> AnalysisResult<Vec<String>> {
+    let mut analysis = match configuration.load_analysis {
+        Some(ref path) => Analysis::load(path)?,
+        None => Analysis::new(configuration)?,
+    };
+
+    if let Some(ref name) = configuration.solution_filter {
+        analysis.retain_solution(name)?;
+    }
+
+    analysis.exclude_solution_directories(&configuration.excluded_solution_directories);
+
+    Ok(summary_lines(&analysis))
+}
+
+/// The summary of an analysis, one statistic per line. These are also logged
+/// at the end of a full scan.
+pub fn summary_lines(analysis: &Analysis) -> Vec<String> {
+    let output_types = analysis
+        .output_type_histogram()
+        .iter()
+        .map(|(output_type, count)| format!("{}={}", output_type.as_ref(), count))
+        .collect::<Vec<_>>();
+
+    let test_frameworks = analysis
+        .test_framework_histogram()
+        .iter()
+        .map(|(test_framework, count)| format!("{}={}", test_framework.as_ref(), count))
+        .collect::<Vec<_>>();
+
+    vec![
+        format!("Solutions: {}", analysis.num_solutions()),
+        format!("Projects: {}", analysis.projects().count()),
+        format!("Project output types: {}", output_types.join(", ")),
+        format!(
+            "Test frameworks: {} (plus {} non-test projects)",
+            test_frameworks.join(", "),
+            analysis.num_non_test_projects()
+        ),
+        format!("Projects allowing unsafe code: {}", analysis.num_projects_allowing_unsafe_code()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn make_project(path: &str, output_type: OutputType, test_framework: TestFramework) -> Project {
+        let mut proj = Project::default();
+        proj.file_info.path = PathBuf::from(path);
+        proj.output_type = output_type;
+        proj.test_framework = test_framework;
+        proj
+    }
+
+    #[test]
+    pub fn summary_lines_works() {
+        let mut sln = Solution::default();
+        sln.projects = vec![
+            make_project("/src/web.csproj", OutputType::Exe, TestFramework::None),
+            make_project("/src/core.csproj", OutputType::Library, TestFramework::None),
+            make_project("/src/core.tests.csproj", OutputType::Library, TestFramework::XUnit),
+        ];
+
+        let analysis = Analysis {
+            solution_directories: vec![SolutionDirectory { solutions: vec![sln], ..Default::default() }],
+            ..Default::default()
+        };
+
+        assert_eq!(summary_lines(&analysis), vec![
+            "Solutions: 1",
+            "Projects: 3",
+            "Project output types: Library=2, Exe=1",
+            "Test frameworks: XUnit=1 (plus 2 non-test projects)",
+            "Projects allowing unsafe code: 0",
+        ]);
+    }
+}
@@ -0,0 +1,57 @@
+use crate::errors::{AnalysisError, AnalysisResult};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// Runs `f` on a worker thread and waits at most `timeout` for it to finish.
+///
+/// If the timeout expires a `Timeout` error is returned. The worker thread cannot
+/// be cancelled (the walk and the rayon work inside it have no cancellation points)
+/// so it is simply abandoned; callers are expected to exit promptly afterwards,
+/// which stops any further output being written.
+pub fn run_with_timeout<T, F>(timeout: Duration, f: F) -> AnalysisResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> AnalysisResult<T> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        // The receiver may have given up waiting, in which case there is nobody
+        // to tell and the error can be ignored.
+        let _ = tx.send(f());
+    });
+
+    match rx.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(_) => Err(AnalysisError::Timeout(timeout)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn returns_result_of_fast_closure() {
+        let result = run_with_timeout(Duration::from_secs(5), || Ok(42));
+        assert_eq!(result.unwrap(), 42);
+    }
+
+    #[test]
+    pub fn returns_error_of_fast_closure() {
+        let result: AnalysisResult<()> = run_with_timeout(Duration::from_secs(5), || {
+            Err(std::io::Error::from(std::io::ErrorKind::NotFound).into())
+        });
+        assert!(matches!(result, Err(AnalysisError::Io(_))));
+    }
+
+    #[test]
+    pub fn times_out_on_slow_closure() {
+        let result = run_with_timeout(Duration::from_millis(50), || {
+            thread::sleep(Duration::from_secs(5));
+            Ok(())
+        });
+        assert!(matches!(result, Err(AnalysisError::Timeout(d)) if d == Duration::from_millis(50)));
+    }
+}
@@ -0,0 +1,145 @@
+use dnlib::prelude::*;
+use std::io::{self, IsTerminal};
+use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+
+/// The kinds of node in the tree, which determine how each line is colored.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum NodeKind {
+    Root,
+    SolutionDirectory,
+    Solution,
+    Project(ProjectOwnership),
+    Package,
+}
+
+impl NodeKind {
+    fn color(self) -> Option<Color> {
+        match self {
+            NodeKind::Root => Some(Color::White),
+            NodeKind::SolutionDirectory => Some(Color::Cyan),
+            NodeKind::Solution => Some(Color::Green),
+            NodeKind::Project(ProjectOwnership::Orphaned) => Some(Color::Yellow),
+            NodeKind::Project(ProjectOwnership::TransitivelyRequired) => Some(Color::Magenta),
+            NodeKind::Project(_) => None,
+            NodeKind::Package => Some(Color::Blue),
+        }
+    }
+}
+
+/// One line of the tree: how deeply it is indented, and what it says.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TreeLine {
+    depth: usize,
+    kind: NodeKind,
+    text: String,
+}
+
+impl TreeLine {
+    fn new<S: Into<String>>(depth: usize, kind: NodeKind, text: S) -> Self {
+        TreeLine { depth, kind, text: text.into() }
+    }
+}
+
+fn project_line(depth: usize, proj: &Project) -> TreeLine {
+    let marker = match proj.ownership {
+        ProjectOwnership::Orphaned => " [orphaned]",
+        ProjectOwnership::TransitivelyRequired => " [transitively required]",
+        _ => "",
+    };
+
+    TreeLine::new(depth, NodeKind::Project(proj.ownership), format!("{}{}", proj.file_info.filename_as_str(), marker))
+}
+
+fn push_project(lines: &mut Vec<TreeLine>, depth: usize, proj: &Project) {
+    lines.push(project_line(depth, proj));
+    for pkg in &proj.packages {
+        lines.push(TreeLine::new(depth + 1, NodeKind::Package, format!("{} {}", pkg.name, pkg.version)));
+    }
+}
+
+/// Flattens the analysis into the lines of the tree: root, solution directories,
+/// solutions, projects and then packages. Projects that could not be associated
+/// with a solution are listed last.
+fn tree_lines(analysis: &Analysis) -> Vec<TreeLine> {
+    let mut lines = vec![TreeLine::new(0, NodeKind::Root, analysis.root_path.as_str())];
+
+    for sd in &analysis.solution_directories {
+        lines.push(TreeLine::new(1, NodeKind::SolutionDirectory, sd.directory.as_str()));
+        for sln in &sd.solutions {
+            lines.push(TreeLine::new(2, NodeKind::Solution, sln.file_info.filename_as_str()));
+            for proj in &sln.projects {
+                push_project(&mut lines, 3, proj);
+            }
+        }
+    }
+
+    if !analysis.unassociated_projects.is_empty() {
+        lines.push(TreeLine::new(1, NodeKind::SolutionDirectory, "(no solution)"));
+        for proj in &analysis.unassociated_projects {
+            push_project(&mut lines, 2, proj);
+        }
+    }
+
+    lines
+}
+
+/// Writes the tree to `stream`, indenting two spaces per level.
+fn write_tree<W: WriteColor>(stream: &mut W, analysis: &Analysis) -> io::Result<()> {
+    for line in tree_lines(analysis) {
+        write!(stream, "{:indent$}", "", indent = line.depth * 2)?;
+        stream.set_color(ColorSpec::new().set_fg(line.kind.color()))?;
+        write!(stream, "{}", line.text)?;
+        stream.reset()?;
+        writeln!(stream)?;
+    }
+
+    stream.flush()
+}
+
+/// Prints the analysis as a tree on stdout. Colors are only used when stdout
+/// is a terminal, so that the tree can be redirected to a file.
+pub fn print_tree(analysis: &Analysis) -> io::Result<()> {
+    let color_choice = if io::stdout().is_terminal() { ColorChoice::Auto } else { ColorChoice::Never };
+    let stdout = StandardStream::stdout(color_choice);
+    let mut stdoutlock = stdout.lock();
+    write_tree(&mut stdoutlock, analysis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use termcolor::NoColor;
+
+    #[test]
+    pub fn write_tree_renders_the_analysis() {
+        let input = crate::test_input_dir();
+        let root = input.path();
+        fs::write(root.join("car.sln"), r#""ford.csproj""#).unwrap();
+        fs::write(root.join("ford.csproj"), r#"<Project Sdk="Microsoft.NET.Sdk">
+            <PackageReference Include="Newtonsoft.Json" Version="12.0.1" />
+            </Project>"#).unwrap();
+        fs::write(root.join("bmw.csproj"), r#"<Project Sdk="Microsoft.NET.Sdk"></Project>"#).unwrap();
+
+        let configuration = Configuration {
+            input_directory: root.to_owned(),
+            ..Configuration::default()
+        };
+        let analysis = Analysis::new(&configuration).unwrap();
+
+        let mut stream = NoColor::new(vec![]);
+        write_tree(&mut stream, &analysis).unwrap();
+        let output = String::from_utf8(stream.into_inner()).unwrap();
+
+        let root = root.as_str();
+        let expected = vec![
+            root.to_owned(),
+            format!("  {}", root),
+            "    car.sln".to_owned(),
+            "      bmw.csproj [orphaned]".to_owned(),
+            "      ford.csproj".to_owned(),
+            "        Newtonsoft.Json 12.0.1".to_owned(),
+        ];
+        assert_eq!(output.lines().collect::<Vec<_>>(), expected);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::errors::AnalysisResult;
+use dnlib::prelude::*;
+use std::collections::BTreeMap;
+
+/// The exit code used when the validation finds problems. 1 is already used for
+/// errors that stop the analysis from running at all.
+pub const PROBLEMS_FOUND_EXIT_CODE: i32 = 2;
+
+/// Runs the analysis and the diagnostic checks, but does not write any output
+/// files. Used by `--validate-only`, e.g. from a pre-commit hook.
+pub fn validate(configuration: &Configuration) -> AnalysisResult<Vec<Diagnostic>> {
+    let analysis = load_analysis(configuration)?;
+    Ok(filter_by_severity(collect_diagnostics(&analysis), configuration.min_severity))
+}
+
+/// Runs the analysis and the diagnostic checks, and returns the JSON report along
+/// with the exit code, without writing any output files. Used by `--porcelain`.
+pub fn porcelain(configuration: &Configuration) -> AnalysisResult<(String, i32)> {
+    let analysis = load_analysis(configuration)?;
+    let diagnostics = filter_by_severity(collect_diagnostics(&analysis), configuration.min_severity);
+    let json = DiagnosticsReport::new(&analysis, &diagnostics).to_json()?;
+    Ok((json, exit_code(&diagnostics, &configuration.fail_on)))
+}
+
+fn load_analysis(configuration: &Configuration) -> AnalysisResult<Analysis> {
+    let mut analysis = match configuration.load_analysis {
+        Some(ref path) => Analysis::load(path)?,
+        None => Analysis::new(configuration)?,
+    };
+
+    if let Some(ref name) = configuration.solution_filter {
+        analysis.retain_solution(name)?;
+    }
+
+    analysis.exclude_solution_directories(&configuration.excluded_solution_directories);
+    Ok(analysis)
+}
+
+/// Returns the process exit code for the diagnostics: 0 if there are none,
+/// otherwise `PROBLEMS_FOUND_EXIT_CODE`. If `fail_on` is not empty, only
+/// diagnostics in those categories count.
+pub fn exit_code(diagnostics: &[Diagnostic], fail_on: &[DiagnosticCategory]) -> i32 {
+    let problems_found = diagnostics.iter()
+        .any(|d| fail_on.is_empty() || fail_on.contains(&d.category));
+
+    if problems_found { PROBLEMS_FOUND_EXIT_CODE } else { 0 }
+}
+
+/// Summarises the diagnostics as one line per category, e.g.
+/// "OrphanedProject: 3 (Warning)", followed by a total.
+pub fn summary_lines(diagnostics: &[Diagnostic]) -> Vec<String> {
+    let mut counts = BTreeMap::new();
+    for diag in diagnostics {
+        *counts.entry((diag.category, diag.severity)).or_insert(0) += 1;
+    }
+
+    let mut lines = counts.into_iter()
+        .map(|((category, severity), count)| format!("{}: {} ({})", category.as_ref(), count, severity.as_ref()))
+        .collect::<Vec<_>>();
+    lines.push(format!("{} problems found", diagnostics.len()));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    pub fn validate_reports_problems_without_writing_files() {
+        let input = crate::test_input_dir();
+        fs::write(input.path().join("car.sln"), r#""ford.csproj" "missing.csproj""#).unwrap();
+        fs::write(input.path().join("ford.csproj"), r#"<Project Sdk="Microsoft.NET.Sdk"></Project>"#).unwrap();
+        fs::write(input.path().join("stray.csproj"), r#"<Project Sdk="Microsoft.NET.Sdk"></Project>"#).unwrap();
+
+        let output = tempfile::tempdir().unwrap();
+        let configuration = Configuration {
+            input_directory: input.path().to_owned(),
+            output_directory: output.path().join("out"),
+            validate_only: true,
+            ..Configuration::default()
+        };
+
+        let diagnostics = validate(&configuration).unwrap();
+        let categories = diagnostics.iter().map(|d| d.category).collect::<Vec<_>>();
+        assert!(categories.contains(&DiagnosticCategory::OrphanedProject));
+        assert!(categories.contains(&DiagnosticCategory::DanglingReference));
+        assert_eq!(exit_code(&diagnostics, &[]), PROBLEMS_FOUND_EXIT_CODE);
+        assert_eq!(summary_lines(&diagnostics).last().unwrap(), &format!("{} problems found", diagnostics.len()));
+
+        assert!(!configuration.output_directory.exists());
+
+        // The orphan is only a warning, the dangling reference is an error.
+        let configuration = Configuration { min_severity: Severity::Error, ..configuration };
+        let diagnostics = validate(&configuration).unwrap();
+        let categories = diagnostics.iter().map(|d| d.category).collect::<Vec<_>>();
+        assert_eq!(categories, vec![DiagnosticCategory::DanglingReference]);
+        assert_eq!(exit_code(&diagnostics, &[]), PROBLEMS_FOUND_EXIT_CODE);
+    }
+
+    #[test]
+    pub fn porcelain_prints_json_without_writing_files() {
+        let input = crate::test_input_dir();
+        fs::write(input.path().join("car.sln"), r#""ford.csproj" "missing.csproj""#).unwrap();
+        fs::write(input.path().join("ford.csproj"), r#"<Project Sdk="Microsoft.NET.Sdk"></Project>"#).unwrap();
+
+        let output = tempfile::tempdir().unwrap();
+        let configuration = Configuration {
+            input_directory: input.path().to_owned(),
+            output_directory: output.path().join("out"),
+            porcelain: true,
+            ..Configuration::default()
+        };
+
+        let (json, code) = porcelain(&configuration).unwrap();
+        assert!(json.starts_with(r#"{"solutions":1,"projects":1,"problems":1,"counts":{"dangling-refs":1},"diagnostics":["#));
+        assert_eq!(code, PROBLEMS_FOUND_EXIT_CODE);
+        assert!(!configuration.output_directory.exists());
+
+        let configuration = Configuration { fail_on: vec![DiagnosticCategory::EndOfLifeFramework], ..configuration };
+        let (_, code) = porcelain(&configuration).unwrap();
+        assert_eq!(code, 0);
+    }
+
+    #[test]
+    pub fn exit_code_is_zero_when_there_are_no_problems() {
+        assert_eq!(exit_code(&[], &[]), 0);
+        assert_eq!(exit_code(&[], &[DiagnosticCategory::DanglingReference]), 0);
+        assert_eq!(summary_lines(&[]), vec!["0 problems found"]);
+    }
+
+    #[test]
+    pub fn exit_code_only_counts_the_fail_on_categories() {
+        let make = |category| Diagnostic {
+            category,
+            severity: Severity::Warning,
+            solution: None,
+            project: None,
+            line: None,
+            detail: String::new(),
+        };
+        let diagnostics = vec![make(DiagnosticCategory::OrphanedProject), make(DiagnosticCategory::EndOfLifeFramework)];
+
+        assert_eq!(exit_code(&diagnostics, &[DiagnosticCategory::DanglingReference]), 0);
+        assert_eq!(exit_code(&diagnostics, &[DiagnosticCategory::DanglingReference, DiagnosticCategory::EndOfLifeFramework]),
+            PROBLEMS_FOUND_EXIT_CODE);
+        assert_eq!(exit_code(&diagnostics, &[]), PROBLEMS_FOUND_EXIT_CODE);
+    }
+
+    #[test]
+    pub fn fail_on_categories_are_parsed_from_their_cli_names() {
+        assert_eq!("dangling-refs".parse::<DiagnosticCategory>(), Ok(DiagnosticCategory::DanglingReference));
+        assert_eq!("Version-Conflicts".parse::<DiagnosticCategory>(), Ok(DiagnosticCategory::VersionConflict));
+        assert_eq!("eol-frameworks".parse::<DiagnosticCategory>(), Ok(DiagnosticCategory::EndOfLifeFramework));
+        for category in DiagnosticCategory::iter() {
+            assert_eq!(category.cli_name().parse::<DiagnosticCategory>(), Ok(category));
+        }
+
+        let err = "typos".parse::<DiagnosticCategory>().unwrap_err();
+        assert!(err.contains("'typos'"));
+        assert!(err.contains("dangling-refs, version-conflicts, eol-frameworks"));
+    }
+}
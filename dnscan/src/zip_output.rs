@@ -0,0 +1,122 @@
+use crate::errors::AnalysisResult;
+use dnlib::prelude::*;
+use log::info;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Runs `write_outputs` with the output directory redirected to a temporary staging
+/// directory, then bundles everything that was written there into the zip archive
+/// `zip_path`. This means the individual writers do not need to know about zip files.
+pub fn write_outputs_to_zip<F>(configuration: &Configuration, zip_path: &Path, write_outputs: F) -> AnalysisResult<()>
+where F: FnOnce(&Configuration) -> AnalysisResult<()>
+{
+    let staging_dir = tempfile::Builder::new().prefix("dnscan-zip-").tempdir()?;
+    let staged_configuration = Configuration {
+        output_directory: staging_dir.path().to_owned(),
+        ..configuration.clone()
+    };
+
+    let result = write_outputs(&staged_configuration)
+        .and_then(|_| write_zip(staging_dir.path(), zip_path));
+
+    staging_dir.close()?;
+    result
+}
+
+/// Writes every file under `dir` into a new zip archive at `zip_path`. Entries are
+/// named by their path relative to `dir` and are added in sorted order, so that the
+/// archive is deterministic.
+fn write_zip(dir: &Path, zip_path: &Path) -> AnalysisResult<()> {
+    let mut files = vec![];
+    collect_files(dir, &mut files)?;
+    files.sort();
+
+    if let Some(parent) = zip_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut zip = ZipWriter::new(File::create(zip_path)?);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    for path in &files {
+        let name = path.strip_prefix(dir).unwrap_or(path).as_str().replace('\\', "/");
+        zip.start_file(name, options)?;
+        io::copy(&mut File::open(path)?, &mut zip)?;
+    }
+
+    zip.finish()?;
+    info!("Wrote {} files to {:?}", files.len(), zip_path);
+    Ok(())
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn run_analysis_writes_all_outputs_into_zip() {
+        let input = crate::test_input_dir();
+        fs::write(input.path().join("car.sln"), r#""ford.csproj""#).unwrap();
+        fs::write(input.path().join("ford.csproj"), r#"<Project Sdk="Microsoft.NET.Sdk"></Project>"#).unwrap();
+
+        let output = tempfile::tempdir().unwrap();
+        let zip_path = output.path().join("out").join("dnscan.zip");
+        let configuration = Configuration {
+            input_directory: input.path().to_owned(),
+            output_directory: output.path().join("loose"),
+            zip_output: Some(zip_path.clone()),
+            ..Configuration::default()
+        };
+
+        crate::run_analysis(&configuration).unwrap();
+
+        let mut archive = zip::ZipArchive::new(File::open(&zip_path).unwrap()).unwrap();
+        let names = (0..archive.len())
+            .map(|i| archive.by_index(i).unwrap().name().to_owned())
+            .collect::<Vec<_>>();
+        for expected in &["solutions.csv", "solutions_to_projects.csv", "projects_to_packages.csv", "diagnostics.csv", "dnscan.dot"] {
+            assert!(names.iter().any(|n| n == expected), "{} not in {:?}", expected, names);
+        }
+
+        // Nothing is written loose.
+        assert!(!configuration.output_directory.exists());
+    }
+
+    #[test]
+    pub fn write_outputs_to_zip_removes_the_staging_directory() {
+        let output = tempfile::tempdir().unwrap();
+        let zip_path = output.path().join("dnscan.zip");
+        let mut staging_dir = PathBuf::new();
+
+        write_outputs_to_zip(&Configuration::default(), &zip_path, |staged_configuration| {
+            staging_dir = staged_configuration.output_directory.clone();
+            fs::write(staging_dir.join("solutions.csv"), "")?;
+            Ok(())
+        })
+        .unwrap();
+
+        assert!(zip_path.exists());
+        assert!(!staging_dir.as_os_str().is_empty());
+        assert!(!staging_dir.exists());
+    }
+}